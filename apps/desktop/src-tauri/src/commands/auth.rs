@@ -1,20 +1,281 @@
 use tauri::Window;
 use serde::{Deserialize, Serialize};
 use keyring::Entry;
+use crate::error::{CommandError, CommandResult};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct AuthSession {
     pub access_token: String,
     pub user: serde_json::Value,
+    /// When the access token stops being valid (epoch ms)
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+/// Whether a stored token exists and is still within its expiry
+#[derive(Debug, Serialize)]
+pub struct SessionStatus {
+    pub present: bool,
+    /// Epoch ms, if an expiry was recorded with the token
+    pub expires_at: Option<i64>,
+    /// True once `expires_at` has passed; tokens without an expiry never expire
+    pub expired: bool,
 }
 
 const SERVICE_NAME: &str = "dev.deeprecall.desktop";
 
+/// Reserved key holding a JSON list of every key saved through `save_auth_session`
+/// (keyring backends can't enumerate entries)
+const KEY_INDEX: &str = "__keys__";
+
+/// Suffix of the companion entry holding a key's expiry (epoch ms); not listed in the index
+const EXPIRY_SUFFIX: &str = "::expires_at";
+
+fn expiry_key(key: &str) -> String {
+    format!("{}{}", key, EXPIRY_SUFFIX)
+}
+
+/// Serializes read-modify-write cycles on the key index
+static KEY_INDEX_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Where secrets are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SecretBackend {
+    /// OS keychain / secret service
+    Keychain,
+    /// `~/DeepRecall/secrets.enc`, for machines without a usable keychain
+    EncryptedFile,
+}
+
+impl SecretBackend {
+    fn name(self) -> &'static str {
+        match self {
+            SecretBackend::Keychain => "keychain",
+            SecretBackend::EncryptedFile => "encrypted file",
+        }
+    }
+}
+
+/// Backend chosen on first use: the keychain if it answers, the encrypted file otherwise
+static SECRET_BACKEND: std::sync::OnceLock<SecretBackend> = std::sync::OnceLock::new();
+
+/// Serializes read-modify-write cycles on `secrets.enc`
+static SECRET_FILE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+fn secret_backend() -> SecretBackend {
+    *SECRET_BACKEND.get_or_init(|| {
+        // Headless Linux without a secret service fails here rather than on NoEntry
+        let probe = Entry::new(SERVICE_NAME, KEY_INDEX).and_then(|entry| entry.get_password());
+        match probe {
+            Ok(_) | Err(keyring::Error::NoEntry) => {
+                crate::app_log!("Storing secrets in the OS keychain");
+                SecretBackend::Keychain
+            }
+            Err(e) => {
+                crate::log_warn!("OS keychain unavailable ({}), storing secrets in secrets.enc", e);
+                SecretBackend::EncryptedFile
+            }
+        }
+    })
+}
+
+fn keychain_entry(key: &str) -> CommandResult<Entry> {
+    Entry::new(SERVICE_NAME, key)
+        .map_err(|e| CommandError::Keychain(format!("Failed to create keyring entry: {}", e)))
+}
+
+fn get_secrets_file() -> CommandResult<std::path::PathBuf> {
+    let home_dir = dirs::home_dir()
+        .ok_or_else(|| CommandError::Internal("Could not find home directory".to_string()))?;
+    let dir = home_dir.join("DeepRecall");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join("secrets.enc"))
+}
+
+/// Random key for `secrets.enc`, kept next to it in an owner-only `secrets.key`
+///
+/// Generated on first use. Like the secrets file itself, it only protects against
+/// other accounts on this machine, not against someone with access to this one.
+fn secrets_file_key() -> CommandResult<chacha20poly1305::Key> {
+    use chacha20poly1305::aead::{KeyInit, OsRng};
+    use chacha20poly1305::ChaCha20Poly1305;
+    use std::io::Write;
+    
+    let path = get_secrets_file()?.with_file_name("secrets.key");
+    let read_key = |bytes: Vec<u8>| {
+        <[u8; 32]>::try_from(bytes.as_slice())
+            .map(chacha20poly1305::Key::from)
+            .map_err(|_| CommandError::Keychain("secrets.key is corrupt".to_string()))
+    };
+    match std::fs::read(&path) {
+        Ok(bytes) => return read_key(bytes),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    
+    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    
+    match options.open(&path) {
+        Ok(mut file) => {
+            file.write_all(&key)?;
+            file.sync_all()?;
+            Ok(key)
+        }
+        // Another process created it first; use theirs
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => read_key(std::fs::read(&path)?),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Key `secrets.enc` was encrypted with before `secrets.key` existed, derived from the
+/// machine id and user name; only used to read those files until they're rewritten
+fn legacy_secrets_file_key() -> chacha20poly1305::Key {
+    use sha2::{Digest, Sha256};
+    
+    let machine_id = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    let home = dirs::home_dir().unwrap_or_default();
+    
+    let mut hasher = Sha256::new();
+    hasher.update(SERVICE_NAME.as_bytes());
+    hasher.update(machine_id.trim().as_bytes());
+    hasher.update(user.as_bytes());
+    hasher.update(home.to_string_lossy().as_bytes());
+    hasher.finalize()
+}
+
+/// Decrypt `secrets.enc` (nonce || ciphertext of a JSON map); empty if it doesn't exist
+///
+/// Files from before `secrets.key` fall back to the legacy key; the next write
+/// re-encrypts them with the random one.
+fn read_secrets_file() -> CommandResult<std::collections::BTreeMap<String, String>> {
+    use chacha20poly1305::aead::{Aead, KeyInit};
+    use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+    
+    let path = get_secrets_file()?;
+    let data = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(e) => return Err(e.into()),
+    };
+    let (nonce, ciphertext) = data
+        .split_first_chunk::<12>()
+        .ok_or_else(|| CommandError::Keychain("secrets.enc is truncated".to_string()))?;
+    let decrypt = |key: &chacha20poly1305::Key| {
+        ChaCha20Poly1305::new(key).decrypt(&Nonce::from(*nonce), ciphertext)
+    };
+    let plaintext = decrypt(&secrets_file_key()?)
+        .or_else(|_| decrypt(&legacy_secrets_file_key()))
+        .map_err(|_| CommandError::Keychain("Failed to decrypt secrets.enc".to_string()))?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Encrypt and atomically replace `secrets.enc` (owner-only permissions on Unix)
+fn write_secrets_file(secrets: &std::collections::BTreeMap<String, String>) -> CommandResult<()> {
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use chacha20poly1305::ChaCha20Poly1305;
+    use std::io::Write;
+    
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(&secrets_file_key()?)
+        .encrypt(&nonce, serde_json::to_vec(secrets)?.as_slice())
+        .map_err(|_| CommandError::Keychain("Failed to encrypt secrets".to_string()))?;
+    
+    let path = get_secrets_file()?;
+    let tmp = path.with_extension("enc.tmp");
+    {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        
+        let mut file = options.open(&tmp)?;
+        file.write_all(&nonce)?;
+        file.write_all(&ciphertext)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// Read a secret from the active backend
+fn secret_get(key: &str) -> CommandResult<Option<String>> {
+    match secret_backend() {
+        SecretBackend::Keychain => match keychain_entry(key)?.get_password() {
+            Ok(password) => Ok(Some(password)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(CommandError::Keychain(format!("Failed to get from keychain: {}", e))),
+        },
+        SecretBackend::EncryptedFile => {
+            let _guard = SECRET_FILE_LOCK.lock().unwrap();
+            Ok(read_secrets_file()?.remove(key))
+        }
+    }
+}
+
+/// Write a secret to the active backend
+fn secret_set(key: &str, value: &str) -> CommandResult<()> {
+    match secret_backend() {
+        SecretBackend::Keychain => keychain_entry(key)?
+            .set_password(value)
+            .map_err(|e| CommandError::Keychain(format!("Failed to save to keychain: {}", e))),
+        SecretBackend::EncryptedFile => {
+            let _guard = SECRET_FILE_LOCK.lock().unwrap();
+            let mut secrets = read_secrets_file()?;
+            secrets.insert(key.to_string(), value.to_string());
+            write_secrets_file(&secrets)
+        }
+    }
+}
+
+/// Delete a secret from the active backend; a missing entry counts as deleted
+fn delete_entry(key: &str) -> CommandResult<()> {
+    match secret_backend() {
+        SecretBackend::Keychain => match keychain_entry(key)?.delete_credential() {
+            Ok(_) => Ok(()),
+            Err(keyring::Error::NoEntry) => Ok(()), // Already deleted
+            Err(e) => Err(CommandError::Keychain(format!("Failed to delete from keychain: {}", e))),
+        },
+        SecretBackend::EncryptedFile => {
+            let _guard = SECRET_FILE_LOCK.lock().unwrap();
+            let mut secrets = read_secrets_file()?;
+            if secrets.remove(key).is_some() {
+                write_secrets_file(&secrets)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Keys recorded in the index (empty if it doesn't exist yet)
+fn read_key_index() -> CommandResult<Vec<String>> {
+    match secret_get(KEY_INDEX)? {
+        Some(json) => Ok(serde_json::from_str(&json).unwrap_or_else(|e| {
+            crate::app_log!("Key index is corrupt ({}), starting a new one", e);
+            Vec::new()
+        })),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn write_key_index(keys: &[String]) -> CommandResult<()> {
+    secret_set(KEY_INDEX, &serde_json::to_string(keys)?)
+}
+
 /// Open external auth URL in system browser (DEPRECATED - use native OAuth instead)
 /// This function is kept for backwards compatibility but native OAuth flows should be used
 #[tauri::command]
-pub async fn open_auth_window(_window: Window, provider: String) -> Result<(), String> {
+pub async fn open_auth_window(_window: Window, provider: String) -> CommandResult<()> {
     // Auth URL on Railway (production) or localhost:3000 (dev)
     let auth_base = if cfg!(debug_assertions) {
         "http://localhost:3000"
@@ -41,53 +302,131 @@ pub async fn open_auth_window(_window: Window, provider: String) -> Result<(), S
     Ok(())
 }
 
-/// Save a value to OS keychain
+/// Save a value to the OS keychain (or the encrypted file fallback)
+///
+/// `expires_at` (epoch ms) is stored alongside for `get_session_status`; saving without
+/// one clears any previously recorded expiry.
 #[tauri::command]
 pub async fn save_auth_session(
     key: String,
     value: String,
-) -> Result<(), String> {
-    crate::app_log!("Saving {} to keychain", key);
+    expires_at: Option<i64>,
+) -> CommandResult<()> {
+    crate::app_log!("Saving {} to {}", key, secret_backend().name());
     
-    let entry = Entry::new(SERVICE_NAME, &key)
-        .map_err(|e| format!("Failed to create keyring entry: {}", e))?;
+    if key == KEY_INDEX || key.ends_with(EXPIRY_SUFFIX) {
+        return Err(CommandError::InvalidInput(format!("{} is a reserved key", key)));
+    }
     
-    entry.set_password(&value)
-        .map_err(|e| format!("Failed to save to keychain: {}", e))?;
+    secret_set(&key, &value)?;
+    match expires_at {
+        Some(expires_at) => secret_set(&expiry_key(&key), &expires_at.to_string())?,
+        None => delete_entry(&expiry_key(&key))?,
+    }
+    
+    let _guard = KEY_INDEX_LOCK.lock().unwrap();
+    let mut keys = read_key_index()?;
+    if !keys.contains(&key) {
+        keys.push(key);
+        write_key_index(&keys)?;
+    }
     
     Ok(())
 }
 
-/// Get a value from OS keychain
+/// Get a value from the OS keychain (or the encrypted file fallback)
 #[tauri::command]
 pub async fn get_auth_session(
     key: String,
-) -> Result<Option<String>, String> {
-    crate::app_log!("Getting {} from keychain", key);
-    
-    let entry = Entry::new(SERVICE_NAME, &key)
-        .map_err(|e| format!("Failed to create keyring entry: {}", e))?;
+) -> CommandResult<Option<String>> {
+    crate::app_log!("Getting {} from {}", key, secret_backend().name());
     
-    match entry.get_password() {
-        Ok(password) => Ok(Some(password)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(format!("Failed to get from keychain: {}", e)),
-    }
+    secret_get(&key)
 }
 
-/// Delete a value from OS keychain
+/// Delete a value from the OS keychain (or the encrypted file fallback)
 #[tauri::command]
 pub async fn clear_auth_session(
     key: String,
-) -> Result<(), String> {
-    crate::app_log!("Deleting {} from keychain", key);
+) -> CommandResult<()> {
+    crate::app_log!("Deleting {} from {}", key, secret_backend().name());
     
-    let entry = Entry::new(SERVICE_NAME, &key)
-        .map_err(|e| format!("Failed to create keyring entry: {}", e))?;
+    delete_entry(&key)?;
+    delete_entry(&expiry_key(&key))?;
     
-    match entry.delete_credential() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()), // Already deleted
-        Err(e) => Err(format!("Failed to delete from keychain: {}", e)),
+    let _guard = KEY_INDEX_LOCK.lock().unwrap();
+    let mut keys = read_key_index()?;
+    if keys.contains(&key) {
+        keys.retain(|k| k != &key);
+        write_key_index(&keys)?;
     }
+    
+    Ok(())
+}
+
+/// Report whether a token is stored under `key` and whether it has expired
+#[tauri::command]
+pub async fn get_session_status(key: String) -> CommandResult<SessionStatus> {
+    let present = secret_get(&key)?.is_some();
+    let expires_at = if present {
+        secret_get(&expiry_key(&key))?.and_then(|v| v.parse::<i64>().ok())
+    } else {
+        None
+    };
+    let expired = expires_at
+        .map(|t| chrono::Utc::now().timestamp_millis() >= t)
+        .unwrap_or(false);
+    
+    Ok(SessionStatus {
+        present,
+        expires_at,
+        expired,
+    })
+}
+
+/// List the keys saved under the service (from the key index)
+#[tauri::command]
+pub async fn list_auth_keys() -> CommandResult<Vec<String>> {
+    let _guard = KEY_INDEX_LOCK.lock().unwrap();
+    read_key_index()
+}
+
+/// Delete every key in the index ("sign out of everything")
+///
+/// Returns the keys that were removed. Keys that fail to delete stay in the index so a
+/// later call can retry them, and the command reports them as an error.
+#[tauri::command]
+pub async fn clear_all_auth_sessions() -> CommandResult<Vec<String>> {
+    let _guard = KEY_INDEX_LOCK.lock().unwrap();
+    
+    let mut cleared = Vec::new();
+    let mut remaining = Vec::new();
+    for key in read_key_index()? {
+        match delete_entry(&key).and_then(|_| delete_entry(&expiry_key(&key))) {
+            Ok(()) => cleared.push(key),
+            Err(e) => {
+                crate::log_error!("Failed to delete {}: {}", key, e);
+                remaining.push(key);
+            }
+        }
+    }
+    
+    if remaining.is_empty() {
+        delete_entry(KEY_INDEX)?;
+    } else {
+        write_key_index(&remaining)?;
+    }
+    
+    crate::app_log!(
+        "Cleared {} {} entries ({} failed)",
+        cleared.len(), secret_backend().name(), remaining.len()
+    );
+    if !remaining.is_empty() {
+        return Err(CommandError::Keychain(format!(
+            "Failed to delete keys: {}",
+            remaining.join(", ")
+        )));
+    }
+    
+    Ok(cleared)
 }
@@ -2,65 +2,332 @@
  * Avatar management commands
  */
 
-use anyhow::{Context, Result};
+use crate::commands::blobs::{
+    delete_blob_file, get_blob_references, get_blobs_dir, hash_from_file_name, store_blob,
+    store_blob_from_path,
+};
+use crate::db::{
+    get_blob_by_hash, get_connection, get_data_dir, list_avatar_migrations,
+    record_avatar_migration, AvatarMigration,
+};
+use crate::error::{CommandError, CommandResult};
+use crate::{app_log, log_warn};
+use anyhow::Result;
 use base64::{Engine as _, engine::general_purpose};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Default upper bound for an uploaded avatar, after base64 decoding
+const DEFAULT_MAX_AVATAR_BYTES: usize = 10 * 1024 * 1024;
+/// Longest edge of the normalized display avatar
+const DISPLAY_AVATAR_SIZE: u32 = 512;
+/// JPEG quality for the re-encoded original
+const ORIGINAL_AVATAR_QUALITY: u8 = 95;
+/// JPEG quality for the display avatar
+const DISPLAY_AVATAR_QUALITY: u8 = 85;
 
 /// Get avatars directory
 fn get_avatars_dir() -> Result<PathBuf> {
-    let home_dir = dirs::home_dir().context("Failed to get home directory")?;
-    let avatars_dir = home_dir.join("DeepRecall").join("avatars");
+    let avatars_dir = get_data_dir()?.join("avatars");
     fs::create_dir_all(&avatars_dir)?;
     Ok(avatars_dir)
 }
 
+/// Hex SHA-256 of avatar bytes
+fn content_hash(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// All `<author_id>_<kind>_*` files in the avatars directory, sorted by name
+fn avatar_files(avatars_dir: &Path, author_id: &str, kind: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}_{}_", author_id, kind);
+    let Ok(entries) = fs::read_dir(avatars_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(&prefix))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Decode an uploaded avatar, rejecting oversized payloads and anything that isn't an image
+fn decode_avatar(
+    label: &str,
+    encoded: &str,
+    max_bytes: usize,
+) -> CommandResult<image::DynamicImage> {
+    // Check the encoded length first so a huge payload isn't decoded just to be rejected
+    if encoded.len() / 4 * 3 > max_bytes + 3 {
+        return Err(CommandError::InvalidInput(format!(
+            "{} avatar exceeds the {} byte limit",
+            label, max_bytes
+        )));
+    }
+    let data = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| {
+            CommandError::InvalidInput(format!("{} avatar is not valid base64: {}", label, e))
+        })?;
+    if data.len() > max_bytes {
+        return Err(CommandError::InvalidInput(format!(
+            "{} avatar is {} bytes, over the {} byte limit",
+            label, data.len(), max_bytes
+        )));
+    }
+
+    image::load_from_memory(&data)
+        .map_err(|e| {
+            CommandError::InvalidInput(format!("{} avatar is not a valid image: {}", label, e))
+        })
+}
+
+/// Re-encode an avatar as JPEG, which also drops EXIF and other metadata
+fn encode_avatar_jpeg(image: &image::DynamicImage, quality: u8) -> CommandResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, quality)
+        .encode_image(&image.to_rgb8())
+        .map_err(|e| CommandError::Internal(format!("Failed to encode avatar: {}", e)))?;
+    Ok(bytes)
+}
+
 /// Upload avatar (save original and display versions)
+///
+/// Both images must decode as images and stay under `max_size` bytes (default 10 MB).
+/// They are re-encoded as JPEG, which strips EXIF metadata such as GPS location; the
+/// display version is also scaled down to at most 512px on its longest edge.
+///
+/// Both versions go into the content-addressed blob store like any other file, so they
+/// are deduplicated and health-checked with the rest. Returns their `sha256` hashes for
+/// the author record; resolve them with `resolve_blob_path` when displaying. Pass the
+/// previous hashes to `delete_avatar` once the author points at the new ones.
 #[tauri::command]
 pub async fn upload_avatar(
+    app: AppHandle,
     author_id: String,
     original_base64: String,
     display_base64: String,
     crop_region: String,
-) -> Result<serde_json::Value, String> {
-    let avatars_dir = get_avatars_dir().map_err(|e| e.to_string())?;
-
-    // Decode base64
-    let original_data = general_purpose::STANDARD.decode(&original_base64).map_err(|e| e.to_string())?;
-    let display_data = general_purpose::STANDARD.decode(&display_base64).map_err(|e| e.to_string())?;
+    max_size: Option<usize>,
+) -> CommandResult<serde_json::Value> {
+    let max_bytes = max_size.unwrap_or(DEFAULT_MAX_AVATAR_BYTES);
 
-    // Create filenames
-    let timestamp = chrono::Utc::now().timestamp();
-    let original_filename = format!("{}_original_{}.jpg", author_id, timestamp);
-    let display_filename = format!("{}_display_{}.jpg", author_id, timestamp);
+    // Validate and normalize
+    let original = decode_avatar("Original", &original_base64, max_bytes)?;
+    let mut display = decode_avatar("Display", &display_base64, max_bytes)?;
+    if display.width().max(display.height()) > DISPLAY_AVATAR_SIZE {
+        display = display.thumbnail(DISPLAY_AVATAR_SIZE, DISPLAY_AVATAR_SIZE);
+    }
+    let original_data = encode_avatar_jpeg(&original, ORIGINAL_AVATAR_QUALITY)?;
+    let display_data = encode_avatar_jpeg(&display, DISPLAY_AVATAR_QUALITY)?;
 
-    let original_path = avatars_dir.join(&original_filename);
-    let display_path = avatars_dir.join(&display_filename);
+    // Parse crop region before anything is written
+    let crop: serde_json::Value = serde_json::from_str(&crop_region)
+        .map_err(|e| CommandError::InvalidInput(format!("Invalid crop region: {}", e)))?;
 
-    // Write files
-    fs::write(&original_path, original_data).map_err(|e| e.to_string())?;
-    fs::write(&display_path, display_data).map_err(|e| e.to_string())?;
+    let original_blob = store_blob(
+        app.clone(),
+        format!("{}_original.jpg", author_id),
+        original_data,
+        "image/jpeg".to_string(),
+        None,
+    )
+    .await?;
+    let display_blob = store_blob(
+        app,
+        format!("{}_display.jpg", author_id),
+        display_data,
+        "image/jpeg".to_string(),
+        None,
+    )
+    .await?;
 
-    // Parse crop region
-    let crop: serde_json::Value = serde_json::from_str(&crop_region).map_err(|e| e.to_string())?;
+    app_log!(
+        "[Avatars] Stored avatar for {} (original {}, display {})",
+        author_id, original_blob.sha256, display_blob.sha256
+    );
 
     Ok(serde_json::json!({
-        "paths": {
-            "original": original_path.to_string_lossy(),
-            "display": display_path.to_string_lossy()
+        "sha256": {
+            "original": original_blob.sha256,
+            "display": display_blob.sha256
         },
         "cropRegion": crop
     }))
 }
 
-/// Delete avatar file
+/// List an author's legacy avatar files on disk (for debugging leftovers)
 #[tauri::command]
-pub async fn delete_avatar(path: String) -> Result<(), String> {
-    let avatar_path = PathBuf::from(&path);
-    
-    if avatar_path.exists() {
-        fs::remove_file(avatar_path).map_err(|e| e.to_string())?;
+pub async fn list_avatar_versions(author_id: String) -> CommandResult<Vec<String>> {
+    let avatars_dir = get_avatars_dir()?;
+    Ok(["original", "display"]
+        .iter()
+        .flat_map(|kind| avatar_files(&avatars_dir, &author_id, kind))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
+}
+
+/// Blob hash behind an avatar reference: a bare sha256, or a path inside the blob store
+///
+/// Returns `None` for legacy files in the avatars directory.
+pub(crate) fn avatar_blob_hash(reference: &str) -> Result<Option<String>> {
+    if hash_from_file_name(reference) == Some(reference) {
+        return Ok(Some(reference.to_string()));
+    }
+    let path = Path::new(reference);
+    if !path.starts_with(get_blobs_dir()?) {
+        return Ok(None);
     }
-    
+    Ok(path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(hash_from_file_name)
+        .map(str::to_string))
+}
+
+/// A legacy avatar file, confined to the avatars directory
+///
+/// Returns `None` when the file is already gone. Paths that resolve outside the avatars
+/// directory (via `..` or symlinks) are refused, so a reference can't delete other files.
+pub(crate) fn legacy_avatar_file(reference: &str) -> CommandResult<Option<PathBuf>> {
+    let path = match Path::new(reference).canonicalize() {
+        Ok(path) => path,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if !path.starts_with(get_avatars_dir()?.canonicalize()?) {
+        return Err(CommandError::InvalidInput(format!(
+            "{} is not in the avatars directory",
+            reference
+        )));
+    }
+    Ok(Some(path))
+}
+
+/// Drop an avatar an author no longer uses
+///
+/// `reference` is the blob hash stored on the author (or a legacy avatar file path). The
+/// blob is removed from the store and catalog unless something other than `author_id`'s
+/// own avatar still references it in Postgres, e.g. another author with the same image;
+/// it fails with `Offline` rather than guessing when Postgres is unreachable. Legacy paths
+/// outside the avatars directory are refused with `InvalidInput`.
+#[tauri::command]
+pub async fn delete_avatar(author_id: Option<String>, reference: String) -> CommandResult<()> {
+    if let Some(hash) = avatar_blob_hash(&reference)? {
+        let others: Vec<String> = get_blob_references(hash.clone())
+            .await?
+            .into_iter()
+            // Presence rows only say which devices hold the blob
+            .filter(|r| r.table != "device_blobs")
+            .filter(|r| !(r.table == "authors" && Some(&r.id) == author_id.as_ref()))
+            .map(|r| format!("{}/{}", r.table, r.id))
+            .collect();
+        if !others.is_empty() {
+            app_log!(
+                "[Avatars] Keeping avatar blob {}: still referenced by {}",
+                hash,
+                others.join(", ")
+            );
+            return Ok(());
+        }
+
+        return match delete_blob_file(hash.clone(), true, Some(true)).await {
+            Ok(()) => {
+                app_log!("[Avatars] Removed superseded avatar blob {}", hash);
+                Ok(())
+            }
+            Err(CommandError::NotFound(_)) => Ok(()),
+            Err(e) => Err(e),
+        };
+    }
+
+    if let Some(avatar_path) = legacy_avatar_file(&reference)? {
+        fs::remove_file(avatar_path)?;
+    }
+
     Ok(())
 }
+
+/// Import legacy avatar files into the content-addressed blob store
+///
+/// Each `*.jpg` in the avatars directory is stored as a blob and the old path -> sha256
+/// mapping is recorded in the catalog, so re-running skips files already migrated. With
+/// `delete_originals`, a file is removed only after its blob has been re-read and its hash
+/// confirmed. Returns every recorded mapping so the frontend can point authors at hashes.
+#[tauri::command]
+pub async fn migrate_avatars_to_blobs(
+    app: AppHandle,
+    delete_originals: Option<bool>,
+) -> CommandResult<Vec<AvatarMigration>> {
+    let avatars_dir = get_avatars_dir()?;
+    let conn = get_connection()?;
+    let already_migrated: HashSet<String> = list_avatar_migrations(&conn)?
+        .into_iter()
+        .map(|m| m.old_path)
+        .collect();
+
+    let mut legacy_files: Vec<PathBuf> = fs::read_dir(&avatars_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "jpg"))
+        .collect();
+    legacy_files.sort();
+
+    let mut imported = 0;
+    let mut deleted = 0;
+    for path in legacy_files {
+        let old_path = path.to_string_lossy().to_string();
+        let hash = content_hash(&fs::read(&path)?);
+
+        if !already_migrated.contains(&old_path) {
+            let stored = store_blob_from_path(
+                app.clone(),
+                old_path.clone(),
+                None,
+                Some("image/jpeg".to_string()),
+                Some(false),
+            )
+            .await?;
+            if stored.sha256 != hash {
+                return Err(CommandError::Io(format!(
+                    "Avatar {} changed while it was being migrated",
+                    old_path
+                )));
+            }
+            record_avatar_migration(&conn, &old_path, &hash)?;
+            imported += 1;
+        }
+
+        if delete_originals.unwrap_or(false) {
+            let verified = get_blob_by_hash(&conn, &hash)?
+                .and_then(|blob| blob.path)
+                .is_some_and(|blob_path| {
+                    fs::read(blob_path).is_ok_and(|data| content_hash(&data) == hash)
+                });
+            if verified {
+                fs::remove_file(&path)?;
+                deleted += 1;
+            } else {
+                log_warn!(
+                    "[Avatars] Keeping {}: stored blob {} failed verification",
+                    old_path, hash
+                );
+            }
+        }
+    }
+
+    app_log!(
+        "[Avatars] Migrated {} avatars to blob storage ({} originals deleted)",
+        imported, deleted
+    );
+    Ok(list_avatar_migrations(&conn)?)
+}
@@ -3,23 +3,226 @@
  */
 
 use crate::db::{
-    delete_blob as db_delete_blob, get_blob_by_hash, get_connection, get_stats, insert_blob,
-    insert_path, list_all_blobs, update_filename, BlobInfo, BlobWithMetadata, HealthReport,
-    ScanResult,
+    catalog_file_size, configured_data_dir, data_dir_config_path, delete_blob as db_delete_blob, delete_path,
+    delete_paths_for_hash, delete_setting, ensure_writable_dir, get_blob_by_hash, get_connection,
+    get_data_dir, get_line_count, get_paths_for_hash, get_setting, get_stats, get_stats_by_mime,
+    insert_blob, insert_path, list_all_blobs, list_all_blobs_paged, set_declared_mime,
+    set_image_dimensions, set_line_count, set_page_count, set_setting, update_filename,
+    update_health, update_path, BlobAction, BlobCategory, BlobClass, BlobInfo, BlobManifestEntry,
+    BlobPage, BlobReference, BlobSortKey, BlobWithMetadata, DedupReport, DuplicateGroup, GcReport,
+    HealthReport, ImportResult, IntegrityProgress, IntegrityReport, ReindexReport,
+    RelocationReport, ReshardResult, ScanResult, StoreBlobResult, VacuumResult, VerifyResult,
+    ZipExportResult, CATALOG_LOCK, DATA_DIR_ENV,
 };
-use anyhow::{Context, Result};
+use crate::commands::database::get_pg_client;
+use crate::error::{CommandError, CommandResult};
+use crate::{app_log, log_debug, log_warn};
+use tauri::{AppHandle, Emitter};
+use anyhow::Result;
+use rusqlite::Connection;
 use sha2::{Digest, Sha256};
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 /// Get the blob storage directory path
-fn get_blobs_dir() -> Result<PathBuf> {
-    let home_dir = dirs::home_dir().context("Failed to get home directory")?;
-    let blobs_dir = home_dir.join("DeepRecall").join("blobs");
+pub(crate) fn get_blobs_dir() -> Result<PathBuf> {
+    let blobs_dir = get_data_dir()?.join("blobs");
     fs::create_dir_all(&blobs_dir)?;
     Ok(blobs_dir)
 }
 
+/// Settings key for the number of 2-hex-char subdirectory levels
+const SHARD_DEPTH_KEY: &str = "blob_shard_depth";
+
+/// Get the configured sharding depth (1 = `ab/<hash>`, 2 = `ab/cd/<hash>`)
+fn get_shard_depth(conn: &Connection) -> Result<usize> {
+    let depth = get_setting(conn, SHARD_DEPTH_KEY)?
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|d| (1..=2).contains(d))
+        .unwrap_or(1);
+    Ok(depth)
+}
+
+/// Get the shard directory a blob lives in for the given depth
+fn shard_dir(blobs_dir: &Path, hash: &str, depth: usize) -> PathBuf {
+    let mut dir = blobs_dir.to_path_buf();
+    for level in 0..depth {
+        dir = dir.join(&hash[level * 2..level * 2 + 2]);
+    }
+    dir
+}
+
+/// Extract the hash from an on-disk blob name (`<hash>` or `<hash>.<ext>`)
+///
+/// In-progress `<hash>.tmp` files from atomic writes are not blobs and yield `None`.
+pub(crate) fn hash_from_file_name(file_name: &str) -> Option<&str> {
+    if file_name.ends_with(".tmp") {
+        return None;
+    }
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    if stem.len() == 64 && stem.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(stem)
+    } else {
+        None
+    }
+}
+
+/// Compute the SHA-256 of a file by streaming it from disk
+fn hash_file(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Put a blob at its content-addressed path without ever exposing a torn file
+///
+/// `fill` creates `<hash>.tmp` next to the target and may return the hash of what it
+/// wrote; otherwise the result is re-hashed. Only a matching file is renamed into place.
+fn place_blob_atomically(
+    target: &Path,
+    expected_hash: &str,
+    fill: impl FnOnce(&Path) -> Result<Option<String>>,
+) -> Result<()> {
+    let tmp_path = target.with_file_name(format!("{}.tmp", expected_hash));
+
+    let write_result = (|| -> Result<()> {
+        let written_hash = match fill(&tmp_path)? {
+            Some(hash) => hash,
+            None => hash_file(&tmp_path)?,
+        };
+        if written_hash != expected_hash {
+            anyhow::bail!(
+                "Hash mismatch after write: expected {}, got {}",
+                expected_hash,
+                written_hash
+            );
+        }
+
+        fs::rename(&tmp_path, target)?;
+        Ok(())
+    })();
+
+    if write_result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    write_result
+}
+
+/// Write blob bytes to their content-addressed path, fsyncing before the rename
+fn write_blob_atomically(target: &Path, data: &[u8], expected_hash: &str) -> Result<()> {
+    place_blob_atomically(target, expected_hash, |tmp_path| {
+        let mut file = fs::File::create(tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        Ok(None)
+    })
+}
+
+/// Bytes read per step when copying a blob; the most ever held in memory at once
+const COPY_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Copy `source` to `dest` in fixed-size chunks, hashing each chunk as it is written
+fn copy_and_hash(source: &Path, dest: &Path) -> Result<String> {
+    use std::io::Read;
+
+    let mut reader = fs::File::open(source)?;
+    let mut writer = fs::File::create(dest)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; COPY_CHUNK_SIZE];
+
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n])?;
+    }
+
+    writer.sync_all()?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Stream-copy a file to its content-addressed path, fsyncing before the rename
+///
+/// The copy is hashed in the same pass, so the file is never read back just to verify it.
+fn copy_blob_atomically(source: &Path, target: &Path, expected_hash: &str) -> Result<()> {
+    place_blob_atomically(target, expected_hash, |tmp_path| {
+        copy_and_hash(source, tmp_path).map(Some)
+    })
+}
+
+/// Hard-link a file to its content-addressed path (same filesystem only)
+fn link_blob_atomically(source: &Path, target: &Path, expected_hash: &str) -> Result<()> {
+    place_blob_atomically(target, expected_hash, |tmp_path| {
+        fs::hard_link(source, tmp_path)?;
+        Ok(None)
+    })
+}
+
+/// Settings key for the JSON list of glob patterns `scan_blobs` skips
+const SCAN_IGNORE_KEY: &str = "scan_ignore_patterns";
+
+/// Patterns ignored when no list has been configured
+const DEFAULT_SCAN_IGNORE_PATTERNS: &[&str] = &["*.tmp", "thumbnails"];
+
+/// Get the configured scan ignore patterns (defaults when unset or unreadable)
+fn get_scan_ignore_patterns(conn: &Connection) -> Result<Vec<String>> {
+    let patterns = get_setting(conn, SCAN_IGNORE_KEY)?
+        .and_then(|v| serde_json::from_str::<Vec<String>>(&v).ok())
+        .unwrap_or_else(|| {
+            DEFAULT_SCAN_IGNORE_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect()
+        });
+    Ok(patterns)
+}
+
+/// Compile ignore patterns, skipping (and logging) invalid ones
+fn compile_ignore_patterns(patterns: &[String]) -> Vec<glob::Pattern> {
+    patterns
+        .iter()
+        .filter_map(|p| match glob::Pattern::new(p) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                log_warn!("[Blobs] Ignoring invalid scan pattern '{}': {}", p, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Check whether a path under the blobs dir matches an ignore pattern
+///
+/// Patterns are matched against both the relative path (`/`-separated) and the bare name,
+/// so `thumbnails` prunes that directory and `*.tmp` skips temp files at any depth.
+fn is_ignored(blobs_dir: &Path, path: &Path, patterns: &[glob::Pattern]) -> bool {
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    let relative = path
+        .strip_prefix(blobs_dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    patterns
+        .iter()
+        .any(|p| p.matches_with(&relative, options) || p.matches_with(&name, options))
+}
+
 /// Get MIME type from file extension
 fn get_mime_type(path: &Path) -> String {
     mime_guess::from_path(path)
@@ -27,263 +230,2828 @@ fn get_mime_type(path: &Path) -> String {
         .to_string()
 }
 
-/// List all blobs with metadata
-#[tauri::command]
-pub async fn list_blobs(orphaned_only: bool) -> Result<Vec<BlobWithMetadata>, String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
-    let blobs = list_all_blobs(&conn).map_err(|e| e.to_string())?;
+/// Whether a caller-supplied MIME carries no real information
+fn is_generic_mime(mime: &str) -> bool {
+    mime.is_empty() || mime == "application/octet-stream"
+}
+
+/// Pick the MIME to catalog given the declared type and the type sniffed from content
+///
+/// The sniffed type wins when the declared one is generic or names a different
+/// top-level type (e.g. `text/plain` for a PDF). Returns the authoritative MIME and,
+/// when it was overridden, the declared one.
+fn resolve_mime(declared: &str, sniffed: Option<&str>) -> (String, Option<String>) {
+    let top_level = |mime: &str| mime.split('/').next().unwrap_or("").to_ascii_lowercase();
+
+    match sniffed {
+        Some(detected) if is_generic_mime(declared) || top_level(declared) != top_level(detected) => {
+            let declared = (!declared.is_empty()).then(|| declared.to_string());
+            (detected.to_string(), declared)
+        }
+        _ if declared.is_empty() => ("application/octet-stream".to_string(), None),
+        _ => (declared.to_string(), None),
+    }
+}
+
+/// Extensions treated as source code / plain text, regardless of the stored MIME
+const CODE_EXTENSIONS: &[&str] = &[
+    "rs", "ts", "tsx", "js", "jsx", "py", "c", "h", "cpp", "hpp", "java", "go", "rb", "sh",
+    "toml", "yaml", "yml", "json", "xml", "html", "css", "sql", "md", "tex", "bib", "txt",
+];
+
+/// Extensions of common archive formats
+const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "tar", "gz", "tgz", "bz2", "xz", "7z", "rar", "zst"];
+
+/// Image types the webview can render inline
+const PREVIEWABLE_IMAGES: &[&str] = &[
+    "image/png", "image/jpeg", "image/gif", "image/webp", "image/svg+xml", "image/bmp",
+];
+
+/// Audio/video containers the webview can play natively
+const PREVIEWABLE_MEDIA: &[&str] = &[
+    "audio/mpeg", "audio/wav", "audio/ogg", "audio/webm", "audio/aac", "audio/flac",
+    "video/mp4", "video/webm", "video/ogg",
+];
+
+/// Classify a blob into a UI category from its MIME type and filename extension
+fn classify(mime: &str, filename: Option<&str>) -> BlobClass {
+    let mime = mime.to_ascii_lowercase();
+    let extension = filename
+        .and_then(|f| Path::new(f).extension())
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    let ext = extension.as_str();
+
+    let category = if mime == "application/pdf" || ext == "pdf" || ext == "epub" || ext == "djvu" {
+        BlobCategory::Document
+    } else if mime.starts_with("image/") {
+        BlobCategory::Image
+    } else if mime.starts_with("audio/") {
+        BlobCategory::Audio
+    } else if mime.starts_with("video/") {
+        BlobCategory::Video
+    } else if ARCHIVE_EXTENSIONS.contains(&ext)
+        || mime == "application/zip"
+        || mime == "application/gzip"
+        || mime == "application/x-tar"
+        || mime == "application/x-7z-compressed"
+    {
+        BlobCategory::Archive
+    } else if mime.starts_with("text/") || mime == "application/json" || CODE_EXTENSIONS.contains(&ext) {
+        BlobCategory::Code
+    } else if mime.contains("word") || mime.contains("opendocument") || mime.contains("presentation") {
+        BlobCategory::Document
+    } else {
+        BlobCategory::Other
+    };
+
+    let (previewable, default_action) = match category {
+        BlobCategory::Document => {
+            let is_pdf = mime == "application/pdf" || ext == "pdf";
+            (
+                is_pdf,
+                if is_pdf { BlobAction::OpenReader } else { BlobAction::OpenExternal },
+            )
+        }
+        BlobCategory::Image => {
+            let ok = PREVIEWABLE_IMAGES.contains(&mime.as_str());
+            (ok, if ok { BlobAction::ViewImage } else { BlobAction::OpenExternal })
+        }
+        BlobCategory::Audio | BlobCategory::Video => {
+            let ok = PREVIEWABLE_MEDIA.contains(&mime.as_str());
+            (ok, if ok { BlobAction::PlayMedia } else { BlobAction::OpenExternal })
+        }
+        BlobCategory::Code => (true, BlobAction::ViewText),
+        BlobCategory::Archive | BlobCategory::Other => (false, BlobAction::OpenExternal),
+    };
+
+    BlobClass {
+        category,
+        previewable,
+        default_action,
+    }
+}
+
+/// Classify a blob for icon selection and preview support
+#[tauri::command]
+pub async fn classify_blob(sha256: String) -> CommandResult<BlobClass> {
+    let conn = get_connection()?;
+    let blob = get_blob_by_hash(&conn, &sha256)?
+        .ok_or_else(|| CommandError::NotFound(format!("Blob not found: {}", sha256)))?;
+
+    Ok(classify(&blob.mime, blob.filename.as_deref()))
+}
+
+/// List blobs with metadata, a page at a time
+///
+/// With `orphaned_only`, only blobs nothing in Postgres references (assets, device_blobs,
+/// annotations, cards or author avatars) are returned. That needs Postgres, so it fails with `Offline`
+/// rather than guessing when the database is unreachable.
+///
+/// `mime_prefix` filters by type in SQL: `"image/"` matches every image type, while a full
+/// type such as `"application/pdf"` matches exactly.
+///
+/// Blobs are ordered by `sort` (default `created_ms`, ascending unless `descending`) and
+/// `limit`/`offset` select the page; without `limit` every match is returned. `total`
+/// counts all matches, so the frontend can size its pager.
+#[tauri::command]
+pub async fn list_blobs(
+    orphaned_only: bool,
+    mime_prefix: Option<String>,
+    sort: Option<BlobSortKey>,
+    descending: Option<bool>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> CommandResult<BlobPage> {
+    let mime_prefix = mime_prefix.filter(|p| !p.is_empty());
+    let sort = sort.unwrap_or_default();
+    let descending = descending.unwrap_or(false);
+    if limit.is_some_and(|l| l < 0) || offset.is_some_and(|o| o < 0) {
+        return Err(CommandError::InvalidInput(
+            "limit and offset must not be negative".to_string(),
+        ));
+    }
+    let offset = offset.unwrap_or(0);
+
+    if !orphaned_only {
+        let conn = get_connection()?;
+        let (blobs, total) =
+            list_all_blobs_paged(&conn, mime_prefix.as_deref(), sort, descending, limit, offset)?;
+        return Ok(BlobPage { blobs, total });
+    }
+
+    let client = get_pg_client().await?;
+    let referenced = referenced_hashes(&client).await?;
+
+    // Orphans are only known after the Postgres check, so page in memory
+    let conn = get_connection()?;
+    let (all, _) =
+        list_all_blobs_paged(&conn, mime_prefix.as_deref(), sort, descending, None, 0)?;
+    let orphans: Vec<BlobWithMetadata> = all
+        .into_iter()
+        .filter(|blob| !referenced.contains(&blob.sha256))
+        .collect();
+    let total = orphans.len() as i64;
+    let blobs = orphans
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit.map_or(usize::MAX, |l| l as usize))
+        .collect();
+    Ok(BlobPage { blobs, total })
+}
+
+/// Default page size for `search_blobs`
+const DEFAULT_SEARCH_LIMIT: i64 = 50;
+
+/// Search blobs by filename, best matches first
+///
+/// Every word in `query` must match the start of a word in the filename, so "rep"
+/// finds "report.pdf".
+#[tauri::command]
+pub async fn search_blobs(
+    query: String,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> CommandResult<Vec<BlobWithMetadata>> {
+    let conn = get_connection()?;
+    Ok(crate::db::search_blobs(
+        &conn,
+        &query,
+        limit.unwrap_or(DEFAULT_SEARCH_LIMIT).max(0),
+        offset.unwrap_or(0).max(0),
+    )?)
+}
+
+/// Catalog-only manifest of every blob (hash, size, mime, health) for sync reconciliation
+///
+/// Never touches blob files, so it's cheap enough to call before every comparison.
+#[tauri::command]
+pub async fn export_blob_manifest() -> CommandResult<Vec<BlobManifestEntry>> {
+    let conn = get_connection()?;
+    Ok(crate::db::blob_manifest(&conn)?)
+}
+
+/// List only the blobs with a given health status ("healthy", "missing", "modified", "relocated")
+#[tauri::command]
+pub async fn list_blobs_by_health(status: String) -> CommandResult<Vec<BlobWithMetadata>> {
+    if !crate::db::HEALTH_STATUSES.contains(&status.as_str()) {
+        return Err(CommandError::InvalidInput(format!(
+            "Unknown health status '{}' (expected one of: {})",
+            status,
+            crate::db::HEALTH_STATUSES.join(", ")
+        )));
+    }
+
+    let conn = get_connection()?;
+    Ok(crate::db::list_blobs_by_health(&conn, &status)?)
+}
+
+/// Get blob metadata by SHA-256 hash
+#[tauri::command]
+pub async fn stat_blob(sha256: String) -> CommandResult<Option<BlobInfo>> {
+    let conn = get_connection()?;
+    Ok(get_blob_by_hash(&conn, &sha256)?)
+}
+
+/// Store a new blob
+///
+/// With `verify`, the written file is re-read and checked against its hash and catalog
+/// row before returning; see `verify_stored_blob`.
+#[tauri::command]
+pub async fn store_blob(
+    app: AppHandle,
+    filename: String,
+    data: Vec<u8>,
+    mime: String,
+    verify: Option<bool>,
+) -> CommandResult<BlobWithMetadata> {
+    let conn = get_connection()?;
+    let stored = write_blob_bytes(&conn, &filename, &data, &mime)?;
+
+    if verify.unwrap_or(false) {
+        let file_path = PathBuf::from(stored.path.as_deref().unwrap_or_default());
+        verify_stored_blob(&app, &conn, &stored.sha256, &file_path)?;
+    }
+    Ok(stored)
+}
+
+/// Where content with `hash` is stored, creating its shard directory
+///
+/// Files are named by hash plus the original extension (for MIME type detection), in a
+/// subdirectory named after the leading hash chars (for better filesystem performance).
+fn blob_file_path(conn: &Connection, hash: &str, filename: &str) -> CommandResult<PathBuf> {
+    let subdir = shard_dir(&get_blobs_dir()?, hash, get_shard_depth(conn)?);
+    fs::create_dir_all(&subdir)?;
+
+    let extension = std::path::Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    let filename_on_disk = if extension.is_empty() {
+        hash.to_string()
+    } else {
+        format!("{}.{}", hash, extension)
+    };
+    Ok(subdir.join(filename_on_disk))
+}
+
+/// Write in-memory content into the store under its hash and catalog it
+fn write_blob_bytes(
+    conn: &Connection,
+    filename: &str,
+    data: &[u8],
+    mime: &str,
+) -> CommandResult<BlobWithMetadata> {
+    // Calculate SHA-256 hash
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    let hash = format!("{:x}", hasher.finalize());
+
+    let file_path = blob_file_path(conn, &hash, filename)?;
+    write_blob_atomically(&file_path, data, &hash)?;
+
+    // Don't trust the caller's MIME blindly; the leading bytes usually know better
+    let (mime, declared_mime) = resolve_mime(mime, infer::get(data).map(|t| t.mime_type()));
+    if let Some(ref declared) = declared_mime {
+        log_debug!("[Blobs] Sniffed {} for {} (declared {})", mime, hash, declared);
+    }
+
+    let stored = record_stored_blob(conn, &hash, &mime, filename, &file_path)?;
+    set_declared_mime(conn, &hash, declared_mime.as_deref())?;
+    Ok(stored)
+}
+
+/// One file in a `store_blobs` batch
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct BlobUpload {
+    pub filename: String,
+    pub data: Vec<u8>,
+    pub mime: String,
+}
+
+/// Store many blobs in one call, sharing a connection and a transaction
+///
+/// Results come back in input order. Content already catalogued with a file on disk is
+/// not rewritten and is reported as `deduplicated`. A file that fails is rolled back on
+/// its own and reported with an `error`; the rest of the batch is still committed. Files
+/// this call added to the store are removed again with the catalog rows that would have
+/// referenced them, so a rollback never leaves uncatalogued files behind.
+#[tauri::command]
+pub async fn store_blobs(files: Vec<BlobUpload>) -> CommandResult<Vec<StoreBlobResult>> {
+    let mut conn = get_connection()?;
+    let mut created = Vec::new();
+    let results = store_blob_batch(&mut conn, files, &mut created);
+    if results.is_err() {
+        remove_created_files(&created);
+    }
+    let results = results?;
+
+    app_log!(
+        "[Blobs] Stored batch of {} ({} failed)",
+        results.len(),
+        results.iter().filter(|r| r.error.is_some()).count()
+    );
+    Ok(results)
+}
+
+/// Delete files a failed store created
+fn remove_created_files(paths: &[PathBuf]) {
+    for path in paths {
+        if let Err(e) = fs::remove_file(path) {
+            log_warn!("[Blobs] Failed to remove rolled back blob file {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// The `store_blobs` transaction; every file it newly writes is recorded in `created`
+fn store_blob_batch(
+    conn: &mut Connection,
+    files: Vec<BlobUpload>,
+    created: &mut Vec<PathBuf>,
+) -> CommandResult<Vec<StoreBlobResult>> {
+    let mut tx = conn.transaction()?;
+    let mut results = Vec::with_capacity(files.len());
+
+    for file in files {
+        let sp = tx.savepoint()?;
+        let hash = format!("{:x}", Sha256::digest(&file.data));
+        let existing = get_blob_by_hash(&sp, &hash)?
+            .filter(|b| b.path.as_deref().is_some_and(|p| Path::new(p).exists()));
+
+        let mut new_file = None;
+        let outcome = match existing {
+            Some(existing) => Ok((BlobWithMetadata::from(existing), true)),
+            None => blob_file_path(&sp, &hash, &file.filename).and_then(|path| {
+                if !path.exists() {
+                    new_file = Some(path);
+                }
+                write_blob_bytes(&sp, &file.filename, &file.data, &file.mime)
+            })
+            .map(|stored| (stored, false)),
+        };
+        let new_file = new_file.filter(|path| path.exists());
+        match outcome {
+            Ok((blob, deduplicated)) => {
+                // Recorded before the savepoint commits, which can fail too
+                created.extend(new_file);
+                sp.commit()?;
+                results.push(StoreBlobResult {
+                    filename: file.filename,
+                    blob: Some(blob),
+                    deduplicated,
+                    error: None,
+                });
+            }
+            Err(e) => {
+                // Dropping the savepoint rolls back this file's catalog rows
+                drop(sp);
+                if let Some(path) = new_file {
+                    remove_created_files(&[path]);
+                }
+                log_warn!("[Blobs] Failed to store {}: {}", file.filename, e);
+                results.push(StoreBlobResult {
+                    filename: file.filename,
+                    blob: None,
+                    deduplicated: false,
+                    error: Some(e.to_string()),
+                });
+            }
+        }
+    }
+
+    tx.commit()?;
+    Ok(results)
+}
+
+/// Payload of the `blob-verified` event
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlobVerified {
+    pub sha256: String,
+    pub ok: bool,
+    /// Why verification failed, if it did
+    pub error: Option<String>,
+}
+
+/// Re-read a freshly stored blob and confirm its content hash and catalog row
+///
+/// Emits `blob-verified` either way. On a mismatch the blob is marked 'modified' and an
+/// error is returned so the caller can retry the store.
+fn verify_stored_blob(
+    app: &AppHandle,
+    conn: &Connection,
+    hash: &str,
+    file_path: &Path,
+) -> CommandResult<()> {
+    let problem = match hash_file(file_path) {
+        Ok(actual) if actual != hash => Some(format!("content hash is {}", actual)),
+        Ok(_) => {
+            let paths = get_paths_for_hash(conn, hash)?;
+            let expected = file_path.to_string_lossy();
+            if get_blob_by_hash(conn, hash)?.is_none() {
+                Some("catalog row is missing".to_string())
+            } else if !paths.iter().any(|p| *p == expected) {
+                Some("catalog path is missing".to_string())
+            } else {
+                None
+            }
+        }
+        Err(e) => Some(format!("re-read failed: {:#}", e)),
+    };
+
+    let _ = app.emit(
+        "blob-verified",
+        BlobVerified {
+            sha256: hash.to_string(),
+            ok: problem.is_none(),
+            error: problem.clone(),
+        },
+    );
+
+    match problem {
+        None => Ok(()),
+        Some(reason) => {
+            log_warn!("[Blobs] Verification failed for {}: {}", hash, reason);
+            update_health(conn, hash, "modified")?;
+            Err(CommandError::Io(format!(
+                "Stored blob {} failed verification: {}",
+                hash, reason
+            )))
+        }
+    }
+}
+
+/// Count the pages of a PDF, or `None` (logged) if it is encrypted or can't be parsed
+fn pdf_page_count(path: &Path) -> Option<i32> {
+    match lopdf::Document::load(path) {
+        Ok(doc) if doc.is_encrypted() => {
+            log_debug!("[Blobs] Skipping page count for encrypted PDF {}", path.display());
+            None
+        }
+        Ok(doc) => Some(doc.get_pages().len() as i32),
+        Err(e) => {
+            log_warn!("[Blobs] Failed to read page count of {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Read an image's dimensions from its header without decoding pixels
+///
+/// Corrupt or unsupported images yield `None` (logged).
+fn image_dimensions(path: &Path) -> Option<(i32, i32)> {
+    let dimensions = image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(image::ImageError::from)
+        .and_then(|reader| reader.into_dimensions());
+    match dimensions {
+        Ok((width, height)) => Some((width as i32, height as i32)),
+        Err(e) => {
+            log_warn!("[Blobs] Failed to read dimensions of {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Non-`text/*` MIME types whose content is line-oriented text
+const TEXT_LIKE_MIMES: &[&str] = &[
+    "application/json",
+    "application/xml",
+    "application/javascript",
+    "application/x-yaml",
+    "application/toml",
+    "application/x-sh",
+    "application/x-tex",
+    "application/sql",
+];
+
+fn is_text_like(mime: &str) -> bool {
+    mime.starts_with("text/") || TEXT_LIKE_MIMES.contains(&mime)
+}
+
+/// Counting stops past this many lines
+const LINE_COUNT_LIMIT: i32 = 1_000_000;
+/// Stored as `line_count` for files longer than `LINE_COUNT_LIMIT`
+const LINE_COUNT_OVERFLOW: i32 = -1;
+
+/// Count the lines of a text file by streaming it
+///
+/// Returns `LINE_COUNT_OVERFLOW` past `LINE_COUNT_LIMIT`, and `None` for files that
+/// turn out to be binary (contain NUL bytes) or can't be read.
+fn text_line_count(path: &Path) -> Option<i32> {
+    use std::io::Read;
+
+    let mut file = fs::File::open(path).ok()?;
+    let mut buf = vec![0u8; COPY_CHUNK_SIZE];
+    let mut lines: i32 = 0;
+    let mut last = None;
+
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                log_warn!("[Blobs] Failed to count lines of {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        let chunk = &buf[..n];
+        if chunk.contains(&0) {
+            log_warn!("[Blobs] {} is typed as text but looks binary", path.display());
+            return None;
+        }
+        lines += chunk.iter().filter(|&&b| b == b'\n').count() as i32;
+        if lines > LINE_COUNT_LIMIT {
+            return Some(LINE_COUNT_OVERFLOW);
+        }
+        last = chunk.last().copied();
+    }
+
+    // A final line without a trailing newline still counts
+    match last {
+        Some(b) if b != b'\n' => Some(lines + 1),
+        _ => Some(lines),
+    }
+}
+
+/// Type-specific metadata extracted from a blob's content
+#[derive(Debug, Clone, Copy, Default)]
+struct ContentMetadata {
+    page_count: Option<i32>,
+    image_width: Option<i32>,
+    image_height: Option<i32>,
+    line_count: Option<i32>,
+}
+
+/// Extract type-specific metadata (PDF page count, image dimensions, line count) and catalog it
+fn record_content_metadata(
+    conn: &Connection,
+    hash: &str,
+    mime: &str,
+    path: &Path,
+) -> Result<ContentMetadata> {
+    let mut metadata = ContentMetadata::default();
+
+    if mime == "application/pdf" {
+        metadata.page_count = pdf_page_count(path);
+        set_page_count(conn, hash, metadata.page_count)?;
+    } else if mime.starts_with("image/") {
+        let dimensions = image_dimensions(path);
+        metadata.image_width = dimensions.map(|(w, _)| w);
+        metadata.image_height = dimensions.map(|(_, h)| h);
+        set_image_dimensions(conn, hash, metadata.image_width, metadata.image_height)?;
+    } else if is_text_like(mime) {
+        metadata.line_count = text_line_count(path);
+        set_line_count(conn, hash, metadata.line_count)?;
+    }
+
+    Ok(metadata)
+}
+
+/// Catalog a blob that was just placed in the store and describe it for the frontend
+fn record_stored_blob(
+    conn: &Connection,
+    hash: &str,
+    mime: &str,
+    filename: &str,
+    file_path: &Path,
+) -> CommandResult<BlobWithMetadata> {
+    // Get file metadata
+    let metadata = fs::metadata(file_path)?;
+    let mtime_ms = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis() as i64;
+
+    // Insert into database
+    insert_blob(
+        conn,
+        hash,
+        metadata.len() as i64,
+        mime,
+        mtime_ms,
+        Some(filename),
+    )?;
+
+    insert_path(conn, hash, file_path.to_str().unwrap())?;
+    let content = record_content_metadata(conn, hash, mime, file_path)?;
+
+    Ok(BlobWithMetadata {
+        sha256: hash.to_string(),
+        filename: Some(filename.to_string()),
+        size: metadata.len() as i64,
+        mime: mime.to_string(),
+        created_ms: chrono::Utc::now().timestamp_millis(),
+        mtime_ms,
+        path: Some(file_path.to_string_lossy().to_string()),
+        health: Some("healthy".to_string()),
+        page_count: content.page_count,
+        image_width: content.image_width,
+        image_height: content.image_height,
+        line_count: content.line_count,
+    })
+}
+
+/// Payload of the `blob-hash-computed` event
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlobHashComputed {
+    pub source: String,
+    pub sha256: String,
+    /// The content is already catalogued, so no write will follow
+    pub already_stored: bool,
+}
+
+/// Payload of the `blob-stored` event
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlobStored {
+    pub sha256: String,
+    pub path: Option<String>,
+    pub deduplicated: bool,
+}
+
+/// Copy (or hard-link) a file whose hash is known into the content-addressed store and
+/// catalog it
+///
+/// `filename` defaults to the source's name; `mime` defaults to a guess from the
+/// extension, overridden by content sniffing where they disagree.
+fn place_file_in_store(
+    conn: &Connection,
+    source: &Path,
+    hash: &str,
+    filename: Option<String>,
+    mime: Option<String>,
+    link: bool,
+) -> CommandResult<BlobWithMetadata> {
+    let blobs_dir = get_blobs_dir()?;
+    let filename = filename.unwrap_or_else(|| {
+        source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| hash.to_string())
+    });
+    let declared = mime.unwrap_or_else(|| get_mime_type(source));
+    let sniffed = infer::get_from_path(source).ok().flatten();
+    let (mime, declared_mime) = resolve_mime(&declared, sniffed.map(|t| t.mime_type()));
+
+    let depth = get_shard_depth(conn)?;
+    let subdir = shard_dir(&blobs_dir, hash, depth);
+    fs::create_dir_all(&subdir)?;
+
+    let extension = Path::new(&filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    let filename_on_disk = if extension.is_empty() {
+        hash.to_string()
+    } else {
+        format!("{}.{}", hash, extension)
+    };
+    let file_path = subdir.join(&filename_on_disk);
+
+    if !file_path.exists() {
+        let linked = link
+            && match link_blob_atomically(source, &file_path, hash) {
+                Ok(()) => true,
+                Err(e) => {
+                    log_warn!("[Blobs] Hard link failed ({}), copying instead", e);
+                    false
+                }
+            };
+        if !linked {
+            copy_blob_atomically(source, &file_path, hash)?;
+        }
+        app_log!(
+            "[Blobs] Imported {} as {} ({})",
+            source.display(),
+            hash,
+            if linked { "linked" } else { "copied" }
+        );
+    }
+
+    let stored = record_stored_blob(conn, hash, &mime, &filename, &file_path)?;
+    set_declared_mime(conn, hash, declared_mime.as_deref())?;
+    Ok(stored)
+}
+
+/// Store a file already on disk without round-tripping its bytes through IPC
+///
+/// Runs in two phases: once the source is hashed a `blob-hash-computed` event carries
+/// the content address, and `blob-stored` follows when the file is in place. If the
+/// hash is already catalogued with a file on disk, the write is skipped entirely.
+///
+/// With `link`, the blob is hard-linked into the store when source and store share a
+/// filesystem, so importing costs no extra space; linking falls back to a streaming
+/// copy otherwise. The source is read in fixed-size chunks, never loaded whole, so
+/// multi-gigabyte documents import in constant memory. Either way the stored file's
+/// hash is verified. A linked blob shares its inode with the source, so editing the
+/// source in place also changes the blob.
+#[tauri::command]
+pub async fn store_blob_from_path(
+    app: AppHandle,
+    path: String,
+    filename: Option<String>,
+    mime: Option<String>,
+    link: Option<bool>,
+) -> CommandResult<BlobWithMetadata> {
+    let source = PathBuf::from(&path);
+    if !source.is_file() {
+        return Err(CommandError::NotFound(format!("Source file not found: {}", path)));
+    }
+
+    let conn = get_connection()?;
+    let hash = hash_file(&source)?;
+
+    let existing = get_blob_by_hash(&conn, &hash)?
+        .filter(|b| b.path.as_deref().map(|p| Path::new(p).exists()).unwrap_or(false));
+    let _ = app.emit(
+        "blob-hash-computed",
+        BlobHashComputed {
+            source: path.clone(),
+            sha256: hash.clone(),
+            already_stored: existing.is_some(),
+        },
+    );
+
+    if let Some(existing) = existing {
+        log_debug!("[Blobs] {} already stored as {}, skipping write", source.display(), hash);
+        let _ = app.emit(
+            "blob-stored",
+            BlobStored {
+                sha256: hash,
+                path: existing.path.clone(),
+                deduplicated: true,
+            },
+        );
+        return Ok(existing.into());
+    }
+
+    let stored = place_file_in_store(&conn, &source, &hash, filename, mime, link.unwrap_or(false))?;
+
+    let _ = app.emit(
+        "blob-stored",
+        BlobStored {
+            sha256: hash,
+            path: stored.path.clone(),
+            deduplicated: false,
+        },
+    );
+    Ok(stored)
+}
+
+/// Copy every file under `source_dir` into the blob store
+///
+/// Hidden files and directories are skipped, as are files larger than `max_file_size`
+/// bytes when given. Files whose content is already stored count as skipped, so
+/// importing the same folder twice is harmless.
+#[tauri::command]
+pub async fn import_blobs_from_dir(
+    source_dir: String,
+    max_file_size: Option<u64>,
+) -> CommandResult<ImportResult> {
+    let root = PathBuf::from(&source_dir);
+    if !root.is_dir() {
+        return Err(CommandError::NotFound(format!("Directory not found: {}", source_dir)));
+    }
+
+    let conn = get_connection()?;
+    let mut result = ImportResult {
+        added: 0,
+        skipped: 0,
+        errors: Vec::new(),
+    };
+
+    let is_hidden = |entry: &walkdir::DirEntry| {
+        entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.')
+    };
+
+    for entry in WalkDir::new(&root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| !is_hidden(e))
+    {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                result.errors.push(format!("Error walking {}: {}", source_dir, e));
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        let outcome = (|| -> CommandResult<bool> {
+            if let Some(limit) = max_file_size {
+                if entry.metadata().map_err(std::io::Error::from)?.len() > limit {
+                    return Ok(false);
+                }
+            }
+
+            let hash = hash_file(path)?;
+            let stored = get_blob_by_hash(&conn, &hash)?
+                .and_then(|b| b.path)
+                .is_some_and(|p| Path::new(&p).exists());
+            if stored {
+                return Ok(false);
+            }
+
+            place_file_in_store(&conn, path, &hash, None, None, false)?;
+            Ok(true)
+        })();
+
+        match outcome {
+            Ok(true) => result.added += 1,
+            Ok(false) => result.skipped += 1,
+            Err(e) => result
+                .errors
+                .push(format!("Error importing {}: {}", path.display(), e)),
+        }
+    }
+
+    app_log!(
+        "[Blobs] Import from {}: {} added, {} skipped, {} errors",
+        source_dir,
+        result.added,
+        result.skipped,
+        result.errors.len()
+    );
+
+    Ok(result)
+}
+
+/// Delete a blob (database entry only, keeps file on disk)
+#[tauri::command]
+pub async fn delete_blob(sha256: String) -> CommandResult<()> {
+    let conn = get_connection()?;
+
+    // Delete from database only - DO NOT delete the actual file
+    // Files are content-addressed and may be referenced elsewhere
+    db_delete_blob(&conn, &sha256)?;
+
+    Ok(())
+}
+
+/// Metadata of an in-progress chunked upload, persisted next to its data so uploads
+/// can be resumed after a restart
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct PendingUpload {
+    filename: String,
+    mime: String,
+    total_size: u64,
+}
+
+/// Directory holding in-progress chunked uploads (outside the blob store so scans skip it)
+fn get_uploads_dir() -> Result<PathBuf> {
+    let uploads_dir = get_data_dir()?.join("uploads");
+    fs::create_dir_all(&uploads_dir)?;
+    Ok(uploads_dir)
+}
+
+/// Paths of an upload's data and metadata files
+fn upload_paths(upload_id: &str) -> CommandResult<(PathBuf, PathBuf)> {
+    // Upload ids become file names; only accept the UUIDs we hand out
+    uuid::Uuid::parse_str(upload_id)
+        .map_err(|_| CommandError::InvalidInput(format!("Invalid upload id: {}", upload_id)))?;
+    let uploads_dir = get_uploads_dir()?;
+    Ok((
+        uploads_dir.join(format!("{}.part", upload_id)),
+        uploads_dir.join(format!("{}.json", upload_id)),
+    ))
+}
+
+/// Load an upload's metadata, failing with `NotFound` for unknown or finished uploads
+fn load_upload(upload_id: &str) -> CommandResult<(PendingUpload, PathBuf, PathBuf)> {
+    let (data_path, meta_path) = upload_paths(upload_id)?;
+    if !meta_path.exists() {
+        return Err(CommandError::NotFound(format!("Unknown upload: {}", upload_id)));
+    }
+    let upload: PendingUpload = serde_json::from_slice(&fs::read(&meta_path)?)?;
+    Ok((upload, data_path, meta_path))
+}
+
+/// Start a chunked upload for a file too large to send in one `store_blob` call
+///
+/// Returns the upload id to pass to `append_blob_chunk` / `finish_blob_upload`.
+#[tauri::command]
+pub async fn begin_blob_upload(
+    filename: String,
+    mime: String,
+    total_size: u64,
+) -> CommandResult<String> {
+    let upload_id = uuid::Uuid::new_v4().to_string();
+    let (data_path, meta_path) = upload_paths(&upload_id)?;
+
+    fs::File::create(&data_path)?;
+    let upload = PendingUpload {
+        filename,
+        mime,
+        total_size,
+    };
+    fs::write(&meta_path, serde_json::to_vec(&upload)?)?;
+
+    app_log!(
+        "[Blobs] Started upload {} ({}, {} bytes)",
+        upload_id, upload.filename, total_size
+    );
+    Ok(upload_id)
+}
+
+/// Bytes received so far for an upload; resume by appending from this offset
+#[tauri::command]
+pub async fn get_blob_upload_offset(upload_id: String) -> CommandResult<u64> {
+    let (_, data_path, _) = load_upload(&upload_id)?;
+    Ok(fs::metadata(&data_path)?.len())
+}
+
+/// Write a chunk at `offset`
+///
+/// `offset` must not be past the bytes received so far; resending from an earlier
+/// offset overwrites (and truncates) what followed, so a retried chunk is harmless.
+/// Returns the number of bytes received after this chunk.
+#[tauri::command]
+pub async fn append_blob_chunk(upload_id: String, offset: u64, data: Vec<u8>) -> CommandResult<u64> {
+    use std::io::{Seek, SeekFrom};
+
+    let (upload, data_path, _) = load_upload(&upload_id)?;
+    let received = fs::metadata(&data_path)?.len();
+    if offset > received {
+        return Err(CommandError::InvalidInput(format!(
+            "Chunk offset {} is past the {} bytes received for upload {}",
+            offset, received, upload_id
+        )));
+    }
+    let end = offset + data.len() as u64;
+    if end > upload.total_size {
+        return Err(CommandError::InvalidInput(format!(
+            "Chunk ends at {} but upload {} is only {} bytes",
+            end, upload_id, upload.total_size
+        )));
+    }
+
+    let mut file = fs::OpenOptions::new().write(true).open(&data_path)?;
+    file.set_len(offset)?;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(&data)?;
+    Ok(end)
+}
+
+/// Complete an upload: hash the assembled file and move it into the content store
+#[tauri::command]
+pub async fn finish_blob_upload(upload_id: String) -> CommandResult<BlobWithMetadata> {
+    let (upload, data_path, meta_path) = load_upload(&upload_id)?;
+    let received = fs::metadata(&data_path)?.len();
+    if received != upload.total_size {
+        return Err(CommandError::InvalidInput(format!(
+            "Upload {} is incomplete: {} of {} bytes received",
+            upload_id, received, upload.total_size
+        )));
+    }
+    fs::OpenOptions::new().write(true).open(&data_path)?.sync_all()?;
+
+    let conn = get_connection()?;
+    let blobs_dir = get_blobs_dir()?;
+    let hash = hash_file(&data_path)?;
+
+    let depth = get_shard_depth(&conn)?;
+    let subdir = shard_dir(&blobs_dir, &hash, depth);
+    fs::create_dir_all(&subdir)?;
+
+    let extension = Path::new(&upload.filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    let filename_on_disk = if extension.is_empty() {
+        hash.clone()
+    } else {
+        format!("{}.{}", hash, extension)
+    };
+    let file_path = subdir.join(&filename_on_disk);
+
+    let sniffed = infer::get_from_path(&data_path).ok().flatten();
+    let (mime, declared_mime) = resolve_mime(&upload.mime, sniffed.map(|t| t.mime_type()));
+
+    // Already hashed, so a plain rename is enough to place it atomically
+    if file_path.exists() {
+        fs::remove_file(&data_path)?;
+    } else {
+        fs::rename(&data_path, &file_path)?;
+    }
+    fs::remove_file(&meta_path)?;
+
+    let stored = record_stored_blob(&conn, &hash, &mime, &upload.filename, &file_path)?;
+    set_declared_mime(&conn, &hash, declared_mime.as_deref())?;
+
+    app_log!("[Blobs] Finished upload {} as {}", upload_id, hash);
+    Ok(stored)
+}
+
+/// Abort an upload and delete what was received
+#[tauri::command]
+pub async fn abort_blob_upload(upload_id: String) -> CommandResult<()> {
+    let (data_path, meta_path) = upload_paths(&upload_id)?;
+    for path in [data_path, meta_path] {
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    app_log!("[Blobs] Aborted upload {}", upload_id);
+    Ok(())
+}
+
+/// Postgres `(table, column)` pairs that reference blobs by hash
+///
+/// Author avatars are blobs too, referenced from both avatar columns. `device_blobs`
+/// records which devices hold a blob, so a blob only it mentions is still in use.
+const BLOB_REFERENCING_TABLES: &[(&str, &str)] = &[
+    ("assets", "sha256"),
+    ("device_blobs", "sha256"),
+    ("annotations", "sha256"),
+    ("cards", "sha256"),
+    ("authors", "avatar_original_path"),
+    ("authors", "avatar_display_path"),
+];
+
+/// List Postgres entities that reference a blob
+#[tauri::command]
+pub async fn get_blob_references(sha256: String) -> CommandResult<Vec<BlobReference>> {
+    let client = get_pg_client().await?;
+    let mut references = Vec::new();
+
+    for (table, column) in BLOB_REFERENCING_TABLES {
+        // `annotations.id` is TEXT while the others are UUID, so read every id as text
+        let query = format!("SELECT id::text FROM {} WHERE {} = $1", table, column);
+        let rows = client
+            .query(&query, &[&sha256])
+            .await
+            .map_err(|e| CommandError::Database(format!("Failed to query {}: {}", table, e)))?;
+
+        for row in rows {
+            let id: String = row.get(0);
+            references.push(BlobReference {
+                table: table.to_string(),
+                id,
+            });
+        }
+    }
+
+    Ok(references)
+}
+
+/// Every hash referenced by any of `BLOB_REFERENCING_TABLES`
+async fn referenced_hashes(
+    client: &tokio_postgres::Client,
+) -> CommandResult<std::collections::HashSet<String>> {
+    let mut hashes = std::collections::HashSet::new();
+
+    for (table, column) in BLOB_REFERENCING_TABLES {
+        let query = format!(
+            "SELECT DISTINCT {} FROM {} WHERE {} IS NOT NULL",
+            column, table, column
+        );
+        let rows = client
+            .query(&query, &[])
+            .await
+            .map_err(|e| CommandError::Database(format!("Failed to query {}: {}", table, e)))?;
+        hashes.extend(rows.iter().map(|row| row.get::<_, String>(0)));
+    }
+
+    Ok(hashes)
+}
+
+/// Remove files in the blob store whose hash has no catalog row
+///
+/// `delete_blob` only drops catalog rows, so this is how their files get reclaimed.
+/// With `check_postgres`, hashes still referenced in Postgres are kept too (and the
+/// command fails if Postgres is unreachable). A dry run only reports what would go.
+#[tauri::command]
+pub async fn gc_blobs(dry_run: bool, check_postgres: Option<bool>) -> CommandResult<GcReport> {
+    let referenced = if check_postgres.unwrap_or(false) {
+        let client = get_pg_client().await?;
+        referenced_hashes(&client).await?
+    } else {
+        std::collections::HashSet::new()
+    };
+
+    let _guard = CATALOG_LOCK
+        .lock()
+        .map_err(|e| CommandError::Internal(e.to_string()))?;
+    let conn = get_connection()?;
+    let blobs_dir = get_blobs_dir()?;
+
+    let ignore_patterns = get_scan_ignore_patterns(&conn)?;
+    let ignore_patterns = compile_ignore_patterns(&ignore_patterns);
+
+    let mut report = GcReport {
+        dry_run,
+        paths: Vec::new(),
+        bytes: 0,
+        errors: Vec::new(),
+    };
+
+    for entry in WalkDir::new(&blobs_dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !is_ignored(&blobs_dir, e.path(), &ignore_patterns))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(hash) = hash_from_file_name(&file_name) else {
+            continue;
+        };
+        if referenced.contains(hash) || get_blob_by_hash(&conn, hash)?.is_some() {
+            continue;
+        }
+
+        let size = match entry.metadata() {
+            Ok(metadata) => metadata.len(),
+            Err(e) => {
+                report.errors.push(format!("Error reading {}: {}", entry.path().display(), e));
+                continue;
+            }
+        };
+        if !dry_run {
+            if let Err(e) = fs::remove_file(entry.path()) {
+                report.errors.push(format!("Error removing {}: {}", entry.path().display(), e));
+                continue;
+            }
+        }
+        report.paths.push(entry.path().to_string_lossy().to_string());
+        report.bytes += size;
+    }
+
+    app_log!(
+        "[Blobs] GC{}: {} unreferenced files, {} bytes",
+        if dry_run { " (dry run)" } else { "" },
+        report.paths.len(),
+        report.bytes
+    );
+
+    Ok(report)
+}
+
+/// Delete a blob's file from disk
+///
+/// Refuses when the blob is still referenced in Postgres unless `force` is set.
+/// With `remove_row` the catalog entry is dropped, otherwise it is kept and marked 'missing'.
+#[tauri::command]
+pub async fn delete_blob_file(
+    sha256: String,
+    force: bool,
+    remove_row: Option<bool>,
+) -> CommandResult<()> {
+    if !force {
+        let references = get_blob_references(sha256.clone()).await?;
+        if !references.is_empty() {
+            let listed: Vec<String> = references
+                .iter()
+                .map(|r| format!("{}/{}", r.table, r.id))
+                .collect();
+            return Err(CommandError::Conflict(format!(
+                "Blob {} is still referenced by: {}",
+                sha256,
+                listed.join(", ")
+            )));
+        }
+    }
+
+    let conn = get_connection()?;
+    if get_blob_by_hash(&conn, &sha256)?.is_none() {
+        return Err(CommandError::NotFound(format!("Blob not found: {}", sha256)));
+    }
+
+    for path in get_paths_for_hash(&conn, &sha256)? {
+        let path = PathBuf::from(path);
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| {
+                CommandError::Io(format!("Failed to delete {}: {}", path.display(), e))
+            })?;
+        }
+    }
+
+    if remove_row.unwrap_or(false) {
+        db_delete_blob(&conn, &sha256)?;
+    } else {
+        delete_paths_for_hash(&conn, &sha256)?;
+        update_health(&conn, &sha256, "missing")?;
+    }
+
+    app_log!("[Blobs] Deleted file for blob {} (forced: {})", sha256, force);
+
+    Ok(())
+}
+
+/// Rename a blob (update filename in catalog)
+#[tauri::command]
+pub async fn rename_blob(sha256: String, filename: String) -> CommandResult<()> {
+    let conn = get_connection()?;
+    Ok(update_filename(&conn, &sha256, &filename)?)
+}
+
+/// Trim a tag and reject empty ones
+fn normalize_tag(tag: &str) -> CommandResult<&str> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(CommandError::InvalidInput("Tag must not be empty".to_string()));
+    }
+    Ok(tag)
+}
+
+/// Tag a blob (no-op if it already has the tag)
+#[tauri::command]
+pub async fn add_blob_tag(sha256: String, tag: String) -> CommandResult<()> {
+    let tag = normalize_tag(&tag)?;
+    let conn = get_connection()?;
+    if get_blob_by_hash(&conn, &sha256)?.is_none() {
+        return Err(CommandError::NotFound(format!("Blob not found: {}", sha256)));
+    }
+    Ok(crate::db::add_blob_tag(&conn, &sha256, tag)?)
+}
+
+/// Remove a tag from a blob, returning whether it had it
+#[tauri::command]
+pub async fn remove_blob_tag(sha256: String, tag: String) -> CommandResult<bool> {
+    let conn = get_connection()?;
+    Ok(crate::db::remove_blob_tag(&conn, &sha256, tag.trim())?)
+}
+
+/// List a blob's tags
+#[tauri::command]
+pub async fn list_blob_tags(sha256: String) -> CommandResult<Vec<String>> {
+    let conn = get_connection()?;
+    Ok(crate::db::list_blob_tags(&conn, &sha256)?)
+}
+
+/// Find blobs carrying a tag
+#[tauri::command]
+pub async fn search_blobs_by_tag(tag: String) -> CommandResult<Vec<BlobWithMetadata>> {
+    let tag = normalize_tag(&tag)?;
+    let conn = get_connection()?;
+    Ok(crate::db::list_blobs_by_tag(&conn, tag)?)
+}
+
+/// Scan filesystem for blobs
+#[tauri::command]
+pub async fn scan_blobs(app: AppHandle) -> CommandResult<ScanResult> {
+    let _guard = CATALOG_LOCK
+        .lock()
+        .map_err(|e| CommandError::Internal(e.to_string()))?;
+    let conn = get_connection()?;
+    let result = scan_blob_store(&conn)?;
+
+    if let Err(e) = check_storage_usage(&app, &conn) {
+        log_warn!("[Blobs] Storage check after scan failed: {}", e);
+    }
+    Ok(result)
+}
+
+/// Walk the blob store and catalog every `<hash>[.<ext>]` file found
+fn scan_blob_store(conn: &Connection) -> CommandResult<ScanResult> {
+    let blobs_dir = get_blobs_dir()?;
+
+    let ignore_patterns = get_scan_ignore_patterns(conn)?;
+    let ignore_patterns = compile_ignore_patterns(&ignore_patterns);
+
+    let mut added = 0;
+    let mut updated = 0;
+    let mut skipped = 0;
+    let mut relocated = 0;
+    let mut deleted = 0;
+    let mut errors = Vec::new();
+
+    // Walk through blobs directory, pruning ignored files and directories
+    for entry in WalkDir::new(&blobs_dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !is_ignored(&blobs_dir, e.path(), &ignore_patterns))
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        let file_name = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => continue,
+        };
+
+        // Skip if filename doesn't look like a hash (64 hex chars, optional extension)
+        let hash = match hash_from_file_name(&file_name) {
+            Some(hash) => hash,
+            None => continue,
+        };
+
+        match process_file_for_scan(conn, path, hash) {
+            Ok((outcome, stale_paths)) => {
+                deleted += stale_paths;
+                match outcome {
+                    ScanOutcome::Added => added += 1,
+                    ScanOutcome::Updated => updated += 1,
+                    ScanOutcome::Relocated => relocated += 1,
+                    ScanOutcome::Unchanged => skipped += 1,
+                }
+            }
+            Err(e) => {
+                errors.push(format!("Error processing {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    Ok(ScanResult {
+        added,
+        updated,
+        skipped,
+        relocated,
+        deleted,
+        errors,
+    })
+}
+
+/// Outcome of scanning a single file
+enum ScanOutcome {
+    Added,
+    Updated,
+    /// A known blob whose primary path is gone, found here instead
+    Relocated,
+    Unchanged,
+}
+
+/// Drop recorded paths of `hash` whose files no longer exist, returning how many went
+fn prune_stale_paths(conn: &Connection, hash: &str) -> Result<i32> {
+    let mut removed = 0;
+    for recorded in get_paths_for_hash(conn, hash)? {
+        if !Path::new(&recorded).exists() {
+            delete_path(conn, &recorded)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Catalog one scanned file; also returns how many stale paths of its hash were removed
+fn process_file_for_scan(
+    conn: &rusqlite::Connection,
+    path: &Path,
+    hash: &str,
+) -> Result<(ScanOutcome, i32)> {
+    // Check if already in database and get existing filename
+    let existing_blob = get_blob_by_hash(conn, hash)?;
+
+    // A known hash at a new location: forget paths that are gone, and flag the blob
+    // if its primary path was one of them
+    let path_str = path.to_string_lossy();
+    let mut stale_paths = 0;
+    let mut relocated = false;
+    if let Some(ref blob) = existing_blob {
+        let primary_gone = blob
+            .path
+            .as_deref()
+            .is_some_and(|p| p != path_str && !Path::new(p).exists());
+        stale_paths = prune_stale_paths(conn, hash)?;
+        relocated = primary_gone;
+    }
+    let existing_filename = existing_blob.as_ref().and_then(|b| b.filename.clone());
+    // Blobs first seen by a scan only have their on-disk name; derive `<hash>.<ext>` from it
+    let derived_filename = existing_filename
+        .is_none()
+        .then(|| default_filename_for(path, hash))
+        .flatten();
+
+    // Get file metadata
+    let metadata = fs::metadata(path)?;
+    let mtime_ms = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis() as i64;
+
+    // Unchanged since the last scan: only make sure the path is recorded
+    if let Some(ref blob) = existing_blob {
+        let unchanged = blob.mtime_ms == mtime_ms
+            && blob.size == metadata.len() as i64
+            && matches!(blob.health.as_deref(), Some("healthy") | Some("relocated"));
+        if unchanged {
+            insert_path(conn, hash, path.to_str().unwrap())?;
+            if relocated {
+                update_health(conn, hash, "relocated")?;
+                app_log!("[Blobs] {} relocated to {}", hash, path.display());
+                return Ok((ScanOutcome::Relocated, stale_paths));
+            }
+            // Backfill line counts for text blobs catalogued before they were recorded
+            if is_text_like(&blob.mime) && get_line_count(conn, hash)?.is_none() {
+                record_content_metadata(conn, hash, &blob.mime, path)?;
+            }
+            if let Some(ref filename) = derived_filename {
+                update_filename(conn, hash, filename)?;
+            }
+            return Ok((ScanOutcome::Unchanged, stale_paths));
+        }
+    }
+
+    // Try to detect MIME from filename first (if available), then fallback to path
+    let mime = if let Some(ref filename) = existing_filename {
+        mime_guess::from_path(filename)
+            .first_or_octet_stream()
+            .to_string()
+    } else {
+        get_mime_type(path)
+    };
+
+    // Insert or update (preserve existing filename if available)
+    insert_blob(
+        conn,
+        hash,
+        metadata.len() as i64,
+        &mime,
+        mtime_ms,
+        existing_filename.as_deref().or(derived_filename.as_deref()),
+    )?;
+
+    insert_path(conn, hash, path.to_str().unwrap())?;
+    record_content_metadata(conn, hash, &mime, path)?;
+
+    if relocated {
+        update_health(conn, hash, "relocated")?;
+        app_log!("[Blobs] {} relocated to {}", hash, path.display());
+        Ok((ScanOutcome::Relocated, stale_paths))
+    } else if existing_blob.is_none() {
+        Ok((ScanOutcome::Added, stale_paths))
+    } else {
+        Ok((ScanOutcome::Updated, stale_paths))
+    }
+}
+
+/// Default display name for a scanned blob: `<hash>.<ext>`, or None if the file has no extension
+fn default_filename_for(path: &Path, hash: &str) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .filter(|e| !e.is_empty())
+        .map(|ext| format!("{}.{}", hash, ext.to_lowercase()))
+}
+
+/// Get the glob patterns `scan_blobs` ignores
+#[tauri::command]
+pub async fn get_scan_ignore_list() -> CommandResult<Vec<String>> {
+    let conn = get_connection()?;
+    Ok(get_scan_ignore_patterns(&conn)?)
+}
+
+/// Replace the glob patterns `scan_blobs` ignores
+#[tauri::command]
+pub async fn set_scan_ignore_list(patterns: Vec<String>) -> CommandResult<()> {
+    for pattern in &patterns {
+        glob::Pattern::new(pattern)
+            .map_err(|e| CommandError::InvalidInput(format!("Invalid pattern '{}': {}", pattern, e)))?;
+    }
+
+    let conn = get_connection()?;
+    let value = serde_json::to_string(&patterns)?;
+    Ok(set_setting(&conn, SCAN_IGNORE_KEY, &value)?)
+}
+
+/// Re-hash every blob file and move any whose name or shard doesn't match its content
+#[tauri::command]
+pub async fn fix_mislocated_blobs() -> CommandResult<RelocationReport> {
+    let conn = get_connection()?;
+    let blobs_dir = get_blobs_dir()?;
+    let depth = get_shard_depth(&conn)?;
+
+    let mut report = RelocationReport {
+        checked: 0,
+        relocated: 0,
+        duplicates_removed: 0,
+        errors: Vec::new(),
+    };
+
+    let files: Vec<PathBuf> = WalkDir::new(&blobs_dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
+
+    for path in files {
+        let file_name = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => continue,
+        };
+        let named_hash = match hash_from_file_name(&file_name) {
+            Some(hash) => hash.to_string(),
+            None => continue,
+        };
+
+        report.checked += 1;
+        if let Err(e) = relocate_if_needed(&conn, &blobs_dir, depth, &path, &named_hash, &mut report)
+        {
+            report
+                .errors
+                .push(format!("Error relocating {}: {}", path.display(), e));
+        }
+    }
+
+    app_log!(
+        "[Blobs] Relocation check: {} checked, {} relocated, {} duplicates removed",
+        report.checked, report.relocated, report.duplicates_removed
+    );
+
+    Ok(report)
+}
+
+/// Point every catalog `paths` row at wherever the blob's file actually lives now
+///
+/// Files are matched to blobs by the hash in their name. Blobs whose recorded paths
+/// changed are marked 'relocated'; blobs with no file anywhere are marked 'missing'.
+#[tauri::command]
+pub async fn reindex_paths() -> CommandResult<ReindexReport> {
+    use std::collections::{HashMap, HashSet};
+
+    let _guard = CATALOG_LOCK
+        .lock()
+        .map_err(|e| CommandError::Internal(e.to_string()))?;
+    let conn = get_connection()?;
+    let blobs_dir = get_blobs_dir()?;
+
+    let ignore_patterns = get_scan_ignore_patterns(&conn)?;
+    let ignore_patterns = compile_ignore_patterns(&ignore_patterns);
+
+    // Index the files that are actually on disk by the hash in their name
+    let mut found: HashMap<String, Vec<String>> = HashMap::new();
+    for entry in WalkDir::new(&blobs_dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !is_ignored(&blobs_dir, e.path(), &ignore_patterns))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if let Some(hash) = hash_from_file_name(&file_name) {
+            found
+                .entry(hash.to_string())
+                .or_default()
+                .push(entry.path().to_string_lossy().to_string());
+        }
+    }
+
+    let mut report = ReindexReport {
+        checked: 0,
+        unchanged: 0,
+        relocated: 0,
+        missing: 0,
+        untracked: 0,
+        errors: Vec::new(),
+    };
+
+    let hashes: HashSet<String> = list_all_blobs(&conn)?
+        .into_iter()
+        .map(|b| b.sha256)
+        .collect();
+
+    for hash in &hashes {
+        report.checked += 1;
+        let result = (|| -> Result<()> {
+            let mut recorded = get_paths_for_hash(&conn, hash)?;
+            recorded.sort();
+            let mut actual = found.get(hash).cloned().unwrap_or_default();
+            actual.sort();
+
+            if actual.is_empty() {
+                update_health(&conn, hash, "missing")?;
+                report.missing += 1;
+            } else if actual == recorded {
+                report.unchanged += 1;
+            } else {
+                delete_paths_for_hash(&conn, hash)?;
+                for path in &actual {
+                    insert_path(&conn, hash, path)?;
+                }
+                update_health(&conn, hash, "relocated")?;
+                report.relocated += 1;
+            }
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            report.errors.push(format!("Error reindexing {}: {}", hash, e));
+        }
+    }
+
+    report.untracked = found.keys().filter(|h| !hashes.contains(*h)).count() as i32;
+
+    app_log!(
+        "[Blobs] Reindex: {} checked, {} relocated, {} missing, {} untracked",
+        report.checked, report.relocated, report.missing, report.untracked
+    );
+
+    Ok(report)
+}
+
+/// Move a single file to the location implied by its actual content hash
+fn relocate_if_needed(
+    conn: &Connection,
+    blobs_dir: &Path,
+    depth: usize,
+    path: &Path,
+    named_hash: &str,
+    report: &mut RelocationReport,
+) -> Result<()> {
+    let actual_hash = hash_file(path)?;
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let target_name = if extension.is_empty() {
+        actual_hash.clone()
+    } else {
+        format!("{}.{}", actual_hash, extension)
+    };
+    let target_dir = shard_dir(blobs_dir, &actual_hash, depth);
+    let target = target_dir.join(&target_name);
+
+    if target == path {
+        return Ok(());
+    }
+
+    let old_path = path.to_string_lossy().to_string();
+
+    if target.exists() {
+        // Identical content is already stored correctly; drop the stray copy
+        fs::remove_file(path)?;
+        report.duplicates_removed += 1;
+    } else {
+        fs::create_dir_all(&target_dir)?;
+        fs::rename(path, &target)?;
+        report.relocated += 1;
+    }
+
+    delete_path(conn, &old_path)?;
+
+    // The misnamed hash no longer has this file behind it
+    if named_hash != actual_hash && get_paths_for_hash(conn, named_hash)?.is_empty() {
+        update_health(conn, named_hash, "missing")?;
+    }
+
+    // Make sure the real hash is catalogued, carrying over the stray entry's filename
+    if get_blob_by_hash(conn, &actual_hash)?.is_none() {
+        let filename = get_blob_by_hash(conn, named_hash)?.and_then(|b| b.filename);
+        let metadata = fs::metadata(&target)?;
+        let mtime_ms = metadata
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_millis() as i64;
+        let mime = match filename {
+            Some(ref name) => mime_guess::from_path(name).first_or_octet_stream().to_string(),
+            None => get_mime_type(&target),
+        };
+        insert_blob(
+            conn,
+            &actual_hash,
+            metadata.len() as i64,
+            &mime,
+            mtime_ms,
+            filename.as_deref(),
+        )?;
+    }
+    insert_path(conn, &actual_hash, &target.to_string_lossy())?;
+
+    Ok(())
+}
+
+/// Settings key persisting the last hash verified by `verify_all_integrity`
+const INTEGRITY_PROGRESS_KEY: &str = "integrity_last_hash";
+/// Settings key for how many files are hashed in parallel
+const INTEGRITY_CONCURRENCY_KEY: &str = "integrity_concurrency";
+/// Settings key for the pause between batches, in milliseconds
+const INTEGRITY_THROTTLE_KEY: &str = "integrity_throttle_ms";
+const DEFAULT_INTEGRITY_CONCURRENCY: usize = 2;
+const DEFAULT_INTEGRITY_THROTTLE_MS: u64 = 50;
+/// Blobs loaded from the catalog (and checkpointed) at a time
+const INTEGRITY_BATCH_SIZE: i64 = 32;
+
+/// Set by `cancel_integrity_check`; checked between batches
+static INTEGRITY_CANCELLED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+/// Re-hash every catalogued blob, updating health as it goes
+///
+/// Work happens in batches whose last hash is checkpointed in settings, so an
+/// interrupted run can continue from `get_integrity_resume_token`. Concurrency and the
+/// pause between batches come from settings (`set_integrity_throttle`) to keep the disk
+/// usable while it runs.
+#[tauri::command]
+pub async fn verify_all_integrity(
+    app: AppHandle,
+    resume_token: Option<String>,
+) -> CommandResult<IntegrityReport> {
+    use std::sync::atomic::Ordering;
+
+    INTEGRITY_CANCELLED.store(false, Ordering::SeqCst);
+
+    let (concurrency, throttle_ms, total) = {
+        let conn = get_connection()?;
+        let concurrency = get_setting(&conn, INTEGRITY_CONCURRENCY_KEY)?
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_INTEGRITY_CONCURRENCY);
+        let throttle_ms = get_setting(&conn, INTEGRITY_THROTTLE_KEY)?
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_INTEGRITY_THROTTLE_MS);
+        (concurrency, throttle_ms, get_stats(&conn)?.total_blobs)
+    };
+
+    let mut cursor = resume_token.unwrap_or_default();
+    let mut progress = IntegrityProgress {
+        verified: 0,
+        total,
+        modified: 0,
+        missing: 0,
+    };
+
+    loop {
+        let batch = {
+            let conn = get_connection()?;
+            crate::db::list_blobs_after(&conn, &cursor, INTEGRITY_BATCH_SIZE)?
+        };
+        let Some(last) = batch.last() else {
+            break;
+        };
+        let batch_end = last.sha256.clone();
+
+        for chunk in batch.chunks(concurrency) {
+            let jobs: Vec<_> = chunk
+                .iter()
+                .map(|blob| {
+                    let hash = blob.sha256.clone();
+                    let path = blob.path.clone().map(PathBuf::from);
+                    tokio::task::spawn_blocking(move || {
+                        let health = match path.filter(|p| p.exists()) {
+                            None => "missing",
+                            Some(p) => match hash_file(&p) {
+                                Ok(actual) if actual == hash => "healthy",
+                                Ok(_) => "modified",
+                                Err(_) => "missing",
+                            },
+                        };
+                        (hash, health)
+                    })
+                })
+                .collect();
+
+            let mut outcomes = Vec::with_capacity(jobs.len());
+            for job in jobs {
+                outcomes.push(
+                    job.await
+                        .map_err(|e| CommandError::Internal(format!("Hash task failed: {}", e)))?,
+                );
+            }
+
+            let conn = get_connection()?;
+            for ((hash, health), blob) in outcomes.into_iter().zip(chunk) {
+                match health {
+                    "modified" => progress.modified += 1,
+                    "missing" => progress.missing += 1,
+                    _ => {}
+                }
+                // A relocated blob that still hashes correctly keeps its relocation marker
+                let keep = health == "healthy" && blob.health.as_deref() == Some("relocated");
+                if !keep && blob.health.as_deref() != Some(health) {
+                    update_health(&conn, &hash, health)?;
+                }
+                progress.verified += 1;
+            }
+        }
+
+        cursor = batch_end;
+        {
+            let conn = get_connection()?;
+            set_setting(&conn, INTEGRITY_PROGRESS_KEY, &cursor)?;
+        }
+        let _ = app.emit("integrity-progress", progress.clone());
+
+        if INTEGRITY_CANCELLED.load(Ordering::SeqCst) {
+            app_log!("[Blobs] Integrity check paused at {}", cursor);
+            let conn = get_connection()?;
+            return Ok(IntegrityReport {
+                verified: progress.verified,
+                modified: progress.modified,
+                missing: progress.missing,
+                resume_token: Some(cursor),
+                health: get_stats(&conn)?,
+            });
+        }
+
+        if throttle_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(throttle_ms)).await;
+        }
+    }
+
+    let conn = get_connection()?;
+    delete_setting(&conn, INTEGRITY_PROGRESS_KEY)?;
+
+    app_log!(
+        "[Blobs] Integrity check complete: {} verified, {} modified, {} missing",
+        progress.verified, progress.modified, progress.missing
+    );
+
+    Ok(IntegrityReport {
+        verified: progress.verified,
+        modified: progress.modified,
+        missing: progress.missing,
+        resume_token: None,
+        health: get_stats(&conn)?,
+    })
+}
+
+/// Resume token left behind by an interrupted `verify_all_integrity`, if any
+#[tauri::command]
+pub async fn get_integrity_resume_token() -> CommandResult<Option<String>> {
+    let conn = get_connection()?;
+    Ok(get_setting(&conn, INTEGRITY_PROGRESS_KEY)?)
+}
+
+/// Ask a running `verify_all_integrity` to stop after the current batch
+#[tauri::command]
+pub async fn cancel_integrity_check() -> CommandResult<()> {
+    INTEGRITY_CANCELLED.store(true, std::sync::atomic::Ordering::SeqCst);
+    Ok(())
+}
+
+/// Configure how hard `verify_all_integrity` works the disk
+#[tauri::command]
+pub async fn set_integrity_throttle(concurrency: usize, throttle_ms: u64) -> CommandResult<()> {
+    if concurrency == 0 {
+        return Err(CommandError::InvalidInput(
+            "Concurrency must be at least 1".to_string(),
+        ));
+    }
+
+    let conn = get_connection()?;
+    set_setting(&conn, INTEGRITY_CONCURRENCY_KEY, &concurrency.to_string())?;
+    set_setting(&conn, INTEGRITY_THROTTLE_KEY, &throttle_ms.to_string())?;
+    Ok(())
+}
+
+/// Work out a blob's health from its file on disk
+///
+/// Size and mtime are compared against the catalog; with `deep` the file is re-hashed
+/// and the hash alone decides between 'healthy' and 'modified'.
+fn check_blob_file(blob: &BlobWithMetadata, deep: bool) -> Result<&'static str> {
+    let Some(path) = blob.path.as_deref().map(Path::new).filter(|p| p.is_file()) else {
+        return Ok("missing");
+    };
+
+    if deep {
+        return Ok(if hash_file(path)? == blob.sha256 { "healthy" } else { "modified" });
+    }
+
+    let metadata = fs::metadata(path)?;
+    let mtime_ms = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis() as i64;
+    if metadata.len() as i64 != blob.size || mtime_ms != blob.mtime_ms {
+        Ok("modified")
+    } else {
+        Ok("healthy")
+    }
+}
+
+/// Check every catalogued blob against its file and update the `health` column
+///
+/// A quick pass compares size and mtime; `deep` re-hashes each file instead, which is
+/// slow on large stores (see `verify_all_integrity` for a resumable, throttled version).
+#[tauri::command]
+pub async fn verify_blobs(deep: bool) -> CommandResult<VerifyResult> {
+    let conn = get_connection()?;
+    let mut result = VerifyResult {
+        checked: 0,
+        healthy: 0,
+        modified: 0,
+        missing: 0,
+        unchanged: 0,
+        errors: Vec::new(),
+    };
+
+    for blob in list_all_blobs(&conn)? {
+        result.checked += 1;
+        let health = match check_blob_file(&blob, deep) {
+            Ok(health) => health,
+            Err(e) => {
+                result.errors.push(format!("Error verifying {}: {}", blob.sha256, e));
+                continue;
+            }
+        };
+
+        // A relocated blob that still checks out keeps its relocation marker
+        let current = blob.health.as_deref().unwrap_or("healthy");
+        if current == health || (health == "healthy" && current == "relocated") {
+            result.unchanged += 1;
+            continue;
+        }
+
+        update_health(&conn, &blob.sha256, health)?;
+        match health {
+            "healthy" => result.healthy += 1,
+            "modified" => result.modified += 1,
+            _ => result.missing += 1,
+        }
+    }
+
+    app_log!(
+        "[Blobs] Verify ({}): {} checked, {} healthy, {} modified, {} missing",
+        if deep { "deep" } else { "quick" },
+        result.checked,
+        result.healthy,
+        result.modified,
+        result.missing
+    );
+
+    Ok(result)
+}
+
+/// Health check for blob storage
+#[tauri::command]
+pub async fn health_check() -> CommandResult<HealthReport> {
+    let conn = get_connection()?;
+    Ok(get_stats(&conn)?)
+}
+
+/// Group catalog entries that share a filename but have different content
+#[tauri::command]
+pub async fn find_duplicate_filenames() -> CommandResult<Vec<DuplicateGroup>> {
+    let conn = get_connection()?;
+    Ok(crate::db::find_duplicate_filenames(&conn)?)
+}
+
+/// Group catalog entries that share content but are stored under different names
+#[tauri::command]
+pub async fn find_same_content_different_names() -> CommandResult<Vec<DuplicateGroup>> {
+    let conn = get_connection()?;
+    Ok(crate::db::find_same_content_different_names(&conn)?)
+}
+
+/// Report blobs stored under several filenames or paths and the space dedup saves
+#[tauri::command]
+pub async fn dedup_report() -> CommandResult<DedupReport> {
+    let conn = get_connection()?;
+    Ok(crate::db::dedup_report(&conn)?)
+}
+
+/// Settings key for the blob store size budget in bytes (unset = no budget)
+const STORAGE_BUDGET_KEY: &str = "storage_budget_bytes";
+/// Settings key for the minimum free disk space in bytes before alerting
+const STORAGE_MIN_FREE_KEY: &str = "storage_min_free_bytes";
+const DEFAULT_STORAGE_MIN_FREE_BYTES: u64 = 1024 * 1024 * 1024;
+/// Budget fractions at which `storage-warning` is emitted as "warning" and "critical"
+const STORAGE_WARNING_RATIO: f64 = 0.80;
+const STORAGE_CRITICAL_RATIO: f64 = 0.95;
+/// How often the background monitor re-checks storage usage
+const STORAGE_CHECK_INTERVAL_SECS: u64 = 10 * 60;
+
+/// Payload of the `storage-warning` event
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StorageWarning {
+    /// "warning" or "critical"
+    pub severity: String,
+    /// Which thresholds were crossed: "budget" and/or "free_space"
+    pub reasons: Vec<String>,
+    pub store_bytes: i64,
+    pub budget_bytes: Option<u64>,
+    pub available_bytes: u64,
+    pub min_free_bytes: u64,
+}
+
+/// Compare blob store size and free disk space against the configured thresholds
+///
+/// Emits `storage-warning` and returns it when a threshold is crossed.
+fn check_storage_usage(app: &AppHandle, conn: &Connection) -> CommandResult<Option<StorageWarning>> {
+    let store_bytes = get_stats(conn)?.total_size;
+    let available_bytes = fs2::available_space(get_blobs_dir()?)?;
+    let budget_bytes = get_setting(conn, STORAGE_BUDGET_KEY)?.and_then(|v| v.parse::<u64>().ok());
+    let min_free_bytes = get_setting(conn, STORAGE_MIN_FREE_KEY)?
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_STORAGE_MIN_FREE_BYTES);
+
+    let mut reasons = Vec::new();
+    let mut critical = false;
+    if let Some(budget) = budget_bytes.filter(|b| *b > 0) {
+        let ratio = store_bytes as f64 / budget as f64;
+        if ratio >= STORAGE_WARNING_RATIO {
+            reasons.push("budget".to_string());
+            critical |= ratio >= STORAGE_CRITICAL_RATIO;
+        }
+    }
+    if available_bytes < min_free_bytes {
+        reasons.push("free_space".to_string());
+        critical = true;
+    }
+
+    if reasons.is_empty() {
+        return Ok(None);
+    }
+
+    let warning = StorageWarning {
+        severity: if critical { "critical" } else { "warning" }.to_string(),
+        reasons,
+        store_bytes,
+        budget_bytes,
+        available_bytes,
+        min_free_bytes,
+    };
+    app_log!(
+        "[Blobs] Storage {}: store {} bytes, {} bytes free",
+        warning.severity, store_bytes, available_bytes
+    );
+    let _ = app.emit("storage-warning", warning.clone());
+    Ok(Some(warning))
+}
+
+/// Check storage usage now (also runs after every scan and periodically in the background)
+#[tauri::command]
+pub async fn check_storage(app: AppHandle) -> CommandResult<Option<StorageWarning>> {
+    let conn = get_connection()?;
+    check_storage_usage(&app, &conn)
+}
+
+/// Configure storage alert thresholds
+///
+/// `budget_bytes = None` removes the budget; `min_free_bytes = None` restores the 1 GiB default.
+#[tauri::command]
+pub async fn set_storage_thresholds(
+    budget_bytes: Option<u64>,
+    min_free_bytes: Option<u64>,
+) -> CommandResult<()> {
+    let conn = get_connection()?;
+    match budget_bytes {
+        Some(bytes) => set_setting(&conn, STORAGE_BUDGET_KEY, &bytes.to_string())?,
+        None => delete_setting(&conn, STORAGE_BUDGET_KEY)?,
+    }
+    match min_free_bytes {
+        Some(bytes) => set_setting(&conn, STORAGE_MIN_FREE_KEY, &bytes.to_string())?,
+        None => delete_setting(&conn, STORAGE_MIN_FREE_KEY)?,
+    }
+    Ok(())
+}
+
+/// Re-check storage usage every `STORAGE_CHECK_INTERVAL_SECS` for the lifetime of the app
+pub fn spawn_storage_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(STORAGE_CHECK_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let checked = get_connection()
+                .map_err(CommandError::from)
+                .and_then(|conn| check_storage_usage(&app, &conn));
+            if let Err(e) = checked {
+                log_warn!("[Blobs] Periodic storage check failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Compact the catalog database and report its size before and after
+#[tauri::command]
+pub async fn vacuum_catalog() -> CommandResult<VacuumResult> {
+    let _guard = CATALOG_LOCK
+        .try_lock()
+        .map_err(|_| CommandError::Busy("Catalog is busy (scan in progress)".to_string()))?;
+    let conn = get_connection()?;
+
+    let size_before = catalog_file_size()?;
+    crate::db::vacuum(&conn)?;
+    let size_after = catalog_file_size()?;
+
+    app_log!(
+        "[Blobs] Vacuumed catalog: {} -> {} bytes",
+        size_before, size_after
+    );
+
+    Ok(VacuumResult {
+        size_before,
+        size_after,
+    })
+}
+
+const CATALOG_CSV_HEADER: &[&str] = &[
+    "sha256",
+    "filename",
+    "size",
+    "mime",
+    "created_ms",
+    "mtime_ms",
+    "health",
+    "image_width",
+    "image_height",
+    "line_count",
+    "path",
+];
+
+/// Quote a CSV field when it contains a delimiter, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Write the blob catalog to `dest_path` as CSV, one row per blob with its primary path
+///
+/// Returns the number of rows written (excluding the header).
+#[tauri::command]
+pub async fn export_catalog_csv(dest_path: String) -> CommandResult<i32> {
+    let conn = get_connection()?;
+    let blobs = crate::db::list_blobs_with_primary_path(&conn)?;
+
+    let file = fs::File::create(&dest_path)?;
+    let mut writer = std::io::BufWriter::new(file);
+    writeln!(writer, "{}", CATALOG_CSV_HEADER.join(","))?;
+
+    let optional = |value: Option<i32>| value.map(|v| v.to_string()).unwrap_or_default();
+    for blob in &blobs {
+        let fields = [
+            blob.sha256.clone(),
+            blob.filename.clone().unwrap_or_default(),
+            blob.size.to_string(),
+            blob.mime.clone(),
+            blob.created_ms.to_string(),
+            blob.mtime_ms.to_string(),
+            blob.health.clone().unwrap_or_default(),
+            optional(blob.image_width),
+            optional(blob.image_height),
+            optional(blob.line_count),
+            blob.path.clone().unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        writeln!(writer, "{}", line.join(","))?;
+    }
+    writer.flush()?;
+
+    app_log!("[Blobs] Exported {} catalog rows to {}", blobs.len(), dest_path);
+    Ok(blobs.len() as i32)
+}
+
+/// Payload of the `blob-export-progress` event
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlobExportProgress {
+    pub exported: i32,
+    pub total: i32,
+    pub sha256: String,
+}
 
-    if orphaned_only {
-        // TODO: Filter orphaned blobs (no associated asset)
-        // For now, return all blobs
-        Ok(blobs)
-    } else {
-        Ok(blobs)
-    }
+/// Entry in the `manifest.json` of a zip export
+#[derive(Debug, Clone, serde::Serialize)]
+struct ZipManifestEntry {
+    sha256: String,
+    size: i64,
+    mime: String,
 }
 
-/// Get blob metadata by SHA-256 hash
-#[tauri::command]
-pub async fn stat_blob(sha256: String) -> Result<Option<BlobInfo>, String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
-    get_blob_by_hash(&conn, &sha256).map_err(|e| e.to_string())
+/// Name for a blob inside a zip export: its original filename (without any directory
+/// part), or the hash; names already taken get a short hash suffix
+fn zip_entry_name(blob: &BlobWithMetadata, taken: &std::collections::HashSet<String>) -> String {
+    let name = blob
+        .filename
+        .as_deref()
+        .and_then(|f| Path::new(f).file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .filter(|n| !n.is_empty() && n != "manifest.json")
+        .unwrap_or_else(|| blob.sha256.clone());
+    if !taken.contains(&name) {
+        return name;
+    }
+
+    let path = Path::new(&name);
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, &blob.sha256[..8], ext.to_string_lossy()),
+        None => format!("{}_{}", stem, &blob.sha256[..8]),
+    }
 }
 
-/// Store a new blob
+/// Write every catalogued blob into a zip archive at `dest_path`
+///
+/// Entries are named by original filename, and `manifest.json` maps each entry name to
+/// its `{sha256, size, mime}`. Files are streamed into the archive one at a time, and a
+/// `blob-export-progress` event follows each one.
 #[tauri::command]
-pub async fn store_blob(
-    filename: String,
-    data: Vec<u8>,
-    mime: String,
-) -> Result<BlobWithMetadata, String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
-    let blobs_dir = get_blobs_dir().map_err(|e| e.to_string())?;
+pub async fn export_blobs_zip(app: AppHandle, dest_path: String) -> CommandResult<ZipExportResult> {
+    use std::collections::{BTreeMap, HashSet};
+    use zip::write::SimpleFileOptions;
 
-    // Calculate SHA-256 hash
-    let mut hasher = Sha256::new();
-    hasher.update(&data);
-    let hash = format!("{:x}", hasher.finalize());
+    let conn = get_connection()?;
+    let blobs = crate::db::list_blobs_with_primary_path(&conn)?;
+    let total = blobs.len() as i32;
 
-    // Create subdirectory based on first 2 chars of hash (for better filesystem performance)
-    let subdir = blobs_dir.join(&hash[..2]);
-    fs::create_dir_all(&subdir).map_err(|e| e.to_string())?;
+    let file = fs::File::create(&dest_path)?;
+    let mut zip = zip::ZipWriter::new(std::io::BufWriter::new(file));
+    let zip_error = |e: zip::result::ZipError| CommandError::Io(format!("Failed to write zip: {}", e));
 
-    // Store file with hash + extension as name (for MIME type detection)
-    // Extract extension from original filename
-    let extension = std::path::Path::new(&filename)
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("");
-    let filename_on_disk = if extension.is_empty() {
-        hash.clone()
-    } else {
-        format!("{}.{}", hash, extension)
+    let mut taken = HashSet::new();
+    let mut manifest = BTreeMap::new();
+    let mut result = ZipExportResult {
+        exported: 0,
+        bytes: 0,
+        missing: Vec::new(),
     };
-    let file_path = subdir.join(&filename_on_disk);
-    fs::write(&file_path, &data).map_err(|e| e.to_string())?;
 
-    // Get file metadata
-    let metadata = fs::metadata(&file_path).map_err(|e| e.to_string())?;
-    let mtime_ms = metadata
-        .modified()
-        .map_err(|e| e.to_string())?
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| e.to_string())?
-        .as_millis() as i64;
+    for blob in &blobs {
+        let path = match locate_blob(&conn, &blob.sha256) {
+            Ok((_, path)) => path,
+            Err(CommandError::Missing(_) | CommandError::NotFound(_)) => {
+                result.missing.push(blob.sha256.clone());
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
 
-    // Insert into database
-    insert_blob(
-        &conn,
-        &hash,
-        data.len() as i64,
-        &mime,
-        mtime_ms,
-        Some(&filename),
-    )
-    .map_err(|e| e.to_string())?;
+        let name = zip_entry_name(blob, &taken);
+        // Already-compressed formats gain nothing from deflate
+        let method = if is_text_like(&blob.mime) {
+            zip::CompressionMethod::Deflated
+        } else {
+            zip::CompressionMethod::Stored
+        };
+        let options = SimpleFileOptions::default()
+            .compression_method(method)
+            .large_file(blob.size > u32::MAX as i64);
 
-    insert_path(&conn, &hash, file_path.to_str().unwrap()).map_err(|e| e.to_string())?;
+        zip.start_file(name.as_str(), options).map_err(zip_error)?;
+        result.bytes += std::io::copy(&mut fs::File::open(path)?, &mut zip)?;
+        result.exported += 1;
 
-    Ok(BlobWithMetadata {
-        sha256: hash,
-        filename: Some(filename),
-        size: data.len() as i64,
-        mime,
-        created_ms: chrono::Utc::now().timestamp_millis(),
-        mtime_ms,
-        path: Some(file_path.to_string_lossy().to_string()),
-        health: Some("healthy".to_string()),
-        page_count: None,
-        image_width: None,
-        image_height: None,
-        line_count: None,
-    })
+        manifest.insert(
+            name.clone(),
+            ZipManifestEntry {
+                sha256: blob.sha256.clone(),
+                size: blob.size,
+                mime: blob.mime.clone(),
+            },
+        );
+        taken.insert(name);
+
+        let _ = app.emit(
+            "blob-export-progress",
+            BlobExportProgress {
+                exported: result.exported,
+                total,
+                sha256: blob.sha256.clone(),
+            },
+        );
+    }
+
+    zip.start_file("manifest.json", SimpleFileOptions::default())
+        .map_err(zip_error)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)?;
+    zip.finish().map_err(zip_error)?.flush()?;
+
+    app_log!(
+        "[Blobs] Exported {} blobs ({} bytes) to {}, {} missing",
+        result.exported,
+        result.bytes,
+        dest_path,
+        result.missing.len()
+    );
+    Ok(result)
 }
 
-/// Delete a blob (database entry only, keeps file on disk)
+/// Get blob storage statistics
 #[tauri::command]
-pub async fn delete_blob(sha256: String) -> Result<(), String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
+pub async fn get_blob_stats() -> CommandResult<serde_json::Value> {
+    let conn = get_connection()?;
+    let stats = get_stats(&conn)?;
+    let by_mime = get_stats_by_mime(&conn)?;
 
-    // Delete from database only - DO NOT delete the actual file
-    // Files are content-addressed and may be referenced elsewhere
-    db_delete_blob(&conn, &sha256).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "totalBlobs": stats.total_blobs,
+        "totalSize": stats.total_size,
+        "byMimeType": by_mime
+    }))
+}
 
-    Ok(())
+/// Read blob file content as string
+#[tauri::command]
+pub async fn read_blob(sha256: String) -> CommandResult<String> {
+    let conn = get_connection()?;
+    let (_, blob_path) = locate_blob(&conn, &sha256)?;
+    Ok(fs::read_to_string(blob_path)?)
 }
 
-/// Rename a blob (update filename in catalog)
+/// Raw blob content plus the MIME type the frontend should render it as
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlobBytes {
+    pub sha256: String,
+    pub mime: String,
+    pub data: Vec<u8>,
+}
+
+/// Base64-encoded blob content, cheaper over IPC than a JSON byte array for large blobs
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlobBase64 {
+    pub sha256: String,
+    pub mime: String,
+    pub data: String,
+}
+
+/// Read a blob's bytes and work out its MIME type
+///
+/// The catalog's MIME wins; uncatalogued files are sniffed, then guessed from the extension.
+fn read_blob_content(sha256: &str) -> CommandResult<(String, Vec<u8>)> {
+    let conn = get_connection()?;
+    let (blob, blob_path) = locate_blob(&conn, sha256)?;
+    let data = fs::read(&blob_path)?;
+    Ok((located_mime(blob, &blob_path), data))
+}
+
+/// Read a blob's raw bytes, for binary content `read_blob` can't decode as text
 #[tauri::command]
-pub async fn rename_blob(sha256: String, filename: String) -> Result<(), String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
-    update_filename(&conn, &sha256, &filename).map_err(|e| e.to_string())
+pub async fn read_blob_bytes(sha256: String) -> CommandResult<BlobBytes> {
+    let (mime, data) = read_blob_content(&sha256)?;
+    Ok(BlobBytes { sha256, mime, data })
 }
 
-/// Scan filesystem for blobs
+/// Read a blob's bytes as base64
 #[tauri::command]
-pub async fn scan_blobs() -> Result<ScanResult, String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
-    let blobs_dir = get_blobs_dir().map_err(|e| e.to_string())?;
+pub async fn read_blob_base64(sha256: String) -> CommandResult<BlobBase64> {
+    use base64::{engine::general_purpose, Engine as _};
 
-    let mut added = 0;
-    let mut updated = 0;
+    let (mime, data) = read_blob_content(&sha256)?;
+    Ok(BlobBase64 {
+        sha256,
+        mime,
+        data: general_purpose::STANDARD.encode(data),
+    })
+}
+
+/// Get the current blob store sharding depth
+#[tauri::command]
+pub async fn get_blob_shard_depth() -> CommandResult<usize> {
+    let conn = get_connection()?;
+    Ok(get_shard_depth(&conn)?)
+}
+
+/// Change the sharding depth and move existing blobs into the new layout
+#[tauri::command]
+pub async fn set_blob_shard_depth(depth: usize) -> CommandResult<ReshardResult> {
+    if !(1..=2).contains(&depth) {
+        return Err(CommandError::InvalidInput(format!(
+            "Invalid shard depth: {} (expected 1 or 2)",
+            depth
+        )));
+    }
+
+    let conn = get_connection()?;
+    let blobs_dir = get_blobs_dir()?;
+
+    // Persist first so new stores land in the new layout even if resharding is interrupted
+    set_setting(&conn, SHARD_DEPTH_KEY, &depth.to_string())?;
+
+    let result = reshard_blobs(&conn, &blobs_dir, depth);
+    app_log!(
+        "[Blobs] Resharded to depth {}: {} moved, {} errors",
+        depth,
+        result.moved,
+        result.errors.len()
+    );
+
+    Ok(result)
+}
+
+/// Move every blob file to its location for the given depth and rewrite its path row
+fn reshard_blobs(conn: &Connection, blobs_dir: &Path, depth: usize) -> ReshardResult {
+    let mut moved = 0;
     let mut errors = Vec::new();
 
-    // Walk through blobs directory
-    for entry in WalkDir::new(&blobs_dir)
+    // Collect first so we don't walk directories we're moving files into
+    let files: Vec<PathBuf> = WalkDir::new(blobs_dir)
         .follow_links(false)
         .into_iter()
         .filter_map(|e| e.ok())
-    {
-        if !entry.file_type().is_file() {
-            continue;
-        }
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
 
-        let path = entry.path();
+    for path in files {
         let file_name = match path.file_name() {
             Some(name) => name.to_string_lossy().to_string(),
             None => continue,
         };
+        let hash = match hash_from_file_name(&file_name) {
+            Some(hash) => hash.to_string(),
+            None => continue,
+        };
 
-        // Skip if filename doesn't look like a hash (64 hex chars)
-        if file_name.len() != 64 || !file_name.chars().all(|c| c.is_ascii_hexdigit()) {
+        let target_dir = shard_dir(blobs_dir, &hash, depth);
+        let target = target_dir.join(&file_name);
+        if target == path {
             continue;
         }
 
-        match process_file_for_scan(&conn, path, &file_name) {
-            Ok(is_new) => {
-                if is_new {
-                    added += 1;
-                } else {
-                    updated += 1;
-                }
-            }
-            Err(e) => {
-                errors.push(format!("Error processing {}: {}", path.display(), e));
-            }
+        let moved_file = fs::create_dir_all(&target_dir)
+            .and_then(|_| fs::rename(&path, &target))
+            .map_err(anyhow::Error::from)
+            .and_then(|_| {
+                update_path(conn, &path.to_string_lossy(), &target.to_string_lossy())
+            });
+
+        match moved_file {
+            Ok(()) => moved += 1,
+            Err(e) => errors.push(format!("Error moving {}: {}", path.display(), e)),
         }
     }
 
-    Ok(ScanResult {
-        added,
-        updated,
-        deleted: 0, // TODO: Track deleted files
+    // Remove shard directories left empty by the move (deepest first)
+    for entry in WalkDir::new(blobs_dir)
+        .min_depth(1)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if entry.file_type().is_dir() {
+            let _ = fs::remove_dir(entry.path());
+        }
+    }
+
+    ReshardResult {
+        depth,
+        moved,
         errors,
-    })
+    }
 }
 
-fn process_file_for_scan(conn: &rusqlite::Connection, path: &Path, hash: &str) -> Result<bool> {
-    // Check if already in database and get existing filename
-    let existing_blob = get_blob_by_hash(conn, hash)?;
-    let existing_filename = existing_blob.as_ref().and_then(|b| b.filename.clone());
+/// Find a blob's file on disk, returning its catalog row (if any) and canonical path
+///
+/// Uses the path recorded in the catalog; if there is no catalog row (or its path is
+/// stale), looks for `<hash>*` in the blob's shard directory, since files are stored
+/// as `<hash>.<ext>`. A catalogued blob whose file is gone is marked 'missing' and
+/// reported as `Missing`; unknown hashes are `NotFound`.
+fn locate_blob(conn: &Connection, sha256: &str) -> CommandResult<(Option<BlobInfo>, PathBuf)> {
+    let catalogued = get_blob_by_hash(conn, sha256)?;
+    if let Some(path) = catalogued
+        .as_ref()
+        .and_then(|blob| blob.path.as_deref())
+        .map(PathBuf::from)
+        .filter(|p| p.is_file())
+    {
+        return Ok((catalogued, path.canonicalize()?));
+    }
 
-    // Get file metadata
-    let metadata = fs::metadata(path)?;
-    let mtime_ms = metadata
-        .modified()?
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_millis() as i64;
+    let blobs_dir = get_blobs_dir()?;
+    let subdir = shard_dir(&blobs_dir, sha256, get_shard_depth(conn)?);
+    let pattern = format!(
+        "{}/{}*",
+        glob::Pattern::escape(&subdir.to_string_lossy()),
+        sha256
+    );
+    let found = glob::glob(&pattern)
+        .map_err(|e| CommandError::Internal(format!("Invalid blob pattern {}: {}", pattern, e)))?
+        .filter_map(|entry| entry.ok())
+        .find(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(hash_from_file_name)
+                    == Some(sha256)
+        });
 
-    // Try to detect MIME from filename first (if available), then fallback to path
-    let mime = if let Some(ref filename) = existing_filename {
-        mime_guess::from_path(filename)
-            .first_or_octet_stream()
-            .to_string()
-    } else {
-        get_mime_type(path)
-    };
+    match (found, catalogued) {
+        (Some(path), catalogued) => Ok((catalogued, path.canonicalize()?)),
+        (None, Some(_)) => {
+            update_health(conn, sha256, "missing")?;
+            Err(CommandError::Missing(format!(
+                "Blob file does not exist on disk: {}",
+                sha256
+            )))
+        }
+        (None, None) => Err(CommandError::NotFound(format!("Blob not found: {}", sha256))),
+    }
+}
 
-    // Insert or update (preserve existing filename if available)
-    insert_blob(
-        conn,
-        hash,
-        metadata.len() as i64,
-        &mime,
-        mtime_ms,
-        existing_filename.as_deref(), // Preserve filename if it exists
-    )?;
+/// MIME type of a located blob: the catalog's if it has a row, else sniffed from the file
+fn located_mime(blob: Option<BlobInfo>, path: &Path) -> String {
+    match blob {
+        Some(blob) => blob.mime,
+        None => infer::get_from_path(path)
+            .ok()
+            .flatten()
+            .map(|t| t.mime_type().to_string())
+            .unwrap_or_else(|| mime_guess::from_path(path).first_or_octet_stream().to_string()),
+    }
+}
 
-    insert_path(conn, hash, path.to_str().unwrap())?;
+/// Get the absolute on-disk path of a blob, e.g. to hand to a native viewer
+#[tauri::command]
+pub async fn resolve_blob_path(sha256: String) -> CommandResult<String> {
+    let conn = get_connection()?;
+    let (_, path) = locate_blob(&conn, &sha256)?;
+    Ok(path.to_string_lossy().to_string())
+}
 
-    Ok(existing_blob.is_none())
+/// Reveal a blob's file in the OS file manager with the file selected
+///
+/// The opener plugin handles the platform differences (Finder `-R`,
+/// Explorer `/select`, FileManager1 over D-Bus with an xdg-open fallback on Linux).
+#[tauri::command]
+pub async fn reveal_blob_in_explorer(sha256: String) -> CommandResult<()> {
+    let conn = get_connection()?;
+    let (_, path) = locate_blob(&conn, &sha256)?;
+
+    tauri_plugin_opener::reveal_item_in_dir(&path)
+        .map_err(|e| CommandError::Io(format!("Failed to reveal {}: {}", path.display(), e)))
 }
 
-/// Health check for blob storage
+/// Open the blob store root directory in the OS file manager
 #[tauri::command]
-pub async fn health_check() -> Result<HealthReport, String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
-    get_stats(&conn).map_err(|e| e.to_string())
+pub async fn open_blob_store_dir() -> CommandResult<String> {
+    let blobs_dir = get_blobs_dir()?;
+
+    tauri_plugin_opener::open_path(&blobs_dir, None::<&str>)
+        .map_err(|e| CommandError::Io(format!("Failed to open {}: {}", blobs_dir.display(), e)))?;
+
+    Ok(blobs_dir.to_string_lossy().to_string())
 }
 
-/// Get blob storage statistics
+/// Data root this session is using (catalog, blobs and caches)
 #[tauri::command]
-pub async fn get_blob_stats() -> Result<serde_json::Value, String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
-    let stats = get_stats(&conn).map_err(|e| e.to_string())?;
+pub async fn get_data_directory() -> CommandResult<String> {
+    Ok(get_data_dir()?.to_string_lossy().to_string())
+}
 
-    Ok(serde_json::json!({
-        "totalBlobs": stats.total_blobs,
-        "totalSize": stats.total_size,
-        "byMimeType": {} // TODO: Implement MIME type breakdown
-    }))
+/// Persist a new data root, or reset to `~/DeepRecall` with `None`
+///
+/// The directory must be writable. The change applies on the next launch: this session
+/// keeps its catalog and blob store, and existing data is not moved, so copy the old
+/// root's contents over before restarting. `DEEPRECALL_DATA_DIR`, when set, still takes
+/// precedence. Returns the root the next session will use.
+#[tauri::command]
+pub async fn set_data_directory(path: Option<String>) -> CommandResult<String> {
+    let config_path = data_dir_config_path()?;
+    match path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(dir) => {
+            let dir = PathBuf::from(dir);
+            if !dir.is_absolute() {
+                return Err(CommandError::InvalidInput(format!(
+                    "Data directory must be an absolute path: {}",
+                    dir.display()
+                )));
+            }
+            ensure_writable_dir(&dir)
+                .map_err(|e| CommandError::PermissionDenied(format!("{:#}", e)))?;
+            if let Some(parent) = config_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&config_path, dir.to_string_lossy().as_bytes())?;
+        }
+        None => {
+            if config_path.exists() {
+                fs::remove_file(&config_path)?;
+            }
+        }
+    }
+
+    if std::env::var_os(DATA_DIR_ENV).is_some_and(|v| !v.is_empty()) {
+        log_warn!("[Blobs] {} is set and overrides the saved data directory", DATA_DIR_ENV);
+    }
+    let data_dir = configured_data_dir()?;
+    app_log!(
+        "[Blobs] Data directory will be {} after a restart (currently {})",
+        data_dir.display(),
+        get_data_dir()?.display()
+    );
+    Ok(data_dir.to_string_lossy().to_string())
 }
 
-/// Read blob file content as string
+/// Priority given to replication jobs queued by `prefetch_blob` (user is about to open the blob)
+const PREFETCH_JOB_PRIORITY: i32 = 10;
+
+/// Thumbnail size `prefetch_blob` primes when the caller doesn't pick one
+const PREFETCH_THUMBNAIL_SIZE: u32 = 256;
+
+/// Warm a blob ahead of opening it
+///
+/// Local blobs are read once so the OS page cache holds them and the first read is fast.
+/// Images and PDFs also get their `get_thumbnail` render cached at `thumbnail_size`
+/// (default 256), which for PDFs is the first page; a failed render is logged, not
+/// returned. Blobs without a file on this device get a pending replication job (unless
+/// one is already queued) so they start downloading. Returns `true` if the blob was local.
+#[tauri::command]
+pub async fn prefetch_blob(sha256: String, thumbnail_size: Option<u32>) -> CommandResult<bool> {
+    let conn = get_connection()?;
+    let blob = get_blob_by_hash(&conn, &sha256)?;
+    let local_path = blob
+        .as_ref()
+        .and_then(|blob| blob.path.as_deref())
+        .map(PathBuf::from)
+        .filter(|p| p.exists());
+
+    if let Some(path) = local_path {
+        let mut file = fs::File::open(&path)?;
+        let bytes = std::io::copy(&mut file, &mut std::io::sink())?;
+        log_debug!("[Blobs] Prefetched {} ({} bytes)", sha256, bytes);
+
+        let mime = located_mime(blob, &path);
+        if mime == "application/pdf" || mime.starts_with("image/") {
+            let size = thumbnail_size.unwrap_or(PREFETCH_THUMBNAIL_SIZE);
+            if let Err(e) = get_thumbnail(sha256.clone(), size).await {
+                log_warn!("[Blobs] Failed to prime thumbnail for {}: {}", sha256, e);
+            }
+        }
+        return Ok(true);
+    }
+
+    let device_id = crate::db::get_or_create_device_id(&conn)?;
+    let client = get_pg_client().await?;
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let queued = client
+        .execute(
+            "INSERT INTO replication_jobs (id, sha256, to_destination, status, priority, created_ms)
+             SELECT $1, $2, $3, 'pending', $4, $5
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM replication_jobs
+                 WHERE sha256 = $2 AND to_destination = $3
+                   AND status IN ('pending', 'in_progress')
+             )",
+            &[
+                &uuid::Uuid::new_v4(),
+                &sha256,
+                &device_id,
+                &PREFETCH_JOB_PRIORITY,
+                &now_ms,
+            ],
+        )
+        .await
+        .map_err(|e| CommandError::Database(format!("Failed to queue replication job: {}", e)))?;
+
+    if queued > 0 {
+        log_debug!("[Blobs] Queued download of {} for prefetch", sha256);
+    }
+    Ok(false)
+}
+
+/// Accepted range for `get_thumbnail`'s `max_dimension`
+const THUMBNAIL_DIMENSIONS: std::ops::RangeInclusive<u32> = 16..=2048;
+/// JPEG quality of generated thumbnails
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/// Directory holding cached thumbnails (outside the blob store so scans skip it)
+fn get_thumbnails_dir() -> Result<PathBuf> {
+    let thumbnails_dir = get_data_dir()?.join("thumbnails");
+    fs::create_dir_all(&thumbnails_dir)?;
+    Ok(thumbnails_dir)
+}
+
+/// Render the first page of a PDF, at most `max_dimension` pixels on either edge
+fn render_pdf_first_page(path: &Path, max_dimension: u32) -> CommandResult<image::DynamicImage> {
+    use pdfium_render::prelude::*;
+
+    let bindings = Pdfium::bind_to_system_library()
+        .map_err(|e| CommandError::Internal(format!("PDF rendering unavailable: {}", e)))?;
+    let pdfium = Pdfium::new(bindings);
+    let pdf_error = |e: PdfiumError| {
+        CommandError::InvalidInput(format!("Failed to render {}: {}", path.display(), e))
+    };
+
+    let document = pdfium.load_pdf_from_file(path, None).map_err(pdf_error)?;
+    let page = document.pages().first().map_err(pdf_error)?;
+    let config = PdfRenderConfig::new()
+        .set_maximum_width(max_dimension as Pixels)
+        .set_maximum_height(max_dimension as Pixels);
+    let bitmap = page.render_with_config(&config).map_err(pdf_error)?;
+
+    image::RgbaImage::from_raw(
+        bitmap.width() as u32,
+        bitmap.height() as u32,
+        bitmap.as_rgba_bytes(),
+    )
+    .map(image::DynamicImage::ImageRgba8)
+    .ok_or_else(|| CommandError::Internal(format!("Bad bitmap for {}", path.display())))
+}
+
+/// Produce JPEG thumbnail bytes for an image or PDF
+fn render_thumbnail(path: &Path, mime: &str, max_dimension: u32) -> CommandResult<Vec<u8>> {
+    let image = if mime == "application/pdf" {
+        render_pdf_first_page(path, max_dimension)?
+    } else if mime.starts_with("image/") {
+        image::ImageReader::open(path)?
+            .with_guessed_format()?
+            .decode()
+            .map_err(|e| {
+                CommandError::InvalidInput(format!("Failed to decode {}: {}", path.display(), e))
+            })?
+    } else {
+        return Err(CommandError::InvalidInput(format!(
+            "No thumbnail support for {}",
+            mime
+        )));
+    };
+
+    let thumbnail = image.thumbnail(max_dimension, max_dimension).to_rgb8();
+    let mut bytes = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, THUMBNAIL_QUALITY)
+        .encode_image(&thumbnail)
+        .map_err(|e| CommandError::Internal(format!("Failed to encode thumbnail: {}", e)))?;
+    Ok(bytes)
+}
+
+/// JPEG thumbnail of an image or PDF blob, longest edge `max_dimension` pixels
+///
+/// Thumbnails are cached as `thumbnails/<hash>_<dim>.jpg` and regenerated when the
+/// blob's file is newer than the cached copy. PDFs are rendered from their first page,
+/// which needs the pdfium library installed.
+#[tauri::command]
+pub async fn get_thumbnail(sha256: String, max_dimension: u32) -> CommandResult<Vec<u8>> {
+    if !THUMBNAIL_DIMENSIONS.contains(&max_dimension) {
+        return Err(CommandError::InvalidInput(format!(
+            "Invalid thumbnail size: {} (expected {} to {})",
+            max_dimension,
+            THUMBNAIL_DIMENSIONS.start(),
+            THUMBNAIL_DIMENSIONS.end()
+        )));
+    }
+
+    let (blob, path) = {
+        let conn = get_connection()?;
+        locate_blob(&conn, &sha256)?
+    };
+    let mime = located_mime(blob, &path);
+    let cache_path = get_thumbnails_dir()?.join(format!("{}_{}.jpg", sha256, max_dimension));
+
+    let source_mtime = fs::metadata(&path)?.modified()?;
+    let cached_mtime = fs::metadata(&cache_path).and_then(|m| m.modified()).ok();
+    if cached_mtime.is_some_and(|cached| cached >= source_mtime) {
+        return Ok(fs::read(&cache_path)?);
+    }
+
+    let bytes = tokio::task::spawn_blocking(move || {
+        render_thumbnail(&path, &mime, max_dimension)
+    })
+    .await
+    .map_err(|e| CommandError::Internal(format!("Thumbnail task failed: {}", e)))??;
+
+    let tmp_path = cache_path.with_extension("jpg.tmp");
+    fs::write(&tmp_path, &bytes)?;
+    fs::rename(&tmp_path, &cache_path)?;
+
+    Ok(bytes)
+}
+
+/// Largest blob `read_blob_data_url` inlines unless the caller overrides it (5 MiB)
+const DEFAULT_DATA_URL_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Read a blob as a `data:<mime>;base64,...` URL for direct use as an `<img>`/`<embed>` src
+///
+/// Blobs larger than `max_bytes` are rejected; load those from their file via
+/// `resolve_blob_path` (or fetch them whole with `read_blob_bytes`) instead.
 #[tauri::command]
-pub async fn read_blob(sha256: String) -> Result<String, String> {
-    let blobs_dir = get_blobs_dir().map_err(|e| e.to_string())?;
-    let prefix = &sha256[0..2];
-    let blob_path = blobs_dir.join(prefix).join(&sha256);
+pub async fn read_blob_data_url(sha256: String, max_bytes: Option<u64>) -> CommandResult<String> {
+    use base64::{engine::general_purpose, Engine as _};
 
-    if !blob_path.exists() {
-        return Err(format!("Blob not found: {}", sha256));
+    let conn = get_connection()?;
+    let (blob, path) = locate_blob(&conn, &sha256)?;
+
+    let limit = max_bytes.unwrap_or(DEFAULT_DATA_URL_MAX_BYTES);
+    let size = fs::metadata(&path)?.len();
+    if size > limit {
+        return Err(CommandError::InvalidInput(format!(
+            "Blob {} is too large to inline ({} bytes, limit {}); use resolve_blob_path or \
+             read_blob_bytes instead",
+            sha256, size, limit
+        )));
     }
 
-    fs::read_to_string(blob_path).map_err(|e| e.to_string())
+    let data = fs::read(&path)?;
+    Ok(format!(
+        "data:{};base64,{}",
+        located_mime(blob, &path),
+        general_purpose::STANDARD.encode(data)
+    ))
+}
+
+/// Get this installation's stable device id, creating it on first run
+#[tauri::command]
+pub async fn get_or_create_device_id() -> CommandResult<String> {
+    let conn = get_connection()?;
+    Ok(crate::db::get_or_create_device_id(&conn)?)
 }
 
 /// Sync blob metadata to Electric (Postgres)
+///
+/// `device_id` defaults to this installation's persisted id.
 #[tauri::command]
-pub async fn sync_blob_to_electric(sha256: String, device_id: String) -> Result<(), String> {
-    use tokio_postgres::NoTls;
-    
+pub async fn sync_blob_to_electric(sha256: String, device_id: Option<String>) -> CommandResult<()> {
     // Get blob info from SQLite catalog
-    let conn = get_connection().map_err(|e| e.to_string())?;
-    let blob = get_blob_by_hash(&conn, &sha256)
-        .map_err(|e| format!("Failed to get blob: {}", e))?
-        .ok_or_else(|| format!("Blob not found: {}", sha256))?;
-
-    // Connect to Postgres
-    let pg_conn_str = "host=localhost port=5432 user=deeprecall password=deeprecall dbname=deeprecall";
-    let (client, connection) = tokio_postgres::connect(pg_conn_str, NoTls)
-        .await
-        .map_err(|e| format!("Failed to connect to Postgres: {}", e))?;
-    
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Postgres connection error: {}", e);
-        }
-    });
+    let conn = get_connection()?;
+    let device_id = match device_id {
+        Some(id) => id,
+        None => crate::db::get_or_create_device_id(&conn)?,
+    };
+    let blob = get_blob_by_hash(&conn, &sha256)?
+        .ok_or_else(|| CommandError::NotFound(format!("Blob not found: {}", sha256)))?;
+
+    let client = get_pg_client().await?;
 
     // Generate UUIDs for new records
     let device_blob_id = uuid::Uuid::new_v4(); // Keep as UUID type
@@ -301,7 +3069,7 @@ pub async fn sync_blob_to_electric(sha256: String, device_id: String) -> Result<
     client.execute(
         meta_query,
         &[&sha256, &(blob.size as i64), &blob.mime, &blob.filename, &now_ms]
-    ).await.map_err(|e| format!("Failed to insert blobs_meta: {}", e))?;
+    ).await.map_err(|e| CommandError::Database(format!("Failed to insert blobs_meta: {}", e)))?;
 
     // Insert into device_blobs (if not exists, update if exists)
     let device_query = r#"
@@ -315,7 +3083,7 @@ pub async fn sync_blob_to_electric(sha256: String, device_id: String) -> Result<
     client.execute(
         device_query,
         &[&device_blob_id, &device_id, &sha256, &true, &"healthy", &now_ms]
-    ).await.map_err(|e| format!("Failed to insert device_blobs: {}", e))?;
+    ).await.map_err(|e| CommandError::Database(format!("Failed to insert device_blobs: {}", e)))?;
 
     println!("✅ Synced blob {} to Electric", sha256);
     
@@ -324,20 +3092,191 @@ pub async fn sync_blob_to_electric(sha256: String, device_id: String) -> Result<
 
 /// Clear all blobs from disk
 #[tauri::command]
-pub async fn clear_all_blobs() -> Result<(), String> {
-    let blobs_dir = get_blobs_dir().map_err(|e| e.to_string())?;
+pub async fn clear_all_blobs() -> CommandResult<()> {
+    let blobs_dir = get_blobs_dir()?;
     
     if blobs_dir.exists() {
-        fs::remove_dir_all(&blobs_dir).map_err(|e| e.to_string())?;
-        fs::create_dir_all(&blobs_dir).map_err(|e| e.to_string())?;
+        fs::remove_dir_all(&blobs_dir)?;
+        fs::create_dir_all(&blobs_dir)?;
     }
 
     // Also clear the SQLite catalog
-    let conn = get_connection().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM blobs", [])
-        .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM paths", [])
-        .map_err(|e| e.to_string())?;
+    let conn = get_connection()?;
+    conn.execute("DELETE FROM blobs", [])?;
+    conn.execute("DELETE FROM paths", [])?;
+    conn.execute("DELETE FROM blobs_fts", [])?;
+    conn.execute("DELETE FROM blob_aliases", [])?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Once;
+
+    /// Point the data root at a fresh directory shared by every test in this run
+    fn use_temp_data_dir() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            let dir = std::env::temp_dir().join(format!("deeprecall-test-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            std::env::set_var(crate::db::DATA_DIR_ENV, &dir);
+        });
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    #[tokio::test]
+    async fn stored_text_blob_reads_back() {
+        use_temp_data_dir();
+        let content = "stored_text_blob_reads_back\nsecond line\n";
+        let conn = get_connection().unwrap();
+        let stored = write_blob_bytes(&conn, "notes.txt", content.as_bytes(), "text/plain").unwrap();
+        assert!(stored.path.as_deref().unwrap().ends_with(".txt"));
+
+        assert_eq!(read_blob(stored.sha256.clone()).await.unwrap(), content);
+        let bytes = read_blob_bytes(stored.sha256.clone()).await.unwrap();
+        assert_eq!(bytes.data, content.as_bytes());
+        assert_eq!(bytes.mime, "text/plain");
+    }
+
+    #[tokio::test]
+    async fn uncatalogued_blob_is_found_by_hash_and_extension() {
+        use_temp_data_dir();
+        let content = b"uncatalogued_blob_is_found_by_hash_and_extension";
+        let hash = sha256_hex(content);
+        let conn = get_connection().unwrap();
+        let subdir = shard_dir(&get_blobs_dir().unwrap(), &hash, get_shard_depth(&conn).unwrap());
+        fs::create_dir_all(&subdir).unwrap();
+        fs::write(subdir.join(format!("{}.txt", hash)), content).unwrap();
+
+        assert_eq!(read_blob(hash.clone()).await.unwrap().as_bytes(), content);
+        let resolved = resolve_blob_path(hash.clone()).await.unwrap();
+        assert!(resolved.ends_with(&format!("{}.txt", hash)));
+    }
+
+    #[tokio::test]
+    async fn unknown_blob_is_not_found() {
+        use_temp_data_dir();
+        let hash = sha256_hex(b"unknown_blob_is_not_found");
+        assert!(matches!(read_blob(hash).await, Err(CommandError::NotFound(_))));
+    }
+
+    fn pdf_bytes(marker: &str) -> Vec<u8> {
+        format!("%PDF-1.4\n% {}\n%%EOF\n", marker).into_bytes()
+    }
+
+    #[test]
+    fn scan_keeps_stored_pdf_catalogued_once() {
+        use_temp_data_dir();
+        let _guard = CATALOG_LOCK.lock().unwrap();
+        let conn = get_connection().unwrap();
+        let stored = write_blob_bytes(
+            &conn,
+            "paper.pdf",
+            &pdf_bytes("scan_keeps_stored_pdf_catalogued_once"),
+            "application/pdf",
+        )
+        .unwrap();
+
+        let result = scan_blob_store(&conn).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let blob = get_blob_by_hash(&conn, &stored.sha256).unwrap().unwrap();
+        assert_eq!(blob.mime, "application/pdf");
+        assert_eq!(blob.filename.as_deref(), Some("paper.pdf"));
+        assert_eq!(blob.path, stored.path);
+        assert_eq!(get_paths_for_hash(&conn, &stored.sha256).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn scan_indexes_pdf_dropped_into_the_store() {
+        use_temp_data_dir();
+        let _guard = CATALOG_LOCK.lock().unwrap();
+        let content = pdf_bytes("scan_indexes_pdf_dropped_into_the_store");
+        let hash = sha256_hex(&content);
+        let conn = get_connection().unwrap();
+        let subdir = shard_dir(&get_blobs_dir().unwrap(), &hash, get_shard_depth(&conn).unwrap());
+        fs::create_dir_all(&subdir).unwrap();
+        fs::write(subdir.join(format!("{}.pdf", hash)), &content).unwrap();
+
+        let result = scan_blob_store(&conn).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let blob = get_blob_by_hash(&conn, &hash).unwrap().unwrap();
+        assert_eq!(blob.mime, "application/pdf");
+        assert_eq!(blob.size, content.len() as i64);
+        assert!(blob.path.unwrap().ends_with(&format!("{}.pdf", hash)));
+    }
+
+    #[test]
+    fn scan_follows_a_moved_blob_file() {
+        use_temp_data_dir();
+        let _guard = CATALOG_LOCK.lock().unwrap();
+        let conn = get_connection().unwrap();
+        let stored = write_blob_bytes(
+            &conn,
+            "moved.txt",
+            b"scan_follows_a_moved_blob_file",
+            "text/plain",
+        )
+        .unwrap();
+        let old_path = PathBuf::from(stored.path.clone().unwrap());
+        let new_dir = get_blobs_dir().unwrap().join("moved");
+        fs::create_dir_all(&new_dir).unwrap();
+        let new_path = new_dir.join(old_path.file_name().unwrap());
+        fs::rename(&old_path, &new_path).unwrap();
+
+        let result = scan_blob_store(&conn).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert!(result.relocated >= 1);
+        assert!(result.deleted >= 1);
+
+        let blob = get_blob_by_hash(&conn, &stored.sha256).unwrap().unwrap();
+        assert_eq!(blob.health.as_deref(), Some("relocated"));
+        assert_eq!(blob.path.as_deref(), new_path.to_str());
+        assert_eq!(
+            get_paths_for_hash(&conn, &stored.sha256).unwrap(),
+            vec![new_path.to_string_lossy().to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn device_blobs_rows_keep_a_blob_referenced() {
+        let schema = "deeprecall_test_referenced_hashes";
+        let Some(client) = crate::commands::database::test_pg_client(schema).await else {
+            eprintln!("DEEPRECALL_TEST_DATABASE_URL not set; skipping");
+            return;
+        };
+        client
+            .batch_execute(
+                "CREATE TABLE assets (id UUID PRIMARY KEY, sha256 TEXT);
+                CREATE TABLE device_blobs (id UUID PRIMARY KEY, device_id TEXT, sha256 TEXT);
+                CREATE TABLE annotations (id TEXT PRIMARY KEY, sha256 TEXT);
+                CREATE TABLE cards (id UUID PRIMARY KEY, sha256 TEXT);
+                CREATE TABLE authors (
+                    id UUID PRIMARY KEY,
+                    avatar_original_path TEXT,
+                    avatar_display_path TEXT
+                );
+                INSERT INTO assets VALUES ('00000000-0000-0000-0000-000000000001', 'aaa');
+                INSERT INTO device_blobs
+                VALUES ('00000000-0000-0000-0000-000000000002', 'laptop', 'bbb');",
+            )
+            .await
+            .unwrap();
+
+        let referenced = referenced_hashes(&client).await.unwrap();
+        assert!(referenced.contains("aaa"));
+        assert!(referenced.contains("bbb"), "device_blobs should count as a reference");
+        assert_eq!(referenced.len(), 2);
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {} CASCADE", schema))
+            .await
+            .unwrap();
+    }
+}
@@ -18,10 +18,14 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use postgres_types::Json;
 use tokio_postgres::Client;
 use tokio_postgres_rustls::MakeRustlsConnect;
 use rustls::ClientConfig;
+use crate::error::{CommandError, CommandResult};
+use tauri::{AppHandle, Emitter};
 
 /**
  * Get Postgres configuration from environment variables
@@ -57,22 +61,232 @@ fn get_pg_config() -> (String, u16, String, String, String, bool) {
     (host, port, user, password, database, ssl)
 }
 
+/// Prefix of the `application_name` reported to Postgres
+const APPLICATION_NAME: &str = "deeprecall-desktop";
+
+/**
+ * `application_name` for this instance, so its sessions show up in `pg_stat_activity`
+ * 
+ * Includes the catalog's device id when it can be read.
+ */
+fn application_name() -> String {
+    let device_id = crate::db::get_connection()
+        .and_then(|conn| crate::db::get_or_create_device_id(&conn));
+    match device_id {
+        Ok(id) => format!("{}:{}", APPLICATION_NAME, id),
+        Err(_) => APPLICATION_NAME.to_string(),
+    }
+}
+
+lazy_static! {
+    /// Set DEEPRECALL_LOG_SQL=1 to log every dynamic statement through the app logger
+    static ref SQL_LOGGING: bool = env::var("DEEPRECALL_LOG_SQL")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+}
+
+/**
+ * Log a statement and its parameter count when SQL logging is enabled
+ */
+fn log_statement(sql: &str, param_count: usize) {
+    if *SQL_LOGGING {
+        crate::app_log!("[SQL] {} ({} params)", sql, param_count);
+    }
+}
+
+/// Pooled Postgres connection; derefs to `tokio_postgres::Client`
+pub(crate) type PgClient = deadpool_postgres::Object;
+
+/// Default number of pooled connections (override with VITE_POSTGRES_POOL_SIZE)
+const DEFAULT_POOL_SIZE: usize = 8;
+/// Default idle time before a pooled connection is closed, in seconds
+/// (override with VITE_POSTGRES_POOL_IDLE_TIMEOUT)
+const DEFAULT_POOL_IDLE_TIMEOUT_SECS: u64 = 300;
+
+/**
+ * Read a numeric pool setting from the runtime or compile-time environment
+ */
+fn pool_setting<T: std::str::FromStr>(name: &str, bundled: Option<&str>, default: T) -> T {
+    env::var(name)
+        .ok()
+        .or_else(|| bundled.map(String::from))
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+lazy_static! {
+    static ref POOL_SIZE: usize = pool_setting(
+        "VITE_POSTGRES_POOL_SIZE",
+        option_env!("VITE_POSTGRES_POOL_SIZE"),
+        DEFAULT_POOL_SIZE,
+    )
+    .max(1);
+    static ref POOL_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(pool_setting(
+        "VITE_POSTGRES_POOL_IDLE_TIMEOUT",
+        option_env!("VITE_POSTGRES_POOL_IDLE_TIMEOUT"),
+        DEFAULT_POOL_IDLE_TIMEOUT_SECS,
+    ));
+    static ref PG_POOL: Result<deadpool_postgres::Pool, String> = build_pg_pool();
+}
+
+/// Whether the idle-connection reaper has been started
+static POOL_REAPER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Default retries for a transient failure (override with VITE_POSTGRES_MAX_RETRIES)
+const DEFAULT_MAX_RETRIES: u32 = 4;
+/// First backoff delay; doubled after every failed attempt
+const INITIAL_BACKOFF_MS: u64 = 200;
+/// Upper bound for a single backoff delay
+const MAX_BACKOFF_MS: u64 = 5_000;
+
+lazy_static! {
+    static ref MAX_RETRIES: u32 = pool_setting(
+        "VITE_POSTGRES_MAX_RETRIES",
+        option_env!("VITE_POSTGRES_MAX_RETRIES"),
+        DEFAULT_MAX_RETRIES,
+    );
+}
+
+/**
+ * Backoff before the given retry (1-based): doubles from `INITIAL_BACKOFF_MS`, capped
+ */
+fn backoff_delay(retry: u32) -> std::time::Duration {
+    let ms = INITIAL_BACKOFF_MS.saturating_mul(1 << retry.saturating_sub(1).min(16));
+    std::time::Duration::from_millis(ms.min(MAX_BACKOFF_MS))
+}
+
+/**
+ * Whether a Postgres error is worth retrying
+ * 
+ * Connection failures, serialization failures, deadlocks and "too many connections"
+ * usually succeed on a later attempt. Constraint violations and other permanent
+ * errors would fail the same way again.
+ */
+fn is_transient_pg_error(e: &tokio_postgres::Error) -> bool {
+    use tokio_postgres::error::SqlState;
+    
+    if let Some(db) = e.as_db_error() {
+        let code = db.code();
+        // Class 08: connection exception
+        return code.code().starts_with("08")
+            || *code == SqlState::T_R_SERIALIZATION_FAILURE
+            || *code == SqlState::T_R_DEADLOCK_DETECTED
+            || *code == SqlState::TOO_MANY_CONNECTIONS
+            || *code == SqlState::ADMIN_SHUTDOWN
+            || *code == SqlState::CANNOT_CONNECT_NOW;
+    }
+    
+    // No server error: the connection itself failed (reset, closed, timed out)
+    e.is_closed() || std::error::Error::source(e).is_some_and(|s| s.is::<std::io::Error>())
+}
+
+/**
+ * Whether a pool checkout failure is worth retrying
+ */
+fn is_transient_pool_error(e: &deadpool_postgres::PoolError) -> bool {
+    match e {
+        deadpool_postgres::PoolError::Timeout(_) => true,
+        deadpool_postgres::PoolError::Backend(e) => is_transient_pg_error(e),
+        _ => false,
+    }
+}
+
+/**
+ * Get a connection from the shared pool, retrying with backoff if Postgres is unreachable
+ * 
+ * Connections are opened lazily and reused across commands, so the TCP+TLS handshake
+ * is only paid when the pool grows. Closed connections are dropped on checkout and
+ * replaced, so a network blip doesn't poison later queries.
+ */
+pub(crate) async fn get_pg_client() -> CommandResult<PgClient> {
+    let pool = PG_POOL.as_ref().map_err(|e| CommandError::Offline(e.clone()))?;
+    
+    let mut retries = 0;
+    let client = loop {
+        match pool.get().await {
+            Ok(client) => {
+                if retries > 0 {
+                    println!("[Database] Connected after {} retries", retries);
+                }
+                break client;
+            }
+            Err(e) if retries < *MAX_RETRIES && is_transient_pool_error(&e) => {
+                retries += 1;
+                let delay = backoff_delay(retries);
+                println!(
+                    "[Database] Connect failed: {} (retry {}/{} in {}ms)",
+                    e, retries, *MAX_RETRIES, delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                return Err(CommandError::Offline(format!(
+                    "Failed to connect to Postgres after {} retries: {}",
+                    retries, e
+                )))
+            }
+        }
+    };
+    
+    if !POOL_REAPER_STARTED.swap(true, Ordering::SeqCst) {
+        tokio::spawn(reap_idle_connections(pool.clone()));
+    }
+    
+    if !SCHEMA_CHECKED.swap(true, Ordering::SeqCst) {
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            match pool.get().await {
+                Ok(checked) => log_schema_report(&check_pg_schema(&checked).await),
+                Err(e) => println!("[Database] Skipping schema check: {}", e),
+            }
+        });
+    }
+    
+    Ok(client)
+}
+
+/**
+ * Periodically close connections that have been idle longer than the configured timeout
+ */
+async fn reap_idle_connections(pool: deadpool_postgres::Pool) {
+    let idle_timeout = *POOL_IDLE_TIMEOUT;
+    let mut interval = tokio::time::interval(idle_timeout.max(std::time::Duration::from_secs(1)) / 2);
+    loop {
+        interval.tick().await;
+        pool.retain(|_, metrics| metrics.last_used() < idle_timeout);
+    }
+}
+
 /**
- * Create a new Postgres client connection with SSL support
+ * Build the shared Postgres pool with SSL support
+ * 
+ * No connection is opened here; the pool connects on first checkout.
  */
-async fn get_pg_client() -> Result<Client, String> {
+fn build_pg_pool() -> Result<deadpool_postgres::Pool, String> {
     let (host, port, user, password, database, use_ssl) = get_pg_config();
     
     // Log connection details (without password)
-    println!("[Database] Connecting to: {}:{}/{} (SSL: {})", host, port, database, use_ssl);
+    println!(
+        "[Database] Pooling connections to: {}:{}/{} (SSL: {}, size: {})",
+        host, port, database, use_ssl, *POOL_SIZE
+    );
+    
+    let conn_str = format!(
+        "host={} port={} user={} password={} dbname={} sslmode={} application_name='{}'",
+        host, port, user, password, database,
+        if use_ssl { "require" } else { "disable" },
+        application_name()
+    );
+    let pg_config: tokio_postgres::Config = conn_str
+        .parse()
+        .map_err(|e| format!("Invalid Postgres configuration: {}", e))?;
+    
+    let manager_config = deadpool_postgres::ManagerConfig {
+        recycling_method: deadpool_postgres::RecyclingMethod::Fast,
+    };
     
-    if use_ssl {
+    let manager = if use_ssl {
         // SSL connection for cloud databases (Neon, etc.)
-        let conn_str = format!(
-            "host={} port={} user={} password={} dbname={} sslmode=require",
-            host, port, user, password, database
-        );
-        
         let mut root_store = rustls::RootCertStore::empty();
         root_store.extend(
             webpki_roots::TLS_SERVER_ROOTS
@@ -85,41 +299,26 @@ async fn get_pg_client() -> Result<Client, String> {
             .with_no_client_auth();
         
         let tls = MakeRustlsConnect::new(config);
-        
-        let (client, connection) = tokio_postgres::connect(&conn_str, tls)
-            .await
-            .map_err(|e| format!("Failed to connect to Postgres (SSL): {}", e))?;
-        
-        // Spawn connection handler
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("Postgres connection error: {}", e);
-            }
-        });
-        
-        Ok(client)
+        deadpool_postgres::Manager::from_config(pg_config, tls, manager_config)
     } else {
         // No SSL for local development
-        let conn_str = format!(
-            "host={} port={} user={} password={} dbname={}",
-            host, port, user, password, database
-        );
-        
-        let (client, connection) = tokio_postgres::connect(&conn_str, tokio_postgres::NoTls)
-            .await
-            .map_err(|e| format!("Failed to connect to Postgres: {}", e))?;
-        
-        // Spawn connection handler
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("Postgres connection error: {}", e);
-            }
-        });
-        
-        Ok(client)
-    }
+        deadpool_postgres::Manager::from_config(pg_config, tokio_postgres::NoTls, manager_config)
+    };
+    
+    deadpool_postgres::Pool::builder(manager)
+        .max_size(*POOL_SIZE)
+        .build()
+        .map_err(|e| format!("Failed to create Postgres pool: {}", e))
 }
 
+/// Tables commands may read from or write to (security: table names are interpolated
+/// into SQL). Shared by the admin/read commands and `flush_writes`.
+const SYNC_TABLES: &[&str] = &[
+    "works", "assets", "activities", "collections", "edges", "folder_sources", "presets",
+    "authors", "annotations", "cards", "review_logs", "boards", "strokes",
+    "blobs_meta", "device_blobs"
+];
+
 /**
  * Write operation types
  */
@@ -143,6 +342,12 @@ pub struct WriteChange {
     pub created_at: i64,
     pub status: String,
     pub retry_count: i32,
+    /// Merge JSONB object columns into the stored value (`col || $n`) instead of replacing them
+    #[serde(default)]
+    pub jsonb_merge: bool,
+    /// Physically delete the row even if the table supports tombstones
+    #[serde(default)]
+    pub hard_delete: bool,
 }
 
 /**
@@ -158,6 +363,43 @@ pub struct WriteResult {
     pub error: Option<String>,
 }
 
+/**
+ * A failed write, flagged when applying the same change again could succeed
+ */
+#[derive(Debug)]
+struct WriteError {
+    message: String,
+    transient: bool,
+}
+
+impl WriteError {
+    /// Wrap a Postgres error with context, classifying it as transient or permanent
+    fn pg(context: &str, e: tokio_postgres::Error) -> Self {
+        WriteError {
+            transient: is_transient_pg_error(&e),
+            message: format!("{}: {}", context, e),
+        }
+    }
+}
+
+impl From<String> for WriteError {
+    fn from(message: String) -> Self {
+        WriteError { message, transient: false }
+    }
+}
+
+impl From<&str> for WriteError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
 // JSONB columns that need special handling
 lazy_static! {
     static ref JSONB_COLUMNS: std::collections::HashSet<&'static str> = {
@@ -175,6 +417,235 @@ lazy_static! {
     };
 }
 
+/**
+ * How a table identifies a row when resolving write conflicts
+ */
+struct ConflictKey {
+    /// Unique key columns: the ON CONFLICT target and the WHERE clause for updates
+    columns: &'static [&'static str],
+    /// Epoch-ms column compared for LWW; `None` means inserts of an existing row are no-ops
+    timestamp_column: Option<&'static str>,
+}
+
+/// Conflict key for tables not listed in `CONFLICT_KEYS`
+const DEFAULT_CONFLICT_KEY: ConflictKey = ConflictKey {
+    columns: &["id"],
+    timestamp_column: Some("updated_at"),
+};
+
+/// Tables whose conflict key differs from `id`/`updated_at`
+const CONFLICT_KEYS: &[(&str, ConflictKey)] = &[
+    // Content-addressed: the same hash always describes the same blob
+    ("blobs_meta", ConflictKey { columns: &["sha256"], timestamp_column: None }),
+    // One presence row per device per blob
+    ("device_blobs", ConflictKey { columns: &["device_id", "sha256"], timestamp_column: None }),
+];
+
+/**
+ * What an insert does when a row with the same conflict key already exists
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// Keep the existing row (`DO NOTHING`)
+    Skip,
+    /// Replace the existing row's columns with the inserted values
+    Overwrite,
+}
+
+impl ConflictKey {
+    /// Default insert behavior: LWW tables overwrite, tables without a timestamp skip
+    fn on_conflict(&self) -> OnConflict {
+        match self.timestamp_column {
+            Some(_) => OnConflict::Overwrite,
+            None => OnConflict::Skip,
+        }
+    }
+}
+
+fn conflict_key(table: &str) -> &'static ConflictKey {
+    CONFLICT_KEYS
+        .iter()
+        .find(|(name, _)| *name == table)
+        .map(|(_, key)| key)
+        .unwrap_or(&DEFAULT_CONFLICT_KEY)
+}
+
+/**
+ * `a = $n AND b = $n+1 ...` over the conflict key columns, numbering from `first_param`
+ */
+fn key_predicate(key: &ConflictKey, first_param: usize) -> String {
+    key.columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| format!("{} = ${}", col, first_param + i))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/**
+ * Column metadata from information_schema
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// Postgres type name as reported by `udt_name` (e.g. `uuid`, `jsonb`, `_text`)
+    pub data_type: String,
+    pub is_nullable: bool,
+    pub has_default: bool,
+    pub is_primary_key: bool,
+}
+
+lazy_static! {
+    static ref COLUMN_CACHE: std::sync::Mutex<HashMap<String, Arc<Vec<ColumnInfo>>>> =
+        std::sync::Mutex::new(HashMap::new());
+}
+
+/**
+ * Get the columns of a table, cached for the lifetime of the app
+ */
+async fn get_table_columns(client: &Client, table: &str) -> Result<Arc<Vec<ColumnInfo>>, String> {
+    if let Some(columns) = COLUMN_CACHE.lock().unwrap().get(table) {
+        return Ok(columns.clone());
+    }
+    
+    fetch_table_columns(client, table).await
+}
+
+/**
+ * Fetch the columns of a table from information_schema, refreshing the cache
+ */
+async fn fetch_table_columns(client: &Client, table: &str) -> Result<Arc<Vec<ColumnInfo>>, String> {
+    let rows = client
+        .query(
+            "SELECT c.column_name::text, c.udt_name::text, c.is_nullable = 'YES',
+                    c.column_default IS NOT NULL,
+                    EXISTS (
+                        SELECT 1
+                        FROM information_schema.table_constraints tc
+                        JOIN information_schema.key_column_usage kcu
+                          ON kcu.constraint_name = tc.constraint_name
+                         AND kcu.table_schema = tc.table_schema
+                        WHERE tc.constraint_type = 'PRIMARY KEY'
+                          AND tc.table_schema = c.table_schema
+                          AND tc.table_name = c.table_name
+                          AND kcu.column_name = c.column_name
+                    )
+             FROM information_schema.columns c
+             WHERE c.table_schema = current_schema() AND c.table_name = $1
+             ORDER BY c.ordinal_position",
+            &[&table],
+        )
+        .await
+        .map_err(|e| format!("Failed to read columns for {}: {}", table, e))?;
+    
+    let columns: Arc<Vec<ColumnInfo>> = Arc::new(
+        rows.iter()
+            .map(|row| ColumnInfo {
+                name: row.get(0),
+                data_type: row.get(1),
+                is_nullable: row.get(2),
+                has_default: row.get(3),
+                is_primary_key: row.get(4),
+            })
+            .collect(),
+    );
+    
+    COLUMN_CACHE
+        .lock()
+        .unwrap()
+        .insert(table.to_string(), columns.clone());
+    
+    Ok(columns)
+}
+
+/**
+ * Look up a column's `udt_name` in cached table metadata
+ */
+fn column_type<'a>(columns: &'a [ColumnInfo], name: &str) -> Option<&'a str> {
+    columns.iter().find(|c| c.name == name).map(|c| c.data_type.as_str())
+}
+
+/**
+ * Whether a name is safe to interpolate into SQL as an identifier (`^[a-z_][a-z0-9_]*$`)
+ * 
+ * Column names come from client payload keys and can't be bound as parameters, so
+ * anything else (quotes, spaces, semicolons, uppercase) is rejected outright.
+ */
+fn is_safe_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/**
+ * Validate payload columns against the table schema before issuing SQL
+ * 
+ * Rejects column names that aren't plain identifiers, unknown columns and, for inserts,
+ * missing NOT NULL columns without a default. A cache miss on an unknown column triggers
+ * one refetch in case the schema was migrated.
+ */
+async fn validate_payload_columns(
+    client: &Client,
+    table: &str,
+    data: &HashMap<String, Value>,
+    is_insert: bool,
+) -> Result<(), String> {
+    if let Some(invalid) = data.keys().find(|key| !is_safe_identifier(key)) {
+        return Err(format!("invalid column name {:?} for table {}", invalid, table));
+    }
+    
+    let mut columns = get_table_columns(client, table).await?;
+    if columns.is_empty() {
+        // Table not visible through information_schema; let Postgres report errors
+        return Ok(());
+    }
+    
+    if unknown_column(data, &columns).is_some() {
+        columns = fetch_table_columns(client, table).await?;
+    }
+    check_payload_columns(table, data, &columns, is_insert)
+}
+
+/**
+ * First payload key that isn't a column of the table
+ */
+fn unknown_column<'a>(data: &'a HashMap<String, Value>, columns: &[ColumnInfo]) -> Option<&'a String> {
+    data.keys().find(|key| !columns.iter().any(|c| &c.name == *key))
+}
+
+/**
+ * Check payload keys against known table columns (see `validate_payload_columns`)
+ */
+fn check_payload_columns(
+    table: &str,
+    data: &HashMap<String, Value>,
+    columns: &[ColumnInfo],
+    is_insert: bool,
+) -> Result<(), String> {
+    if let Some(invalid) = data.keys().find(|key| !is_safe_identifier(key)) {
+        return Err(format!("invalid column name {:?} for table {}", invalid, table));
+    }
+    if let Some(unknown) = unknown_column(data, columns) {
+        return Err(format!("unknown column {} for table {}", unknown, table));
+    }
+    
+    if is_insert {
+        for column in columns.iter() {
+            let required = !column.is_nullable && !column.has_default;
+            let provided = data.get(&column.name).map(|v| !v.is_null()).unwrap_or(false);
+            if required && !provided {
+                return Err(format!(
+                    "missing required column {} for table {}",
+                    column.name, table
+                ));
+            }
+        }
+    }
+    
+    Ok(())
+}
+
 /**
  * Convert camelCase to snake_case
  */
@@ -308,6 +779,157 @@ fn transform_annotation_data(validated: &Value) -> Result<Value, String> {
     }
 }
 
+/**
+ * Postgres INTERVAL in its wire representation, exchanged with JS as an ISO-8601 duration
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PgInterval {
+    months: i32,
+    days: i32,
+    microseconds: i64,
+}
+
+impl PgInterval {
+    /// Parse `PnYnMnWnDTnHnMnS` (components optional, seconds may be fractional)
+    fn parse_iso8601(s: &str) -> Option<PgInterval> {
+        let rest = s.trim().strip_prefix('P')?;
+        let (date_part, time_part) = match rest.split_once('T') {
+            // A `T` must introduce at least one time component
+            Some((_, "")) => return None,
+            Some((date, time)) => (date, Some(time)),
+            None => (rest, None),
+        };
+        
+        let mut interval = PgInterval { months: 0, days: 0, microseconds: 0 };
+        let mut seen_any = false;
+        
+        for (number, unit) in Self::components(date_part)? {
+            let n: i32 = number.parse().ok()?;
+            // Anything that overflows Postgres' i32 fields is not a valid interval
+            match unit {
+                'Y' => interval.months = interval.months.checked_add(n.checked_mul(12)?)?,
+                'M' => interval.months = interval.months.checked_add(n)?,
+                'W' => interval.days = interval.days.checked_add(n.checked_mul(7)?)?,
+                'D' => interval.days = interval.days.checked_add(n)?,
+                _ => return None,
+            }
+            seen_any = true;
+        }
+        
+        if let Some(time) = time_part {
+            for (number, unit) in Self::components(time)? {
+                let micros_per_unit: f64 = match unit {
+                    'H' => 3_600_000_000.0,
+                    'M' => 60_000_000.0,
+                    'S' => 1_000_000.0,
+                    _ => return None,
+                };
+                let n: f64 = number.parse().ok()?;
+                let micros = (n * micros_per_unit).round();
+                if !micros.is_finite() || micros.abs() >= i64::MAX as f64 {
+                    return None;
+                }
+                interval.microseconds = interval.microseconds.checked_add(micros as i64)?;
+                seen_any = true;
+            }
+        }
+        
+        seen_any.then_some(interval)
+    }
+    
+    /// Split `12D3H` style text into (number, unit) pairs
+    fn components(s: &str) -> Option<Vec<(&str, char)>> {
+        let mut parts = Vec::new();
+        let mut start = 0;
+        for (idx, ch) in s.char_indices() {
+            if ch.is_ascii_alphabetic() {
+                if idx == start {
+                    return None;
+                }
+                parts.push((&s[start..idx], ch));
+                start = idx + 1;
+            }
+        }
+        (start == s.len()).then_some(parts)
+    }
+    
+    fn to_iso8601(self) -> String {
+        let mut out = String::from("P");
+        let years = self.months / 12;
+        let months = self.months % 12;
+        if years != 0 {
+            out.push_str(&format!("{}Y", years));
+        }
+        if months != 0 {
+            out.push_str(&format!("{}M", months));
+        }
+        if self.days != 0 {
+            out.push_str(&format!("{}D", self.days));
+        }
+        
+        if self.microseconds != 0 {
+            let hours = self.microseconds / 3_600_000_000;
+            let minutes = (self.microseconds % 3_600_000_000) / 60_000_000;
+            let micros = self.microseconds % 60_000_000;
+            out.push('T');
+            if hours != 0 {
+                out.push_str(&format!("{}H", hours));
+            }
+            if minutes != 0 {
+                out.push_str(&format!("{}M", minutes));
+            }
+            if micros != 0 {
+                if micros % 1_000_000 == 0 {
+                    out.push_str(&format!("{}S", micros / 1_000_000));
+                } else {
+                    let seconds = format!("{:.6}", micros as f64 / 1_000_000.0);
+                    out.push_str(seconds.trim_end_matches('0'));
+                    out.push('S');
+                }
+            }
+        }
+        
+        if out == "P" {
+            out.push_str("0D");
+        }
+        out
+    }
+}
+
+impl<'a> postgres_types::FromSql<'a> for PgInterval {
+    fn from_sql(
+        _ty: &postgres_types::Type,
+        raw: &'a [u8],
+    ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        if raw.len() != 16 {
+            return Err(format!("Invalid interval length: {}", raw.len()).into());
+        }
+        Ok(PgInterval {
+            microseconds: i64::from_be_bytes(raw[0..8].try_into()?),
+            days: i32::from_be_bytes(raw[8..12].try_into()?),
+            months: i32::from_be_bytes(raw[12..16].try_into()?),
+        })
+    }
+    
+    postgres_types::accepts!(INTERVAL);
+}
+
+impl postgres_types::ToSql for PgInterval {
+    fn to_sql(
+        &self,
+        _ty: &postgres_types::Type,
+        out: &mut bytes::BytesMut,
+    ) -> Result<postgres_types::IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        out.extend_from_slice(&self.microseconds.to_be_bytes());
+        out.extend_from_slice(&self.days.to_be_bytes());
+        out.extend_from_slice(&self.months.to_be_bytes());
+        Ok(postgres_types::IsNull::No)
+    }
+    
+    postgres_types::accepts!(INTERVAL);
+    postgres_types::to_sql_checked!();
+}
+
 /**
  * Convert Postgres row to JSON object
  * Handles different column types properly
@@ -346,6 +968,11 @@ fn row_to_json(row: &tokio_postgres::Row) -> Value {
                 let json_val: Option<serde_json::Value> = row.get(i);
                 json_val.unwrap_or(Value::Null)
             }
+            Type::JSONB_ARRAY | Type::JSON_ARRAY => {
+                let arr_val: Option<Vec<Option<serde_json::Value>>> = row.get(i);
+                arr_val.map(|arr| Value::Array(arr.into_iter().map(|v| v.unwrap_or(Value::Null)).collect()))
+                    .unwrap_or(Value::Null)
+            }
             Type::TEXT_ARRAY | Type::VARCHAR_ARRAY => {
                 let arr_val: Option<Vec<String>> = row.get(i);
                 arr_val.map(|arr| Value::Array(arr.into_iter().map(Value::String).collect()))
@@ -361,30 +988,96 @@ fn row_to_json(row: &tokio_postgres::Row) -> Value {
                 float_val.and_then(|f| serde_json::Number::from_f64(f).map(Value::Number))
                     .unwrap_or(Value::Null)
             }
-            _ => {
-                // Fallback: try as string
-                let str_val: Option<String> = row.get(i);
-                str_val.map(Value::String).unwrap_or(Value::Null)
+            Type::DATE => {
+                let date_val: Option<chrono::NaiveDate> = row.get(i);
+                date_val.map(|d| Value::String(d.format("%Y-%m-%d").to_string()))
+                    .unwrap_or(Value::Null)
             }
-        };
-        
-        result.insert(column_name, value);
-    }
+            Type::TIMESTAMPTZ => {
+                let ts_val: Option<chrono::DateTime<chrono::Utc>> = row.get(i);
+                ts_val.map(|ts| Value::String(ts.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)))
+                    .unwrap_or(Value::Null)
+            }
+            Type::TIMESTAMP => {
+                let ts_val: Option<chrono::NaiveDateTime> = row.get(i);
+                ts_val.map(|ts| Value::String(ts.format("%Y-%m-%dT%H:%M:%S%.3f").to_string()))
+                    .unwrap_or(Value::Null)
+            }
+            Type::INTERVAL => {
+                let interval_val: Option<PgInterval> = row.get(i);
+                interval_val.map(|iv| Value::String(iv.to_iso8601())).unwrap_or(Value::Null)
+            }
+            Type::BYTEA => {
+                use base64::{engine::general_purpose, Engine as _};
+                
+                let bytes_val: Option<Vec<u8>> = row.get(i);
+                bytes_val.map(|b| Value::String(general_purpose::STANDARD.encode(b)))
+                    .unwrap_or(Value::Null)
+            }
+            Type::NUMERIC => {
+                // Emitted as a string: a JSON number would be parsed as an f64 on the
+                // frontend and silently lose digits beyond ~15 significant figures.
+                // Values outside Decimal's 28-digit range (and NaN) can't be read at all.
+                match row.try_get::<_, Option<rust_decimal::Decimal>>(i) {
+                    Ok(dec_val) => dec_val.map(|d| Value::String(d.to_string())).unwrap_or(Value::Null),
+                    Err(e) => {
+                        println!("[Database] Unreadable NUMERIC in column {}: {}", column_name, e);
+                        Value::Null
+                    }
+                }
+            }
+            _ => {
+                // Fallback: try as string
+                let str_val: Option<String> = row.get(i);
+                str_val.map(Value::String).unwrap_or(Value::Null)
+            }
+        };
+        
+        result.insert(column_name, value);
+    }
     
     Value::Object(result)
 }
 
+/**
+ * Parse a decimal literal, accepting exponent notation ("1.5e-3")
+ */
+fn parse_decimal(s: &str) -> Option<rust_decimal::Decimal> {
+    use std::str::FromStr;
+    
+    rust_decimal::Decimal::from_str(s)
+        .or_else(|_| rust_decimal::Decimal::from_scientific(s))
+        .ok()
+}
+
 /**
  * Convert JSON value to typed Postgres parameter
- * Handles: UUID, UUID[], text[], JSONB, integers, booleans, strings
+ * Handles: UUID, UUID[], text[], JSONB, JSONB[], NUMERIC, BYTEA (base64), integers,
+ * booleans, strings
+ * 
+ * The frontend sends timestamps as epoch milliseconds (or RFC 3339 strings for
+ * TIMESTAMPTZ tables like `folder_sources`); both are converted when bound to
+ * timestamp/timestamptz columns so Postgres doesn't reject the bigint or text.
  */
 fn json_to_param(
     _table: &str,
     column: &str,
+    column_type: Option<&str>,
     value: &Value,
 ) -> Box<dyn tokio_postgres::types::ToSql + Sync + Send> {
 
     match value {
+        Value::Null if column_type == Some("numeric") => Box::new(Option::<rust_decimal::Decimal>::None),
+        Value::Null if column_type == Some("bytea") => Box::new(Option::<Vec<u8>>::None),
+        Value::Null if column_type == Some("date") => Box::new(Option::<chrono::NaiveDate>::None),
+        Value::Null if column_type == Some("interval") => Box::new(Option::<PgInterval>::None),
+        Value::Null if column_type == Some("timestamptz") => {
+            Box::new(Option::<chrono::DateTime<chrono::Utc>>::None)
+        }
+        Value::Null if column_type == Some("timestamp") => Box::new(Option::<chrono::NaiveDateTime>::None),
+        Value::Null if matches!(column_type, Some("_jsonb" | "_json")) => {
+            Box::new(Option::<Vec<Json<Value>>>::None)
+        }
         Value::Null => {
             if column.ends_with("_id") || column == "id" {
                 Box::new(Option::<uuid::Uuid>::None)
@@ -395,6 +1088,23 @@ fn json_to_param(
             }
         }
         Value::Bool(b) => Box::new(*b),
+        Value::Number(n) if matches!(column_type, Some("timestamptz" | "timestamp")) => {
+            let millis = n.as_i64().or_else(|| n.as_f64().map(|f| f as i64));
+            match millis.and_then(chrono::DateTime::<chrono::Utc>::from_timestamp_millis) {
+                Some(ts) if column_type == Some("timestamptz") => Box::new(ts),
+                Some(ts) => Box::new(ts.naive_utc()),
+                // Out of range: bind as-is and let Postgres report the type mismatch
+                None => Box::new(millis),
+            }
+        }
+        Value::Number(n) if column_type == Some("numeric") => {
+            // Goes through the number's decimal text, so no binary float rounding is added
+            match parse_decimal(&n.to_string()) {
+                Some(dec) => Box::new(dec),
+                // Out of Decimal's range: bind as-is and let Postgres report it
+                None => Box::new(n.as_f64()),
+            }
+        }
         Value::Number(n) => {
             if let Some(i) = n.as_i64() {
                 Box::new(i)
@@ -407,6 +1117,41 @@ fn json_to_param(
                 Box::new(Option::<f64>::None)
             }
         }
+        Value::String(s) if column_type == Some("date") => {
+            match chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+                Ok(date) => Box::new(date),
+                Err(_) => Box::new(s.clone()),
+            }
+        }
+        Value::String(s) if column_type == Some("numeric") => {
+            // Numeric strings keep full precision (up to Decimal's 28 significant digits)
+            match parse_decimal(s) {
+                Some(dec) => Box::new(dec),
+                None => Box::new(s.clone()),
+            }
+        }
+        Value::String(s) if column_type == Some("bytea") => {
+            // bytea travels as base64, matching what row_to_json emits
+            use base64::{engine::general_purpose, Engine as _};
+            
+            match general_purpose::STANDARD.decode(s) {
+                Ok(bytes) => Box::new(bytes),
+                Err(_) => Box::new(s.clone()),
+            }
+        }
+        Value::String(s) if matches!(column_type, Some("timestamptz" | "timestamp")) => {
+            match chrono::DateTime::parse_from_rfc3339(s) {
+                Ok(ts) if column_type == Some("timestamptz") => Box::new(ts.to_utc()),
+                Ok(ts) => Box::new(ts.naive_utc()),
+                Err(_) => Box::new(s.clone()),
+            }
+        }
+        Value::String(s) if column_type == Some("interval") => {
+            match PgInterval::parse_iso8601(s) {
+                Some(interval) => Box::new(interval),
+                None => Box::new(s.clone()),
+            }
+        }
         Value::String(s) => {
             if column.ends_with("_id") || column == "id" {
                 match uuid::Uuid::parse_str(s) {
@@ -417,6 +1162,11 @@ fn json_to_param(
                 Box::new(s.clone())
             }
         }
+        Value::Array(arr) if matches!(column_type, Some("_jsonb" | "_json")) => {
+            // jsonb[] column: one JSON document per element
+            let elements: Vec<Json<Value>> = arr.iter().cloned().map(Json).collect();
+            Box::new(elements)
+        }
         Value::Array(arr) => {
             // All string elements => text[] or uuid[]
             if arr.iter().all(|v| v.is_string()) {
@@ -448,10 +1198,13 @@ fn json_to_param(
     }
 }
 
+/// Postgres caps a statement at 65535 bind parameters
+const MAX_STATEMENT_PARAMS: usize = 65_535;
+
 /**
- * Apply insert operation
+ * Snake-cased, validated column map for an insert payload
  */
-async fn apply_insert(client: &Client, change: &WriteChange) -> Result<Value, String> {
+async fn insert_row_data(client: &Client, change: &WriteChange) -> Result<HashMap<String, Value>, String> {
     let transformed = if change.table == "annotations" {
         transform_annotation_data(&change.payload)?
     } else {
@@ -459,47 +1212,127 @@ async fn apply_insert(client: &Client, change: &WriteChange) -> Result<Value, St
     };
     
     let data = keys_to_snake_case(&transformed)?;
+    validate_payload_columns(client, &change.table, &data, true).await?;
+    Ok(data)
+}
+
+/**
+ * Comparable text for a row's conflict key (UUIDs normalized to Postgres' lowercase form)
+ */
+fn conflict_key_text<'a>(key: &ConflictKey, get: impl Fn(&str) -> Option<&'a Value>) -> Option<String> {
+    let parts = key.columns
+        .iter()
+        .map(|col| {
+            get(col).map(|value| match value {
+                Value::String(s) => uuid::Uuid::parse_str(s)
+                    .map(|u| u.to_string())
+                    .unwrap_or_else(|_| s.clone()),
+                other => other.to_string(),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join("/"))
+}
+
+/**
+ * Rows of `columns.len()` values each that fit in one statement's bind parameters
+ */
+fn rows_per_statement(column_count: usize) -> usize {
+    (MAX_STATEMENT_PARAMS / column_count.max(1)).max(1)
+}
+
+/**
+ * `INSERT ... VALUES (...), (...) ON CONFLICT ... RETURNING *` for `row_count` rows
+ */
+fn insert_statement(table: &str, columns: &[String], row_count: usize, on_conflict: OnConflict) -> String {
+    let placeholders: Vec<String> = (0..row_count)
+        .map(|r| {
+            let row: Vec<String> = (1..=columns.len())
+                .map(|c| format!("${}", r * columns.len() + c))
+                .collect();
+            format!("({})", row.join(", "))
+        })
+        .collect();
     
-    let columns: Vec<String> = data.keys().cloned().collect();
-    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("${}", i)).collect();
-    
-    // Build query with appropriate conflict handling
-    let query = if change.table == "blobs_meta" {
-        // blobs_meta: unique on sha256, use DO NOTHING for idempotency
-        format!(
-            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT (sha256) DO NOTHING RETURNING *",
-            change.table,
-            columns.join(", "),
-            placeholders.join(", ")
-        )
-    } else if change.table == "device_blobs" {
-        // device_blobs: unique on (device_id, sha256), use DO NOTHING for idempotency
-        format!(
-            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT (device_id, sha256) DO NOTHING RETURNING *",
-            change.table,
-            columns.join(", "),
-            placeholders.join(", ")
-        )
-    } else {
-        // Other tables: update on conflict (LWW)
-        format!(
-            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT (id) DO UPDATE SET {} RETURNING *",
-            change.table,
-            columns.join(", "),
-            placeholders.join(", "),
-            columns.iter().enumerate()
-                .map(|(i, col)| format!("{} = ${}", col, i + 1))
+    // Build query with the table's conflict handling
+    let key = conflict_key(table);
+    let on_conflict = match on_conflict {
+        // First write wins, DO NOTHING for idempotency
+        OnConflict::Skip => "DO NOTHING".to_string(),
+        // Update on conflict (LWW)
+        OnConflict::Overwrite => format!(
+            "DO UPDATE SET {}",
+            columns.iter()
+                .map(|col| format!("{} = EXCLUDED.{}", col, col))
                 .collect::<Vec<_>>()
                 .join(", ")
-        )
+        ),
+    };
+    format!(
+        "INSERT INTO {} ({}) VALUES {} ON CONFLICT ({}) {} RETURNING *",
+        table,
+        columns.join(", "),
+        placeholders.join(", "),
+        key.columns.join(", "),
+        on_conflict
+    )
+}
+
+/**
+ * Line RETURNING rows up with the input rows they came from
+ * 
+ * Postgres doesn't promise RETURNING order, so rows are matched by conflict key. A row
+ * DO NOTHING skipped maps to null (not an error - the record already existed). Inputs
+ * without their key (e.g. a server-generated id) can only be matched by position.
+ */
+fn match_returned_rows(key: &ConflictKey, rows: &[HashMap<String, Value>], returned: Vec<Value>) -> Vec<Value> {
+    let input_keys: Option<Vec<String>> = rows
+        .iter()
+        .map(|data| conflict_key_text(key, |col| data.get(col)))
+        .collect();
+    let Some(input_keys) = input_keys else {
+        if returned.len() == rows.len() {
+            return returned;
+        }
+        return vec![Value::Null; rows.len()];
     };
     
-    // Convert JSON values to Postgres parameters
-    let param_values: Vec<Box<dyn tokio_postgres::types::ToSql + Sync + Send>> = columns
+    let mut by_key: HashMap<String, Value> = HashMap::new();
+    for json in returned {
+        if let Some(k) = conflict_key_text(key, |col| json.get(col)) {
+            by_key.insert(k, json);
+        }
+    }
+    
+    input_keys
         .iter()
-        .map(|col| {
+        .map(|k| by_key.remove(k).unwrap_or(Value::Null))
+        .collect()
+}
+
+/**
+ * Insert rows sharing one column set with a single `INSERT ... VALUES (...), (...)`
+ * 
+ * Returns one value per input row, in order: the stored row, or null when DO NOTHING
+ * skipped it because the record already existed.
+ */
+async fn insert_rows(
+    client: &Client,
+    table: &str,
+    columns: &[String],
+    rows: &[HashMap<String, Value>],
+    on_conflict: OnConflict,
+) -> Result<Vec<Value>, WriteError> {
+    let query = insert_statement(table, columns, rows.len(), on_conflict);
+    
+    // Convert JSON values to Postgres parameters, row by row
+    let table_columns = get_table_columns(client, table).await?;
+    let param_values: Vec<Box<dyn tokio_postgres::types::ToSql + Sync + Send>> = rows
+        .iter()
+        .flat_map(|data| columns.iter().map(move |col| (data, col)))
+        .map(|(data, col)| {
             let val = data.get(col).unwrap();
-            json_to_param(&change.table, col, val)
+            json_to_param(table, col, column_type(&table_columns, col), val)
         })
         .collect();
 
@@ -510,23 +1343,142 @@ async fn apply_insert(client: &Client, change: &WriteChange) -> Result<Value, St
         .collect();
     
     // Use query() instead of query_one() to handle DO NOTHING case (returns 0 rows)
-    let rows = client.query(&query, &params[..])
+    log_statement(&query, params.len());
+    let returned = client.query(&query, &params[..])
         .await
-        .map_err(|e| format!("Insert failed: {}", e))?;
+        .map_err(|e| WriteError::pg("Insert failed", e))?;
     
-    // If DO NOTHING was used and nothing was inserted, return null
-    // (This is not an error - just means the record already existed)
-    if let Some(row) = rows.first() {
-        Ok(row_to_json(row))
-    } else {
-        Ok(Value::Null)
+    Ok(match_returned_rows(conflict_key(table), rows, returned.iter().map(row_to_json).collect()))
+}
+
+/**
+ * Apply insert operation
+ */
+async fn apply_insert(client: &Client, change: &WriteChange) -> Result<Value, WriteError> {
+    apply_insert_with(client, change, conflict_key(&change.table).on_conflict()).await
+}
+
+/**
+ * Apply insert operation with explicit conflict handling
+ * 
+ * Returns null when `OnConflict::Skip` left an existing row in place.
+ */
+async fn apply_insert_with(
+    client: &Client,
+    change: &WriteChange,
+    on_conflict: OnConflict,
+) -> Result<Value, WriteError> {
+    let data = insert_row_data(client, change).await?;
+    let columns: Vec<String> = data.keys().cloned().collect();
+    
+    let mut values = insert_rows(client, &change.table, &columns, std::slice::from_ref(&data), on_conflict).await?;
+    Ok(values.pop().unwrap_or(Value::Null))
+}
+
+/**
+ * Apply a run of inserts into one table as multi-row statements
+ * 
+ * Returns `None` when the payloads don't share a column set, so the caller falls back
+ * to per-row inserts. Statements are split to stay under `MAX_STATEMENT_PARAMS`.
+ */
+async fn apply_insert_batch(client: &Client, changes: &[WriteChange]) -> Result<Option<Vec<Value>>, WriteError> {
+    let Some(first) = changes.first() else {
+        return Ok(Some(Vec::new()));
+    };
+    
+    let mut rows = Vec::with_capacity(changes.len());
+    for change in changes {
+        rows.push(insert_row_data(client, change).await?);
+    }
+    
+    let columns: Vec<String> = rows[0].keys().cloned().collect();
+    let homogeneous = rows.iter().all(|data| {
+        data.len() == columns.len() && columns.iter().all(|col| data.contains_key(col))
+    });
+    if !homogeneous || columns.is_empty() {
+        return Ok(None);
+    }
+    
+    let rows_per_statement = rows_per_statement(columns.len());
+    let mut values = Vec::with_capacity(rows.len());
+    for chunk in rows.chunks(rows_per_statement) {
+        let on_conflict = conflict_key(&first.table).on_conflict();
+        values.extend(insert_rows(client, &first.table, &columns, chunk, on_conflict).await?);
+    }
+    
+    println!(
+        "[FlushWrites] Batched {} inserts into '{}' in {} statement(s)",
+        rows.len(),
+        first.table,
+        rows.len().div_ceil(rows_per_statement)
+    );
+    Ok(Some(values))
+}
+
+/**
+ * Outcome of an update: applied, or rejected by an optimistic version check
+ */
+enum UpdateOutcome {
+    Applied(Value),
+    /// The expected version didn't match; carries the current server row
+    Conflict(Value),
+}
+
+/**
+ * A row's LWW timestamp as epoch milliseconds
+ * 
+ * Most sync tables store `updated_at` as epoch-ms bigints, but some (e.g. `folder_sources`)
+ * use TIMESTAMPTZ, so the column is read as whichever of the two it is.
+ */
+fn row_timestamp_ms(row: &tokio_postgres::Row, column: &str) -> Result<i64, WriteError> {
+    use tokio_postgres::types::Type;
+
+    // A NULL timestamp never beats the client's
+    let millis = match *row.columns()[0].type_() {
+        Type::INT8 => row.try_get::<_, Option<i64>>(0).ok(),
+        Type::TIMESTAMPTZ => row
+            .try_get::<_, Option<chrono::DateTime<chrono::Utc>>>(0)
+            .ok()
+            .map(|ts| ts.map(|ts| ts.timestamp_millis())),
+        Type::TIMESTAMP => row
+            .try_get::<_, Option<chrono::NaiveDateTime>>(0)
+            .ok()
+            .map(|ts| ts.map(|ts| ts.and_utc().timestamp_millis())),
+        _ => None,
+    };
+    match millis {
+        Some(millis) => Ok(millis.unwrap_or(0)),
+        None => Err(format!(
+            "Unsupported type {} for timestamp column {}",
+            row.columns()[0].type_(),
+            column
+        )
+        .into()),
+    }
+}
+
+/**
+ * A payload timestamp as epoch milliseconds: a number, or an RFC 3339 string
+ */
+fn json_timestamp_ms(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)),
+        Value::String(s) => chrono::DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|ts| ts.timestamp_millis()),
+        _ => None,
     }
 }
 
 /**
- * Apply update operation with LWW conflict resolution
+ * Apply update operation with conflict resolution
+ * 
+ * Rows are matched on the table's conflict key (see `CONFLICT_KEYS`). Tables with an
+ * integer `version` column use optimistic concurrency when the payload carries the
+ * expected version: the UPDATE only matches that version and bumps it. Other tables
+ * fall back to LWW on their timestamp column, if they have one.
  */
-async fn apply_update(client: &Client, change: &WriteChange) -> Result<Value, String> {
+async fn apply_update(client: &Client, change: &WriteChange) -> Result<UpdateOutcome, WriteError> {
     let transformed = if change.table == "annotations" {
         transform_annotation_data(&change.payload)?
     } else {
@@ -534,100 +1486,206 @@ async fn apply_update(client: &Client, change: &WriteChange) -> Result<Value, St
     };
     
     let data = keys_to_snake_case(&transformed)?;
+    validate_payload_columns(client, &change.table, &data, false).await?;
+    let table_columns = get_table_columns(client, &change.table).await?;
     
-    // Get ID
-    let id = data.get("id")
-        .and_then(|v| v.as_str())
-        .ok_or("Missing id in update payload")?;
+    // Get the conflict key values (bound first, as $1..$n)
+    let key = conflict_key(&change.table);
+    let mut key_values = Vec::with_capacity(key.columns.len());
+    for col in key.columns {
+        match data.get(*col) {
+            Some(value) if !value.is_null() => key_values.push(value),
+            _ => return Err(format!("Missing {} in update payload", col).into()),
+        }
+    }
+    let row_label = key_values
+        .iter()
+        .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+        .collect::<Vec<_>>()
+        .join("/");
+    let key_params: Vec<Box<dyn tokio_postgres::types::ToSql + Sync + Send>> = key.columns
+        .iter()
+        .zip(&key_values)
+        .map(|(col, val)| json_to_param(&change.table, col, column_type(&table_columns, col), val))
+        .collect();
+    let key_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = key_params
+        .iter()
+        .map(|p| &**p as &(dyn tokio_postgres::types::ToSql + Sync))
+        .collect();
+    let key_where = key_predicate(key, 1);
     
     // Check if record exists
-    let check_query = format!("SELECT updated_at FROM {} WHERE id = $1", change.table);
-    let existing = client.query_opt(&check_query, &[&id]).await
-        .map_err(|e| format!("Failed to check existing record: {}", e))?;
+    let check_query = format!(
+        "SELECT {} FROM {} WHERE {}",
+        key.timestamp_column.unwrap_or("1"),
+        change.table,
+        key_where
+    );
+    let existing = client.query_opt(&check_query, &key_refs[..]).await
+        .map_err(|e| WriteError::pg("Failed to check existing record", e))?;
     
-    if existing.is_none() {
+    let existing = match existing {
+        Some(row) => row,
         // Record doesn't exist, treat as insert
-        return apply_insert(client, change).await;
-    }
-    
-    // LWW: compare timestamps
-    let server_updated_at: i64 = existing.unwrap().get(0);
-    let client_updated_at = data.get("updated_at")
-        .and_then(|v| v.as_i64())
-        .unwrap_or(0);
-    
-    if client_updated_at < server_updated_at {
-        println!("[WritesBatch] Skipping update for {}/{} - server is newer", change.table, id);
-        // Return existing record
-        let fetch_query = format!("SELECT * FROM {} WHERE id = $1", change.table);
-        let row = client.query_one(&fetch_query, &[&id]).await
-            .map_err(|e| format!("Failed to fetch existing record: {}", e))?;
-        
-        let mut result = serde_json::Map::new();
-        for (i, column) in row.columns().iter().enumerate() {
-            let value: Option<String> = row.get(i);
-            result.insert(
-                column.name().to_string(),
-                value.map(Value::String).unwrap_or(Value::Null)
-            );
+        None => return apply_insert(client, change).await.map(UpdateOutcome::Applied),
+    };
+    
+    // Optimistic concurrency: only when the table has a version column and the client sent one
+    let version_column = table_columns.iter().find(|c| c.name == "version");
+    let expected_version = version_column
+        .and_then(|_| data.get("version"))
+        .and_then(|v| v.as_i64());
+    
+    if let (None, Some(timestamp_column)) = (expected_version, key.timestamp_column) {
+        // LWW: compare timestamps
+        let server_updated_at = row_timestamp_ms(&existing, timestamp_column)?;
+        let client_updated_at = data.get(timestamp_column)
+            .and_then(json_timestamp_ms)
+            .unwrap_or(0);
+        
+        if client_updated_at < server_updated_at {
+            println!("[WritesBatch] Skipping update for {}/{} - server is newer", change.table, row_label);
+            // Return existing record
+            let fetch_query = format!("SELECT * FROM {} WHERE {}", change.table, key_where);
+            let row = client.query_one(&fetch_query, &key_refs[..]).await
+                .map_err(|e| WriteError::pg("Failed to fetch existing record", e))?;
+            return Ok(UpdateOutcome::Applied(row_to_json(&row)));
         }
-        return Ok(Value::Object(result));
     }
     
-    // Build UPDATE query
+    // Build UPDATE query (the version column is managed by the WHERE/bump below)
     let columns: Vec<String> = data.keys()
-        .filter(|k| *k != "id")
+        .filter(|k| !key.columns.contains(&k.as_str()))
+        .filter(|k| !(expected_version.is_some() && *k == "version"))
         .cloned()
         .collect();
+    let first_set_param = key.columns.len() + 1;
     
-    let set_clause: Vec<String> = columns.iter().enumerate()
-        .map(|(i, col)| format!("{} = ${}", col, i + 2))
+    let mut set_clause: Vec<String> = columns.iter().enumerate()
+        .map(|(i, col)| {
+            let merge = change.jsonb_merge
+                && JSONB_COLUMNS.contains(col.as_str())
+                && data.get(col).map(|v| v.is_object()).unwrap_or(false);
+            if merge {
+                format!("{} = COALESCE({}, '{{}}'::jsonb) || ${}", col, col, first_set_param + i)
+            } else {
+                format!("{} = ${}", col, first_set_param + i)
+            }
+        })
         .collect();
     
-    let query = format!(
-        "UPDATE {} SET {} WHERE id = $1 RETURNING *",
-        change.table,
-        set_clause.join(", ")
-    );
+    let query = if expected_version.is_some() {
+        set_clause.push("version = version + 1".to_string());
+        format!(
+            "UPDATE {} SET {} WHERE {} AND version = ${} RETURNING *",
+            change.table,
+            set_clause.join(", "),
+            key_where,
+            first_set_param + columns.len()
+        )
+    } else {
+        format!(
+            "UPDATE {} SET {} WHERE {} RETURNING *",
+            change.table,
+            set_clause.join(", "),
+            key_where
+        )
+    };
     
-    // Convert JSON values to Postgres parameters (id first)
+    // Convert JSON values to Postgres parameters (key first, expected version last)
     let mut param_values: Vec<Box<dyn tokio_postgres::types::ToSql + Sync + Send>> = Vec::new();
-    param_values.push(Box::new(id.to_string()));
     for col in &columns {
         let val = data.get(col).unwrap();
-        param_values.push(json_to_param(&change.table, col, val));
+        param_values.push(json_to_param(&change.table, col, column_type(&table_columns, col), val));
+    }
+    if let (Some(version), Some(column)) = (expected_version, version_column) {
+        if column.data_type == "int4" || column.data_type == "int2" {
+            param_values.push(Box::new(version as i32));
+        } else {
+            param_values.push(Box::new(version));
+        }
     }
 
-    let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = param_values
+    let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = key_refs
         .iter()
-        .map(|p| &**p as &(dyn tokio_postgres::types::ToSql + Sync))
+        .copied()
+        .chain(param_values.iter().map(|p| &**p as &(dyn tokio_postgres::types::ToSql + Sync)))
         .collect();
     
-    let row = client.query_one(&query, &params[..])
+    log_statement(&query, params.len());
+    let rows = client.query(&query, &params[..])
         .await
-        .map_err(|e| format!("Update failed: {}", e))?;
+        .map_err(|e| WriteError::pg("Update failed", e))?;
     
     // Convert row to JSON using type-aware helper
-    Ok(row_to_json(&row))
+    match rows.first() {
+        Some(row) => Ok(UpdateOutcome::Applied(row_to_json(row))),
+        None if expected_version.is_some() => {
+            println!(
+                "[WritesBatch] Version conflict for {}/{} (expected version {})",
+                change.table, row_label, expected_version.unwrap()
+            );
+            let fetch_query = format!("SELECT * FROM {} WHERE {}", change.table, key_where);
+            let current = client.query_opt(&fetch_query, &key_refs[..]).await
+                .map_err(|e| WriteError::pg("Failed to fetch current record", e))?;
+            Ok(UpdateOutcome::Conflict(current.map(|row| row_to_json(&row)).unwrap_or(Value::Null)))
+        }
+        None => Err("Update failed: no rows updated".into()),
+    }
+}
+
+/**
+ * Tombstone assignments for a table, or None if it has no `deleted_at`/`is_deleted` column
+ */
+fn tombstone_assignments(columns: &[ColumnInfo]) -> Option<String> {
+    let mut assignments = Vec::new();
+    if columns.iter().any(|c| c.name == "deleted_at") {
+        assignments.push("deleted_at = COALESCE(deleted_at, now())");
+    }
+    if columns.iter().any(|c| c.name == "is_deleted") {
+        assignments.push("is_deleted = true");
+    }
+    
+    if assignments.is_empty() {
+        None
+    } else {
+        Some(assignments.join(", "))
+    }
 }
 
 /**
  * Apply delete operation
+ * 
+ * Tables with a `deleted_at`/`is_deleted` column get a tombstone instead of a physical
+ * delete, so other devices learn the row is gone. `hard_delete` on the change opts out.
  */
-async fn apply_delete(client: &Client, change: &WriteChange) -> Result<Value, String> {
+async fn apply_delete(client: &Client, change: &WriteChange) -> Result<Value, WriteError> {
     let id_str = change.payload.get("id")
         .and_then(|v| v.as_str())
         .ok_or("Missing id in delete payload")?;
     
-    let query = format!("DELETE FROM {} WHERE id = $1 RETURNING *", change.table);
+    let tombstone = if change.hard_delete {
+        None
+    } else {
+        tombstone_assignments(&get_table_columns(client, &change.table).await?)
+    };
+    
+    let query = match tombstone {
+        Some(assignments) => format!(
+            "UPDATE {} SET {} WHERE id = $1 RETURNING *",
+            change.table, assignments
+        ),
+        None => format!("DELETE FROM {} WHERE id = $1 RETURNING *", change.table),
+    };
     
     // Parse ID as UUID for proper type handling
     let id_uuid = uuid::Uuid::parse_str(id_str)
         .map_err(|e| format!("Invalid UUID format: {}", e))?;
 
+    log_statement(&query, 1);
     let row = client.query_opt(&query, &[&id_uuid])
         .await
-        .map_err(|e| format!("Delete failed: {}", e))?;
+        .map_err(|e| WriteError::pg("Delete failed", e))?;
     
     if let Some(row) = row {
         // Convert row to JSON using type-aware helper
@@ -644,58 +1702,203 @@ async fn apply_delete(client: &Client, change: &WriteChange) -> Result<Value, St
  * before any writes. The user_id should be obtained from the authenticated session.
  */
 #[tauri::command]
-pub async fn flush_writes(changes: Vec<WriteChange>, user_id: Option<String>) -> Result<Vec<WriteResult>, String> {
+pub async fn flush_writes(changes: Vec<WriteChange>, user_id: Option<String>) -> CommandResult<Vec<WriteResult>> {
     println!("[FlushWrites] Starting flush of {} changes", changes.len());
     
-    let client = get_pg_client().await?;
-    
-    // Set RLS context if user_id is provided
+    let mut client = get_pg_client().await?;
+    apply_changes(&mut client, &changes, user_id)
+        .await
+        .map_err(CommandError::Database)
+}
+
+/**
+ * Open the transaction for one write and set its RLS context (`app.user_id`)
+ * 
+ * `set_config(..., true)` only lasts until the transaction ends, so every write gets its
+ * own transaction: a failed write rolls back alone and the others still apply.
+ */
+async fn begin_write<'a>(
+    client: &'a mut PgClient,
+    user_id: Option<&str>,
+) -> Result<deadpool_postgres::Transaction<'a>, WriteError> {
+    let tx = client.transaction().await
+        .map_err(|e| WriteError::pg("Failed to start transaction", e))?;
     if let Some(uid) = user_id {
-        println!("[FlushWrites] Setting RLS context: app.user_id = {}", uid);
-        client.execute("SET LOCAL app.user_id = $1", &[&uid])
+        tx.execute("SELECT set_config('app.user_id', $1, true)", &[&uid])
             .await
-            .map_err(|e| format!("Failed to set RLS context: {}", e))?;
-    } else {
-        println!("[FlushWrites] WARNING: No user_id provided - writes may fail due to RLS policies!");
+            .map_err(|e| WriteError::pg("Failed to set RLS context", e))?;
+    }
+    Ok(tx)
+}
+
+/**
+ * Commit a write's transaction if it succeeded; dropping it otherwise rolls it back
+ */
+async fn finish_write<T>(
+    tx: deadpool_postgres::Transaction<'_>,
+    result: Result<T, WriteError>,
+) -> Result<T, WriteError> {
+    let value = result?;
+    tx.commit().await.map_err(|e| WriteError::pg("Commit failed", e))?;
+    Ok(value)
+}
+
+/**
+ * Apply one change in its own transaction (see `begin_write`)
+ */
+async fn apply_change(
+    client: &mut PgClient,
+    change: &WriteChange,
+    user_id: Option<&str>,
+) -> Result<UpdateOutcome, WriteError> {
+    let tx = begin_write(client, user_id).await?;
+    let result = match change.op {
+        WriteOperation::Insert => apply_insert(tx.client(), change).await.map(UpdateOutcome::Applied),
+        WriteOperation::Update => apply_update(tx.client(), change).await,
+        WriteOperation::Delete => apply_delete(tx.client(), change).await.map(UpdateOutcome::Applied),
+    };
+    finish_write(tx, result).await
+}
+
+/**
+ * Log the outcome of one change and convert it to its WriteResult
+ */
+fn write_result(change: &WriteChange, result: Result<UpdateOutcome, WriteError>) -> WriteResult {
+    match &result {
+        Ok(UpdateOutcome::Applied(_)) => println!("[FlushWrites] ✓ Success: {}", change.id),
+        Ok(UpdateOutcome::Conflict(_)) => println!("[FlushWrites] ✗ Conflict: {}", change.id),
+        Err(e) => println!("[FlushWrites] ✗ Error: {} - {}", change.id, e),
     }
     
-    let mut results = Vec::new();
+    match result {
+        Ok(UpdateOutcome::Applied(data)) => WriteResult {
+            id: change.id.clone(),
+            success: true,
+            data: Some(data),
+            error: None,
+        },
+        Ok(UpdateOutcome::Conflict(current)) => WriteResult {
+            id: change.id.clone(),
+            success: false,
+            data: Some(current),
+            error: Some("Version conflict: record was modified on the server".to_string()),
+        },
+        Err(error) => WriteResult {
+            id: change.id.clone(),
+            success: false,
+            data: None,
+            error: Some(error.message),
+        },
+    }
+}
+
+/**
+ * Apply a sequence of changes on an open client, one WriteResult per change
+ * 
+ * Runs of inserts into the same table are batched into multi-row statements; results
+ * still come back one per change, in order.
+ */
+async fn apply_changes(
+    client: &mut PgClient,
+    changes: &[WriteChange],
+    user_id: Option<String>,
+) -> Result<Vec<WriteResult>, String> {
+    match &user_id {
+        Some(uid) => println!("[FlushWrites] Setting RLS context: app.user_id = {}", uid),
+        None => println!("[FlushWrites] WARNING: No user_id provided - writes may fail due to RLS policies!"),
+    }
     
-    for change in changes {
-        println!("[FlushWrites] Processing {} operation on table '{}' (id: {})", 
-                 match change.op {
-                     WriteOperation::Insert => "INSERT",
-                     WriteOperation::Update => "UPDATE",
-                     WriteOperation::Delete => "DELETE",
-                 },
-                 change.table,
-                 change.id);
-        
-        let result = match change.op {
-            WriteOperation::Insert => apply_insert(&client, &change).await,
-            WriteOperation::Update => apply_update(&client, &change).await,
-            WriteOperation::Delete => apply_delete(&client, &change).await,
+    let mut results = Vec::with_capacity(changes.len());
+    let mut i = 0;
+    
+    while i < changes.len() {
+        if !SYNC_TABLES.contains(&changes[i].table.as_str()) {
+            let error = format!("Invalid table name: {}", changes[i].table);
+            results.push(write_result(&changes[i], Err(error.into())));
+            i += 1;
+            continue;
+        }
+        
+        // Consecutive inserts into the same table go out as one multi-row statement
+        let run = if matches!(changes[i].op, WriteOperation::Insert) {
+            changes[i..]
+                .iter()
+                .take_while(|c| matches!(c.op, WriteOperation::Insert) && c.table == changes[i].table)
+                .count()
+        } else {
+            1
         };
         
-        match &result {
-            Ok(_) => println!("[FlushWrites] ✓ Success: {}", change.id),
-            Err(e) => println!("[FlushWrites] ✗ Error: {} - {}", change.id, e),
+        if run > 1 {
+            let batch = &changes[i..i + run];
+            let batch_result = match begin_write(client, user_id.as_deref()).await {
+                Ok(tx) => {
+                    let result = apply_insert_batch(tx.client(), batch).await;
+                    finish_write(tx, result).await
+                }
+                Err(e) => Err(e),
+            };
+            match batch_result {
+                Ok(Some(values)) => {
+                    for (change, data) in batch.iter().zip(values) {
+                        results.push(write_result(change, Ok(UpdateOutcome::Applied(data))));
+                    }
+                    i += run;
+                    continue;
+                }
+                // Heterogeneous payloads, or one row failed: redo the run row by row so
+                // each change gets its own result
+                Ok(None) => {}
+                Err(e) => println!(
+                    "[FlushWrites] Batch insert into '{}' failed, retrying per row: {}",
+                    changes[i].table, e
+                ),
+            }
         }
         
-        results.push(match result {
-            Ok(data) => WriteResult {
-                id: change.id.clone(),
-                success: true,
-                data: Some(data),
-                error: None,
-            },
-            Err(error) => WriteResult {
-                id: change.id.clone(),
-                success: false,
-                data: None,
-                error: Some(error),
-            },
-        });
+        for change in &changes[i..i + run] {
+            println!("[FlushWrites] Processing {} operation on table '{}' (id: {})", 
+                     match change.op {
+                         WriteOperation::Insert => "INSERT",
+                         WriteOperation::Update => "UPDATE",
+                         WriteOperation::Delete => "DELETE",
+                     },
+                     change.table,
+                     change.id);
+            
+            // Transient failures (dropped connection, serialization failure, ...) are
+            // retried with backoff; permanent ones fail this change immediately
+            let mut retries = 0;
+            let result = loop {
+                match apply_change(client, change, user_id.as_deref()).await {
+                    Err(e) if e.transient && retries < *MAX_RETRIES => {
+                        retries += 1;
+                        let delay = backoff_delay(retries);
+                        println!(
+                            "[FlushWrites] Transient error on {}: {} (retry {}/{} in {}ms)",
+                            change.id, e, retries, *MAX_RETRIES, delay.as_millis()
+                        );
+                        tokio::time::sleep(delay).await;
+                        
+                        if client.is_closed() {
+                            // The connection is gone; continue on a fresh one
+                            match get_pg_client().await {
+                                Ok(fresh) => *client = fresh,
+                                Err(e) => break Err(WriteError::from(e.to_string())),
+                            }
+                        }
+                    }
+                    result => {
+                        if retries > 0 {
+                            println!("[FlushWrites] {} finished after {} retries", change.id, retries);
+                        }
+                        break result;
+                    }
+                }
+            };
+            results.push(write_result(change, result));
+        }
+        i += run;
     }
     
     let success_count = results.iter().filter(|r| r.success).count();
@@ -705,13 +1908,78 @@ pub async fn flush_writes(changes: Vec<WriteChange>, user_id: Option<String>) ->
     Ok(results)
 }
 
-/// Clear all database tables (Postgres)
+/**
+ * Persist changes to the local outbox so they survive restarts while offline
+ */
 #[tauri::command]
-pub async fn clear_all_database() -> Result<(), String> {
-    let client = get_pg_client().await?;
+pub async fn queue_writes(changes: Vec<WriteChange>) -> CommandResult<usize> {
+    let conn = crate::db::get_connection()?;
     
-    // List of tables to clear (in order to avoid FK constraint issues)
-    let tables = vec![
+    for change in &changes {
+        let change_json = serde_json::to_string(change)?;
+        crate::db::queue_pending_write(&conn, &change.id, &change_json)?;
+    }
+    
+    println!("[Outbox] Queued {} changes", changes.len());
+    Ok(changes.len())
+}
+
+/**
+ * Drain the local outbox to Postgres, removing changes that applied successfully
+ * 
+ * If Postgres is unreachable the queue is left untouched and the error is returned.
+ */
+#[tauri::command]
+pub async fn flush_pending_writes(user_id: Option<String>) -> CommandResult<Vec<WriteResult>> {
+    let pending = {
+        let conn = crate::db::get_connection()?;
+        crate::db::list_pending_writes(&conn)?
+    };
+    
+    if pending.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let mut changes = Vec::new();
+    let mut results = Vec::new();
+    for (id, change_json) in pending {
+        match serde_json::from_str::<WriteChange>(&change_json) {
+            Ok(change) => changes.push(change),
+            Err(e) => results.push(WriteResult {
+                id,
+                success: false,
+                data: None,
+                error: Some(format!("Corrupt outbox entry: {}", e)),
+            }),
+        }
+    }
+    
+    println!("[Outbox] Flushing {} pending changes", changes.len());
+    let mut client = get_pg_client().await?;
+    results.extend(
+        apply_changes(&mut client, &changes, user_id)
+            .await
+            .map_err(CommandError::Database)?,
+    );
+    
+    let conn = crate::db::get_connection()?;
+    for result in results.iter().filter(|r| r.success) {
+        crate::db::delete_pending_write(&conn, &result.id)?;
+    }
+    
+    let remaining = crate::db::list_pending_writes(&conn)?.len();
+    println!("[Outbox] Flush complete, {} changes remain queued", remaining);
+    
+    Ok(results)
+}
+
+/// Clear all database tables (Postgres)
+#[tauri::command]
+pub async fn clear_all_database() -> CommandResult<()> {
+    let client = get_pg_client().await?;
+    
+    // List of tables to clear (in order to avoid FK constraint issues)
+    let tables = vec![
         "review_logs",
         "cards",
         "annotations",
@@ -732,94 +2000,1261 @@ pub async fn clear_all_database() -> Result<(), String> {
     for table in tables {
         client.execute(&format!("DELETE FROM {}", table), &[])
             .await
-            .map_err(|e| format!("Failed to clear table {}: {}", table, e))?;
+            .map_err(|e| CommandError::Database(format!("Failed to clear table {}: {}", table, e)))?;
         println!("[Database] Cleared table: {}", table);
     }
     
     Ok(())
 }
 
-/// Export all data to JSON
+/// Version stamped into file exports; `import_data_from_file` refuses other versions
+const EXPORT_FORMAT_VERSION: &str = "1.0";
+
+/// Tables included in file exports, parents before children so imports satisfy FKs
+const EXPORT_TABLES: &[&str] = &[
+    "blobs_meta",
+    "replication_jobs",
+    "presets",
+    "authors",
+    "collections",
+    "works",
+    "activities",
+    "assets",
+    "device_blobs",
+    "edges",
+    "boards",
+    "strokes",
+    "annotations",
+    "cards",
+    "review_logs",
+    "folder_sources",
+];
+
+/// Rows written between two `export-progress` events
+const EXPORT_PROGRESS_INTERVAL: u64 = 500;
+
+/// Set by `cancel_export`; checked between rows by the running export
+static EXPORT_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportProgress {
+    pub table: String,
+    pub table_index: usize,
+    pub table_count: usize,
+    pub rows_written: u64,
+    pub bytes_written: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportResult {
+    pub path: String,
+    /// Size of the file on disk (after compression, if any)
+    pub bytes: u64,
+}
+
+/**
+ * Destination of a file export, optionally compressed
+ */
+enum ExportSink {
+    Plain(std::io::BufWriter<std::fs::File>),
+    Gzip(flate2::write::GzEncoder<std::io::BufWriter<std::fs::File>>),
+    Zstd(zstd::stream::write::Encoder<'static, std::io::BufWriter<std::fs::File>>),
+}
+
+impl ExportSink {
+    /**
+     * Open `path` for writing with the given compression ("gzip", "zstd" or None)
+     */
+    fn create(path: &std::path::Path, compress: Option<&str>) -> CommandResult<Self> {
+        if let Some(other) = compress.filter(|c| !matches!(*c, "gzip" | "zstd")) {
+            return Err(CommandError::InvalidInput(format!(
+                "Unknown compression: {} (expected gzip or zstd)",
+                other
+            )));
+        }
+        
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        Ok(match compress {
+            Some("gzip") => ExportSink::Gzip(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::default(),
+            )),
+            Some("zstd") => ExportSink::Zstd(zstd::stream::write::Encoder::new(file, 0)?),
+            _ => ExportSink::Plain(file),
+        })
+    }
+    
+    /**
+     * Write any encoder trailer and fsync the file
+     */
+    fn finish(self) -> std::io::Result<()> {
+        let writer = match self {
+            ExportSink::Plain(writer) => writer,
+            ExportSink::Gzip(encoder) => encoder.finish()?,
+            ExportSink::Zstd(encoder) => encoder.finish()?,
+        };
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()
+    }
+}
+
+impl std::io::Write for ExportSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            ExportSink::Plain(w) => w.write(buf),
+            ExportSink::Gzip(w) => w.write(buf),
+            ExportSink::Zstd(w) => w.write(buf),
+        }
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            ExportSink::Plain(w) => w.flush(),
+            ExportSink::Gzip(w) => w.flush(),
+            ExportSink::Zstd(w) => w.flush(),
+        }
+    }
+}
+
+/**
+ * Open an export file for reading, transparently decompressing gzip/zstd by magic bytes
+ */
+fn open_export_reader(file: std::fs::File) -> std::io::Result<Box<dyn std::io::Read + Send>> {
+    use std::io::BufRead;
+    
+    let mut reader = std::io::BufReader::new(file);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(std::io::BufReader::new(flate2::read::MultiGzDecoder::new(reader))))
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Ok(Box::new(std::io::BufReader::new(zstd::stream::read::Decoder::with_buffer(reader)?)))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/**
+ * Writer wrapper that counts bytes for progress reporting
+ */
+struct CountingWriter<W: std::io::Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: std::io::Write> std::io::Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/**
+ * Export all Postgres tables to a JSON file, streaming row by row
+ * 
+ * The file has the shape `{"version", "exportedAt", "tables": {"<name>": [rows...]}}`.
+ * Data is written to `<dest>.partial` and renamed on success, so a cancelled or failed
+ * export never leaves a truncated file at `dest_path`.
+ * 
+ * `compress` ("gzip" or "zstd") streams the JSON through an encoder and appends
+ * `.gz`/`.zst` to the destination unless it already ends with it. Progress byte
+ * counts refer to the uncompressed JSON.
+ */
+#[tauri::command]
+pub async fn export_all_data_to_file(
+    app: AppHandle,
+    dest_path: String,
+    compress: Option<String>,
+) -> CommandResult<ExportResult> {
+    use futures_util::TryStreamExt;
+    use std::io::Write;
+    
+    EXPORT_CANCELLED.store(false, Ordering::SeqCst);
+    
+    let dest_path = match compress.as_deref() {
+        Some("gzip") if !dest_path.ends_with(".gz") => format!("{}.gz", dest_path),
+        Some("zstd") if !dest_path.ends_with(".zst") => format!("{}.zst", dest_path),
+        _ => dest_path,
+    };
+    let dest = std::path::PathBuf::from(&dest_path);
+    let partial = std::path::PathBuf::from(format!("{}.partial", dest_path));
+    
+    let client = get_pg_client().await?;
+    let sink = ExportSink::create(&partial, compress.as_deref())?;
+    let mut out = CountingWriter {
+        inner: sink,
+        count: 0,
+    };
+    
+    let result: CommandResult<()> = async {
+        write!(
+            out,
+            "{{\"version\":{},\"exportedAt\":{},\"tables\":{{",
+            serde_json::to_string(EXPORT_FORMAT_VERSION)?,
+            serde_json::to_string(&chrono::Utc::now().to_rfc3339())?
+        )?;
+        
+        for (table_index, table) in EXPORT_TABLES.iter().enumerate() {
+            if table_index > 0 {
+                out.write_all(b",")?;
+            }
+            write!(out, "{}:[", serde_json::to_string(table)?)?;
+            
+            let query = format!("SELECT * FROM {}", table);
+            log_statement(&query, 0);
+            let rows = client
+                .query_raw(&query, std::iter::empty::<&(dyn tokio_postgres::types::ToSql + Sync)>())
+                .await
+                .map_err(|e| CommandError::Database(format!("Failed to export {}: {}", table, e)))?;
+            futures_util::pin_mut!(rows);
+            
+            let mut rows_written: u64 = 0;
+            while let Some(row) = rows.try_next().await? {
+                if EXPORT_CANCELLED.load(Ordering::SeqCst) {
+                    return Err(CommandError::Cancelled("Export cancelled".to_string()));
+                }
+                if rows_written > 0 {
+                    out.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut out, &row_to_json(&row))?;
+                rows_written += 1;
+                
+                if rows_written.is_multiple_of(EXPORT_PROGRESS_INTERVAL) {
+                    let _ = app.emit("export-progress", ExportProgress {
+                        table: table.to_string(),
+                        table_index,
+                        table_count: EXPORT_TABLES.len(),
+                        rows_written,
+                        bytes_written: out.count,
+                    });
+                }
+            }
+            
+            out.write_all(b"]")?;
+            println!("[Export] {}: {} rows", table, rows_written);
+            let _ = app.emit("export-progress", ExportProgress {
+                table: table.to_string(),
+                table_index,
+                table_count: EXPORT_TABLES.len(),
+                rows_written,
+                bytes_written: out.count,
+            });
+        }
+        
+        out.write_all(b"}}")?;
+        out.flush()?;
+        Ok(())
+    }
+    .await;
+    
+    let result = result.and_then(|_| Ok(out.inner.finish()?));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        println!("[Export] Aborted: {}", e);
+        return Err(e);
+    }
+    
+    let bytes = std::fs::metadata(&partial)?.len();
+    std::fs::rename(&partial, &dest)?;
+    println!("[Export] Wrote {} bytes to {}", bytes, dest.display());
+    
+    Ok(ExportResult {
+        path: dest.to_string_lossy().to_string(),
+        bytes,
+    })
+}
+
+/**
+ * Export all data to a JSON file
+ * 
+ * Dumps every table in `EXPORT_TABLES` to `dest_path` as an uncompressed
+ * `{"version", "exportedAt", "tables": {...}}` document. The file is streamed from the
+ * database rather than returned over IPC, so large libraries don't hit message limits.
+ */
+#[tauri::command]
+pub async fn export_all_data(app: AppHandle, dest_path: String) -> CommandResult<ExportResult> {
+    export_all_data_to_file(app, dest_path, None).await
+}
+
+/// Ask a running `export_all_data_to_file` to stop after the current row
+#[tauri::command]
+pub async fn cancel_export() -> CommandResult<()> {
+    EXPORT_CANCELLED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableSizeEstimate {
+    pub table: String,
+    pub rows: i64,
+    /// On-disk size including indexes and TOAST (`pg_total_relation_size`)
+    pub bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSizeEstimate {
+    pub tables: Vec<TableSizeEstimate>,
+    pub database_bytes: i64,
+    /// Total size of the local blob store, from the catalog
+    pub blob_bytes: i64,
+    pub total_bytes: i64,
+}
+
+/**
+ * Estimate how much an export will take, per table plus the blob store
+ * 
+ * Table sizes are Postgres' on-disk sizes, so they over-estimate the JSON a little
+ * for index-heavy tables; good enough to warn before running out of disk space.
+ * Tables missing from the database are left out.
+ */
+#[tauri::command]
+pub async fn estimate_export_size() -> CommandResult<ExportSizeEstimate> {
+    let client = get_pg_client().await?;
+    
+    let mut tables = Vec::new();
+    for table in EXPORT_TABLES {
+        let size = client
+            .query_one("SELECT pg_total_relation_size(to_regclass($1))", &[table])
+            .await
+            .map_err(|e| CommandError::Database(format!("Failed to size {}: {}", table, e)))?;
+        let Some(bytes) = size.get::<_, Option<i64>>(0) else {
+            continue;
+        };
+        
+        let rows: i64 = client
+            .query_one(&format!("SELECT count(*) FROM {}", table), &[])
+            .await
+            .map_err(|e| CommandError::Database(format!("Failed to count {}: {}", table, e)))?
+            .get(0);
+        
+        tables.push(TableSizeEstimate {
+            table: table.to_string(),
+            rows,
+            bytes,
+        });
+    }
+    
+    let database_bytes = tables.iter().map(|t| t.bytes).sum();
+    let blob_bytes = {
+        let conn = crate::db::get_connection()?;
+        crate::db::get_stats(&conn)?.total_size
+    };
+    
+    println!(
+        "[Export] Estimated {} bytes ({} database, {} blobs)",
+        database_bytes + blob_bytes, database_bytes, blob_bytes
+    );
+    
+    Ok(ExportSizeEstimate {
+        tables,
+        database_bytes,
+        blob_bytes,
+        total_bytes: database_bytes + blob_bytes,
+    })
+}
+
+/// Rows handed from the parser thread to the database per message
+const IMPORT_BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportProgress {
+    pub table: String,
+    pub rows_imported: u64,
+    pub rows_skipped: u64,
+    pub rows_failed: u64,
+}
+
+impl From<&ImportDataResult> for ImportProgress {
+    fn from(result: &ImportDataResult) -> Self {
+        ImportProgress {
+            table: result.table.clone(),
+            rows_imported: result.inserted,
+            rows_skipped: result.skipped,
+            rows_failed: result.failed,
+        }
+    }
+}
+
+/**
+ * Per-table outcome of `import_data` and `import_data_from_file`
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportDataResult {
+    pub table: String,
+    pub inserted: u64,
+    /// Rows that already existed and were kept (`on_conflict: skip`)
+    pub skipped: u64,
+    pub failed: u64,
+}
+
+impl ImportDataResult {
+    fn new(table: &str) -> Self {
+        ImportDataResult {
+            table: table.to_string(),
+            inserted: 0,
+            skipped: 0,
+            failed: 0,
+        }
+    }
+    
+    fn rows_seen(&self) -> u64 {
+        self.inserted + self.skipped + self.failed
+    }
+}
+
+/**
+ * Insert exported rows into `result.table` inside the caller's transaction
+ * 
+ * Rows go through the same insert path as `flush_writes`. Each runs under a savepoint,
+ * so a row that fails is rolled back and counted without aborting the table.
+ */
+async fn import_rows(
+    client: &Client,
+    rows: &[Value],
+    on_conflict: OnConflict,
+    result: &mut ImportDataResult,
+) -> CommandResult<()> {
+    for row in rows {
+        let index = result.rows_seen();
+        let change = WriteChange {
+            id: format!("{}#{}", result.table, index),
+            table: result.table.clone(),
+            op: WriteOperation::Insert,
+            payload: row.clone(),
+            created_at: 0,
+            status: "pending".to_string(),
+            retry_count: 0,
+            jsonb_merge: false,
+            hard_delete: false,
+        };
+        
+        client.batch_execute("SAVEPOINT import_row").await?;
+        match apply_insert_with(client, &change, on_conflict).await {
+            Ok(value) => {
+                client.batch_execute("RELEASE SAVEPOINT import_row").await?;
+                if value.is_null() {
+                    result.skipped += 1;
+                } else {
+                    result.inserted += 1;
+                }
+            }
+            Err(e) => {
+                client.batch_execute("ROLLBACK TO SAVEPOINT import_row").await?;
+                println!("[Import] {} row {} failed: {}", result.table, index, e);
+                result.failed += 1;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/**
+ * Import a document produced by `export_all_data`
+ * 
+ * Tables are applied parents first (`EXPORT_TABLES`, the reverse of the clear order),
+ * each in its own transaction, through the same insert path as `flush_writes`. A row
+ * that fails is rolled back to a savepoint and counted without aborting its table.
+ * `on_conflict` decides whether existing rows are kept (`skip`, the default) or
+ * replaced (`overwrite`).
+ */
 #[tauri::command]
-pub async fn export_all_data() -> Result<String, String> {
-    // TODO: Export all data from Dexie + Postgres
-    // For MVP, this should:
-    // 1. Export Dexie IndexedDB data (via frontend)
-    // 2. Export Postgres data (via API or direct connection)
-    
-    println!("TODO: Implement full data export");
-    
-    // Placeholder return
-    let export_data = serde_json::json!({
-        "version": "1.0",
-        "exportedAt": chrono::Utc::now().to_rfc3339(),
-        "works": [],
-        "assets": [],
-        "annotations": [],
-        "cards": [],
-        "activities": [],
-        "collections": [],
-        "message": "Export not yet implemented"
+pub async fn import_data(
+    data: String,
+    on_conflict: Option<OnConflict>,
+) -> CommandResult<Vec<ImportDataResult>> {
+    let parsed: Value = serde_json::from_str(&data)?;
+    
+    let version = parsed.get("version").and_then(|v| v.as_str());
+    if version != Some(EXPORT_FORMAT_VERSION) {
+        return Err(CommandError::InvalidInput(format!(
+            "Unsupported export version {} (expected {})",
+            version.unwrap_or("(none)"),
+            EXPORT_FORMAT_VERSION
+        )));
+    }
+    let tables = parsed.get("tables")
+        .and_then(|t| t.as_object())
+        .ok_or_else(|| CommandError::InvalidInput("Export is missing its tables".to_string()))?;
+    if let Some(unknown) = tables.keys().find(|t| !EXPORT_TABLES.contains(&t.as_str())) {
+        return Err(CommandError::InvalidInput(format!("Unknown table in export: {}", unknown)));
+    }
+    
+    let on_conflict = on_conflict.unwrap_or(OnConflict::Skip);
+    let client = get_pg_client().await?;
+    let mut results = Vec::new();
+    
+    for table in EXPORT_TABLES {
+        let Some(rows) = tables.get(*table) else {
+            continue;
+        };
+        let rows = rows.as_array().ok_or_else(|| {
+            CommandError::InvalidInput(format!("Expected an array of rows for {}", table))
+        })?;
+        
+        println!("[Import] Importing {} rows into {}", rows.len(), table);
+        client.batch_execute("BEGIN").await?;
+        
+        let mut result = ImportDataResult::new(table);
+        let imported = import_rows(&client, rows, on_conflict, &mut result).await;
+        
+        match imported {
+            Ok(()) => {
+                client.batch_execute("COMMIT").await?;
+                println!(
+                    "[Import] {}: {} inserted, {} skipped, {} failed",
+                    table, result.inserted, result.skipped, result.failed
+                );
+                results.push(result);
+            }
+            Err(e) => {
+                let _ = client.batch_execute("ROLLBACK").await;
+                return Err(e);
+            }
+        }
+    }
+    
+    Ok(results)
+}
+
+/**
+ * Events produced while streaming an export file off disk
+ */
+enum ImportEvent {
+    BeginTable(String),
+    Rows(Vec<Value>),
+    EndTable,
+}
+
+/**
+ * Top-level visitor for an export file: checks `version` before any table is read
+ */
+struct ExportFileVisitor {
+    tx: tokio::sync::mpsc::Sender<ImportEvent>,
+}
+
+impl<'de> serde::de::Visitor<'de> for ExportFileVisitor {
+    type Value = ();
+    
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a DeepRecall export object")
+    }
+    
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        use serde::de::Error;
+        
+        let mut version_checked = false;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "version" => {
+                    let version: String = map.next_value()?;
+                    if version != EXPORT_FORMAT_VERSION {
+                        return Err(A::Error::custom(format!(
+                            "Unsupported export version {} (expected {})",
+                            version, EXPORT_FORMAT_VERSION
+                        )));
+                    }
+                    version_checked = true;
+                }
+                "tables" => {
+                    if !version_checked {
+                        return Err(A::Error::custom("Export file is missing its version header"));
+                    }
+                    map.next_value_seed(TablesSeed { tx: &self.tx })?;
+                }
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+        
+        if !version_checked {
+            return Err(A::Error::custom("Export file is missing its version header"));
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Visits the `tables` object, one key per table
+ */
+struct TablesSeed<'a> {
+    tx: &'a tokio::sync::mpsc::Sender<ImportEvent>,
+}
+
+impl<'de> serde::de::DeserializeSeed<'de> for TablesSeed<'_> {
+    type Value = ();
+    
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for TablesSeed<'_> {
+    type Value = ();
+    
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a map of table names to row arrays")
+    }
+    
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        use serde::de::Error;
+        
+        while let Some(table) = map.next_key::<String>()? {
+            if !EXPORT_TABLES.contains(&table.as_str()) {
+                return Err(A::Error::custom(format!("Unknown table in export: {}", table)));
+            }
+            send_import_event(self.tx, ImportEvent::BeginTable(table)).map_err(A::Error::custom)?;
+            map.next_value_seed(RowsSeed { tx: self.tx })?;
+            send_import_event(self.tx, ImportEvent::EndTable).map_err(A::Error::custom)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Visits one table's row array, forwarding rows in batches
+ */
+struct RowsSeed<'a> {
+    tx: &'a tokio::sync::mpsc::Sender<ImportEvent>,
+}
+
+impl<'de> serde::de::DeserializeSeed<'de> for RowsSeed<'_> {
+    type Value = ();
+    
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for RowsSeed<'_> {
+    type Value = ();
+    
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an array of rows")
+    }
+    
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        use serde::de::Error;
+        
+        let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+        while let Some(row) = seq.next_element::<Value>()? {
+            batch.push(row);
+            if batch.len() == IMPORT_BATCH_SIZE {
+                let full = std::mem::replace(&mut batch, Vec::with_capacity(IMPORT_BATCH_SIZE));
+                send_import_event(self.tx, ImportEvent::Rows(full)).map_err(A::Error::custom)?;
+            }
+        }
+        if !batch.is_empty() {
+            send_import_event(self.tx, ImportEvent::Rows(batch)).map_err(A::Error::custom)?;
+        }
+        Ok(())
+    }
+}
+
+fn send_import_event(
+    tx: &tokio::sync::mpsc::Sender<ImportEvent>,
+    event: ImportEvent,
+) -> Result<(), &'static str> {
+    tx.blocking_send(event).map_err(|_| "Import aborted")
+}
+
+/**
+ * Import an export file produced by `export_all_data_to_file`, streaming it from disk
+ * 
+ * The file is parsed on a blocking thread and rows are applied in one transaction per
+ * table on a dedicated connection, row by row as in `import_data` (including its
+ * `on_conflict` handling). The version header is checked before the database is
+ * touched. Gzip and zstd compressed exports are detected by their magic bytes and
+ * decompressed on the fly.
+ */
+#[tauri::command]
+pub async fn import_data_from_file(
+    app: AppHandle,
+    path: String,
+    on_conflict: Option<OnConflict>,
+) -> CommandResult<Vec<ImportDataResult>> {
+    let on_conflict = on_conflict.unwrap_or(OnConflict::Skip);
+    let file = std::fs::File::open(&path)?;
+    
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<ImportEvent>(4);
+    let parser = tokio::task::spawn_blocking(move || -> Result<(), String> {
+        use serde::Deserializer as _;
+        
+        let reader = open_export_reader(file).map_err(|e| e.to_string())?;
+        let mut de = serde_json::Deserializer::from_reader(reader);
+        de.deserialize_map(ExportFileVisitor { tx })
+            .and_then(|_| de.end())
+            .map_err(|e| e.to_string())
     });
     
-    serde_json::to_string_pretty(&export_data).map_err(|e| e.to_string())
+    let mut client: Option<PgClient> = None;
+    let mut current: Option<ImportDataResult> = None;
+    let mut results = Vec::new();
+    
+    let outcome: CommandResult<()> = async {
+        while let Some(event) = rx.recv().await {
+            match event {
+                ImportEvent::BeginTable(table) => {
+                    if client.is_none() {
+                        client = Some(get_pg_client().await?);
+                    }
+                    client.as_ref().unwrap().batch_execute("BEGIN").await?;
+                    println!("[Import] Importing table: {}", table);
+                    current = Some(ImportDataResult::new(&table));
+                }
+                ImportEvent::Rows(rows) => {
+                    let progress = current.as_mut().unwrap();
+                    import_rows(client.as_ref().unwrap(), &rows, on_conflict, progress).await?;
+                    let _ = app.emit("import-progress", ImportProgress::from(&*progress));
+                }
+                ImportEvent::EndTable => {
+                    client.as_ref().unwrap().batch_execute("COMMIT").await?;
+                    let done = current.take().unwrap();
+                    println!(
+                        "[Import] {}: {} inserted, {} skipped, {} failed",
+                        done.table, done.inserted, done.skipped, done.failed
+                    );
+                    let _ = app.emit("import-progress", ImportProgress::from(&done));
+                    results.push(done);
+                }
+            }
+        }
+        Ok(())
+    }
+    .await;
+    
+    // Stop the parser if we bailed out early, then collect its verdict
+    drop(rx);
+    let parsed = parser.await
+        .map_err(|e| CommandError::Internal(format!("Import parser crashed: {}", e)))?;
+    
+    if current.is_some() {
+        // Either we failed mid-table or the file ended mid-table
+        if let Some(db) = client.as_ref() {
+            let _ = db.batch_execute("ROLLBACK").await;
+        }
+    }
+    
+    outcome?;
+    parsed.map_err(|e| CommandError::InvalidInput(format!("Invalid export file: {}", e)))?;
+    
+    Ok(results)
 }
 
-/// Estimate export size
+/**
+ * Summary of a `merge_authors` run
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeAuthorsResult {
+    pub works_updated: u64,
+    pub authors_removed: u64,
+    /// Whether the kept author took over an avatar from one of the merged records
+    pub avatar_adopted: bool,
+    /// Legacy (pre-blob-store) avatar files of merged authors that were deleted
+    pub avatar_files_removed: i32,
+}
+
+/**
+ * Merge duplicate author records into `keep_id`
+ * 
+ * In one transaction: rewrites `works.author_ids` to point at the kept author (dropping
+ * duplicates while preserving order), lets the kept author adopt the first merged avatar
+ * if it has none, and deletes the merged authors.
+ * 
+ * Avatars are blobs referenced by hash, so adopting one just repoints the kept author at
+ * the donor's hashes. Blob-store files are never deleted here (the content may be shared);
+ * only legacy avatar files of the merged authors are removed, after the commit succeeds.
+ */
 #[tauri::command]
-pub async fn estimate_export_size() -> Result<i64, String> {
-    // TODO: Calculate estimated size of export
-    // For MVP, return placeholder
+pub async fn merge_authors(
+    keep_id: String,
+    merge_ids: Vec<String>,
+    user_id: Option<String>,
+) -> CommandResult<MergeAuthorsResult> {
+    let parse = |id: &str| {
+        uuid::Uuid::parse_str(id)
+            .map_err(|e| CommandError::InvalidInput(format!("Invalid author id {}: {}", id, e)))
+    };
+    let keep = parse(&keep_id)?;
+    let merged = merge_ids
+        .iter()
+        .map(|id| parse(id))
+        .collect::<CommandResult<Vec<_>>>()?;
+    if merged.is_empty() {
+        return Err(CommandError::InvalidInput("No authors to merge".to_string()));
+    }
+    if merged.contains(&keep) {
+        return Err(CommandError::InvalidInput(format!(
+            "Author {} cannot be merged into itself",
+            keep_id
+        )));
+    }
+    
+    let mut client = get_pg_client().await?;
+    let tx = client.transaction().await?;
+    if let Some(uid) = &user_id {
+        tx.execute("SELECT set_config('app.user_id', $1, true)", &[uid]).await?;
+    }
+    
+    let kept_row = tx
+        .query_opt("SELECT avatar_display_path FROM authors WHERE id = $1", &[&keep])
+        .await?
+        .ok_or_else(|| CommandError::NotFound(format!("Author not found: {}", keep_id)))?;
+    let kept_has_avatar = kept_row.get::<_, Option<String>>(0).is_some();
+    
+    let merged_rows = tx
+        .query(
+            "SELECT id, avatar_original_path, avatar_display_path, avatar_crop_region
+             FROM authors WHERE id = ANY($1)",
+            &[&merged],
+        )
+        .await?;
+    
+    let works_updated = tx
+        .execute(
+            "UPDATE works SET author_ids = (
+                 SELECT COALESCE(array_agg(author_id ORDER BY first_pos), '{}')
+                 FROM (
+                     SELECT CASE WHEN a = ANY($2) THEN $1 ELSE a END AS author_id,
+                            MIN(pos) AS first_pos
+                     FROM unnest(author_ids) WITH ORDINALITY AS t(a, pos)
+                     GROUP BY 1
+                 ) deduped
+             )
+             WHERE author_ids && $2",
+            &[&keep, &merged],
+        )
+        .await?;
+    
+    // Adopt the first merged avatar by pointing the kept author at the same references
+    let mut adopted: Vec<String> = Vec::new();
+    if !kept_has_avatar {
+        let donor = merged_rows
+            .iter()
+            .find(|row| row.get::<_, Option<String>>(2).is_some());
+        if let Some(row) = donor {
+            let original: Option<String> = row.get(1);
+            let display: Option<String> = row.get(2);
+            let crop: Option<Value> = row.get(3);
+            tx.execute(
+                "UPDATE authors
+                 SET avatar_original_path = $2, avatar_display_path = $3,
+                     avatar_crop_region = $4
+                 WHERE id = $1",
+                &[&keep, &original, &display, &crop.map(Json)],
+            )
+            .await?;
+            adopted.extend(original.into_iter().chain(display));
+        }
+    }
+    let avatar_adopted = !adopted.is_empty();
+    
+    let authors_removed = tx
+        .execute("DELETE FROM authors WHERE id = ANY($1)", &[&merged])
+        .await?;
+    tx.commit().await?;
+    
+    let mut avatar_files_removed = 0;
+    for row in &merged_rows {
+        for reference in [row.get::<_, Option<String>>(1), row.get::<_, Option<String>>(2)]
+            .into_iter()
+            .flatten()
+        {
+            if adopted.contains(&reference) {
+                continue;
+            }
+            match crate::commands::avatars::avatar_blob_hash(&reference) {
+                Ok(None) => {}
+                // Blob-store avatars may be shared; leave them to the reference checks
+                Ok(Some(_)) => continue,
+                Err(e) => {
+                    println!("[Database] Failed to classify avatar {}: {}", reference, e);
+                    continue;
+                }
+            }
+            let path = match crate::commands::avatars::legacy_avatar_file(&reference) {
+                Ok(Some(path)) => path,
+                Ok(None) => continue,
+                Err(e) => {
+                    println!("[Database] Not removing avatar {}: {}", reference, e);
+                    continue;
+                }
+            };
+            match std::fs::remove_file(&path) {
+                Ok(()) => avatar_files_removed += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => println!("[Database] Failed to remove avatar {}: {}", reference, e),
+            }
+        }
+    }
     
-    println!("TODO: Implement export size estimation");
-    Ok(0)
+    println!(
+        "[Database] Merged {} authors into {}: {} works updated",
+        authors_removed, keep_id, works_updated
+    );
+    
+    Ok(MergeAuthorsResult {
+        works_updated,
+        authors_removed,
+        avatar_adopted,
+        avatar_files_removed,
+    })
+}
+
+/// Columns (and their `udt_name`) the write path relies on, per table
+/// 
+/// Not a full schema: ids and owner_id for RLS, JSONB columns from `JSONB_COLUMNS`, and
+/// the columns `transform_annotation_data` produces.
+const EXPECTED_COLUMNS: &[(&str, &[(&str, &str)])] = &[
+    ("works", &[("id", "uuid"), ("owner_id", "uuid"), ("author_ids", "_uuid"), ("authors", "jsonb"), ("metadata", "jsonb")]),
+    ("assets", &[("id", "uuid"), ("owner_id", "uuid"), ("sha256", "text"), ("metadata", "jsonb")]),
+    ("activities", &[("id", "uuid"), ("owner_id", "uuid"), ("metadata", "jsonb")]),
+    ("collections", &[("id", "uuid"), ("owner_id", "uuid"), ("metadata", "jsonb")]),
+    ("edges", &[("id", "uuid"), ("owner_id", "uuid"), ("metadata", "jsonb")]),
+    ("presets", &[("id", "uuid"), ("owner_id", "uuid"), ("core_field_config", "jsonb"), ("custom_fields", "jsonb")]),
+    ("authors", &[("id", "uuid"), ("owner_id", "uuid"), ("avatar_original_path", "text"), ("avatar_display_path", "text"), ("avatar_crop_region", "jsonb")]),
+    ("annotations", &[("id", "text"), ("owner_id", "uuid"), ("sha256", "text"), ("kind", "text"), ("type", "text"), ("geometry", "jsonb"), ("style", "jsonb"), ("content", "text"), ("metadata", "jsonb")]),
+    ("cards", &[("id", "uuid"), ("owner_id", "uuid")]),
+    ("review_logs", &[("id", "uuid"), ("owner_id", "uuid")]),
+    ("boards", &[("id", "uuid"), ("owner_id", "uuid")]),
+    ("strokes", &[("id", "uuid"), ("owner_id", "uuid"), ("board_id", "uuid"), ("points", "jsonb"), ("style", "jsonb"), ("bounding_box", "jsonb")]),
+    ("blobs_meta", &[("sha256", "text"), ("owner_id", "uuid"), ("size", "int8"), ("mime", "text"), ("filename", "text"), ("created_ms", "int8")]),
+    ("device_blobs", &[("id", "uuid"), ("owner_id", "uuid"), ("device_id", "text"), ("sha256", "text"), ("present", "bool"), ("health", "text"), ("created_ms", "int8")]),
+    ("folder_sources", &[("id", "uuid"), ("owner_id", "uuid")]),
+];
+
+/// Set once the schema has been checked after the first successful connect
+static SCHEMA_CHECKED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnTypeMismatch {
+    pub column: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableSchemaReport {
+    pub table: String,
+    pub exists: bool,
+    pub missing_columns: Vec<String>,
+    /// NOT NULL columns without a default that the app doesn't know how to fill
+    pub unexpected_columns: Vec<String>,
+    pub type_mismatches: Vec<ColumnTypeMismatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaReport {
+    /// True when no table has any discrepancy
+    pub ok: bool,
+    pub tables: Vec<TableSchemaReport>,
+    /// Tables that couldn't be inspected, with the error
+    pub errors: Vec<String>,
+}
+
+/**
+ * Compare the live schema of every table in `EXPECTED_COLUMNS` against what the app expects
+ */
+async fn check_pg_schema(client: &Client) -> SchemaReport {
+    let mut tables = Vec::new();
+    let mut errors = Vec::new();
+    
+    for (table, expected) in EXPECTED_COLUMNS {
+        let columns = match fetch_table_columns(client, table).await {
+            Ok(columns) => columns,
+            Err(e) => {
+                errors.push(format!("{}: {}", table, e));
+                continue;
+            }
+        };
+        
+        let mut report = TableSchemaReport {
+            table: table.to_string(),
+            exists: !columns.is_empty(),
+            missing_columns: Vec::new(),
+            unexpected_columns: Vec::new(),
+            type_mismatches: Vec::new(),
+        };
+        if report.exists {
+            for (name, expected_type) in expected.iter() {
+                match column_type(&columns, name) {
+                    None => report.missing_columns.push(name.to_string()),
+                    Some(actual) if actual != *expected_type => {
+                        report.type_mismatches.push(ColumnTypeMismatch {
+                            column: name.to_string(),
+                            expected: expected_type.to_string(),
+                            actual: actual.to_string(),
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+            report.unexpected_columns = columns
+                .iter()
+                .filter(|c| !c.is_nullable && !c.has_default)
+                .filter(|c| !expected.iter().any(|(name, _)| *name == c.name))
+                .filter(|c| !matches!(c.name.as_str(), "kind" | "created_at" | "updated_at"))
+                .map(|c| c.name.clone())
+                .collect();
+        }
+        tables.push(report);
+    }
+    
+    let ok = errors.is_empty()
+        && tables.iter().all(|t| {
+            t.exists
+                && t.missing_columns.is_empty()
+                && t.unexpected_columns.is_empty()
+                && t.type_mismatches.is_empty()
+        });
+    SchemaReport { ok, tables, errors }
 }
 
-/// Import data from JSON
+/**
+ * Write schema discrepancies to the app log
+ */
+fn log_schema_report(report: &SchemaReport) {
+    if report.ok {
+        crate::app_log!("[Database] Schema check passed");
+        return;
+    }
+    
+    for table in &report.tables {
+        if !table.exists {
+            crate::app_log!("[Database] Schema: table {} does not exist", table.table);
+            continue;
+        }
+        if !table.missing_columns.is_empty() {
+            crate::app_log!("[Database] Schema: {} is missing columns {:?}", table.table, table.missing_columns);
+        }
+        if !table.unexpected_columns.is_empty() {
+            crate::app_log!("[Database] Schema: {} has unexpected required columns {:?}", table.table, table.unexpected_columns);
+        }
+        for mismatch in &table.type_mismatches {
+            crate::app_log!(
+                "[Database] Schema: {}.{} is {} (expected {})",
+                table.table, mismatch.column, mismatch.actual, mismatch.expected
+            );
+        }
+    }
+    for error in &report.errors {
+        crate::app_log!("[Database] Schema check failed for {}", error);
+    }
+}
+
+/**
+ * Check that the Postgres schema matches what the app expects (missing columns, unknown
+ * required columns, type mismatches). Also runs once after the first connect.
+ */
 #[tauri::command]
-pub async fn import_data(data: String) -> Result<(), String> {
-    // TODO: Import data into Postgres
-    // Parse JSON and insert into database tables
+pub async fn verify_pg_schema() -> CommandResult<SchemaReport> {
+    let client = get_pg_client().await?;
+    let report = check_pg_schema(&client).await;
+    log_schema_report(&report);
+    Ok(report)
+}
+
+/**
+ * Describe a table's columns (name, Postgres type, nullability, primary key) for the admin panel
+ */
+#[tauri::command]
+pub async fn describe_table(table: String) -> CommandResult<Vec<ColumnInfo>> {
+    if !SYNC_TABLES.contains(&table.as_str()) {
+        return Err(CommandError::InvalidInput(format!("Invalid table name: {}", table)));
+    }
     
-    let _parsed: Value = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let client = get_pg_client().await?;
+    let columns = fetch_table_columns(&client, &table)
+        .await
+        .map_err(CommandError::Database)?;
     
-    println!("TODO: Implement data import");
-    println!("Data preview: {} bytes", data.len());
+    Ok(columns.as_ref().clone())
+}
+
+/**
+ * Fetch a single row by primary key for the admin panel's inline editor
+ * 
+ * Saving goes through `flush_writes` as usual. Returns None if no row has that key.
+ */
+#[tauri::command]
+pub async fn get_row(table: String, id: String) -> CommandResult<Option<Value>> {
+    if !SYNC_TABLES.contains(&table.as_str()) {
+        return Err(CommandError::InvalidInput(format!("Invalid table name: {}", table)));
+    }
     
-    // For MVP, return success
-    // In production, this should:
-    // 1. Parse JSON
-    // 2. Validate data structure
-    // 3. Insert into Postgres with conflict resolution
-    // 4. Trigger Electric sync
+    let client = get_pg_client().await?;
+    let columns = get_table_columns(&client, &table)
+        .await
+        .map_err(CommandError::Database)?;
     
-    Ok(())
+    let key_columns: Vec<&ColumnInfo> = columns.iter().filter(|c| c.is_primary_key).collect();
+    let key = match key_columns.as_slice() {
+        [key] => *key,
+        _ => {
+            return Err(CommandError::InvalidInput(format!(
+                "Table {} has no single-column primary key",
+                table
+            )))
+        }
+    };
+    
+    let query = format!("SELECT * FROM {} WHERE {} = $1", table, key.name);
+    log_statement(&query, 1);
+    let row = if key.data_type == "uuid" {
+        let uuid = uuid::Uuid::parse_str(&id)
+            .map_err(|e| CommandError::InvalidInput(format!("Invalid UUID {}: {}", id, e)))?;
+        client.query_opt(&query, &[&uuid]).await?
+    } else {
+        client.query_opt(&query, &[&id]).await?
+    };
+    
+    Ok(row.map(|row| row_to_json(&row)))
+}
+
+/**
+ * Filter on a top-level key inside a JSONB column: `column->>'path' = value`
+ */
+#[derive(Debug, Clone, Deserialize)]
+pub struct JsonFilter {
+    pub column: String,
+    pub path: String,
+    pub value: String,
+}
+
+/// Rows returned by `query_postgres_table` when no limit is given
+const DEFAULT_PAGE_SIZE: i64 = 100;
+/// Largest page `query_postgres_table` returns
+const MAX_PAGE_SIZE: i64 = 1000;
+
+/**
+ * One page of an admin table query, plus the number of rows matching in total
+ */
+#[derive(Debug, Serialize)]
+pub struct TablePage {
+    pub rows: Vec<Value>,
+    pub total: i64,
 }
 
 /**
  * Query Postgres table for admin panel
+ * 
+ * `json_filters` restrict rows by keys inside JSONB columns. Columns must be JSON(B)
+ * columns of the table and paths simple keys; both key and value are bound as parameters.
+ * `exclude_deleted` hides tombstoned rows on tables that soft-delete.
+ * 
+ * Pages with `limit` (default 100, at most 1000) and `offset`. `order_by` must be a
+ * column of the table; without it rows are ordered by `created_at DESC` when the table
+ * has that column.
  */
 #[tauri::command]
-pub async fn query_postgres_table(table: String) -> Result<Vec<Value>, String> {
+pub async fn query_postgres_table(
+    table: String,
+    json_filters: Option<Vec<JsonFilter>>,
+    exclude_deleted: Option<bool>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    order_by: Option<String>,
+    descending: Option<bool>,
+) -> CommandResult<TablePage> {
     let client = get_pg_client().await?;
     
     // Validate table name (security: prevent SQL injection)
-    let valid_tables = [
-        "works", "assets", "activities", "collections", "edges", "folder_sources", "presets",
-        "authors", "annotations", "cards", "review_logs", "boards", "strokes",
-        "blobs_meta", "device_blobs"
-    ];
+    if !SYNC_TABLES.contains(&table.as_str()) {
+        return Err(CommandError::InvalidInput(format!("Invalid table name: {}", table)));
+    }
+    
+    let table_columns = get_table_columns(&client, &table)
+        .await
+        .map_err(CommandError::Database)?;
+    
+    // Only real columns of the table may be interpolated into ORDER BY
+    let order = match order_by {
+        Some(column) => {
+            if column_type(&table_columns, &column).is_none() {
+                return Err(CommandError::InvalidInput(format!(
+                    "Cannot order {} by unknown column: {}",
+                    table, column
+                )));
+            }
+            Some((column, descending.unwrap_or(false)))
+        }
+        None => column_type(&table_columns, "created_at")
+            .map(|_| ("created_at".to_string(), descending.unwrap_or(true))),
+    };
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(0, MAX_PAGE_SIZE);
+    let offset = offset.unwrap_or(0).max(0);
+    
+    let filters = json_filters.unwrap_or_default();
+    let exclude_deleted = exclude_deleted.unwrap_or(false);
+    let mut predicates = Vec::new();
+    let mut filter_params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::new();
+    if exclude_deleted {
+        if column_type(&table_columns, "deleted_at").is_some() {
+            predicates.push("deleted_at IS NULL".to_string());
+        }
+        if column_type(&table_columns, "is_deleted").is_some() {
+            predicates.push("is_deleted IS NOT TRUE".to_string());
+        }
+    }
     
-    if !valid_tables.contains(&table.as_str()) {
-        return Err(format!("Invalid table name: {}", table));
+    for filter in &filters {
+        let is_json = matches!(column_type(&table_columns, &filter.column), Some("jsonb" | "json"));
+        if !is_json {
+            return Err(CommandError::InvalidInput(format!(
+                "{}.{} is not a JSON column",
+                table, filter.column
+            )));
+        }
+        let simple_key = !filter.path.is_empty()
+            && filter.path.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !simple_key {
+            return Err(CommandError::InvalidInput(format!(
+                "Invalid JSON key '{}': only letters, digits and underscores are allowed",
+                filter.path
+            )));
+        }
+        
+        predicates.push(format!(
+            "{}->>${} = ${}",
+            filter.column,
+            filter_params.len() + 1,
+            filter_params.len() + 2
+        ));
+        filter_params.push(&filter.path);
+        filter_params.push(&filter.value);
     }
     
-    // Query all rows from table
-    // Try to order by created_at if it exists, otherwise just limit results
-    let query = format!("SELECT * FROM {} LIMIT 100", table);
-    let rows = client.query(&query, &[])
+    let where_clause = if predicates.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", predicates.join(" AND "))
+    };
+    
+    let count_query = format!("SELECT count(*) FROM {}{}", table, where_clause);
+    log_statement(&count_query, filter_params.len());
+    let total: i64 = client.query_one(&count_query, &filter_params[..])
+        .await
+        .map_err(|e| CommandError::Database(format!("Count failed: {}", e)))?
+        .get(0);
+    
+    let order_clause = match &order {
+        Some((column, true)) => format!(" ORDER BY {} DESC", column),
+        Some((column, false)) => format!(" ORDER BY {} ASC", column),
+        None => String::new(),
+    };
+    let query = format!(
+        "SELECT * FROM {}{}{} LIMIT {} OFFSET {}",
+        table, where_clause, order_clause, limit, offset
+    );
+    log_statement(&query, filter_params.len());
+    let rows = client.query(&query, &filter_params[..])
         .await
-        .map_err(|e| format!("Query failed: {}", e))?;
+        .map_err(|e| CommandError::Database(format!("Query failed: {}", e)))?;
     
     // Convert rows to JSON
     let results: Vec<Value> = rows
@@ -827,26 +3262,42 @@ pub async fn query_postgres_table(table: String) -> Result<Vec<Value>, String> {
         .map(|row| row_to_json(row))
         .collect();
     
-    Ok(results)
+    Ok(TablePage { rows: results, total })
+}
+
+/**
+ * Count rows in every admin table without transferring any row data
+ */
+#[tauri::command]
+pub async fn get_table_counts() -> CommandResult<HashMap<String, i64>> {
+    let client = get_pg_client().await?;
+    
+    let mut counts = HashMap::new();
+    for table in SYNC_TABLES.iter() {
+        let query = format!("SELECT count(*) FROM {}", table);
+        log_statement(&query, 0);
+        let row = client
+            .query_one(&query, &[])
+            .await
+            .map_err(|e| CommandError::Database(format!("Failed to count {}: {}", table, e)))?;
+        counts.insert(table.to_string(), row.get::<_, i64>(0));
+    }
+    
+    Ok(counts)
 }
 
 /// Query all Postgres tables at once (efficient for admin panel)
 #[tauri::command]
-pub async fn query_all_postgres_tables() -> Result<HashMap<String, Vec<Value>>, String> {
+pub async fn query_all_postgres_tables() -> CommandResult<HashMap<String, Vec<Value>>> {
     use std::collections::HashMap;
     
     let client = get_pg_client().await?;
     
-    let valid_tables = [
-        "works", "assets", "activities", "collections", "edges", "folder_sources", "presets",
-        "authors", "annotations", "cards", "review_logs", "boards", "strokes",
-        "blobs_meta", "device_blobs"
-    ];
-    
     let mut results = HashMap::new();
     
-    for table in valid_tables.iter() {
+    for table in SYNC_TABLES.iter() {
         let query = format!("SELECT * FROM {} LIMIT 1000", table);
+        log_statement(&query, 0);
         match client.query(&query, &[]).await {
             Ok(rows) => {
                 let table_data: Vec<Value> = rows
@@ -864,3 +3315,332 @@ pub async fn query_all_postgres_tables() -> Result<HashMap<String, Vec<Value>>,
     
     Ok(results)
 }
+
+/// Connect to the Postgres named by `DEEPRECALL_TEST_DATABASE_URL`, if set
+///
+/// Each test gets its own schema so tables like `folder_sources` can be created freely.
+#[cfg(test)]
+pub(crate) async fn test_pg_client(schema: &str) -> Option<Client> {
+    let url = env::var("DEEPRECALL_TEST_DATABASE_URL").ok()?;
+    let (client, connection) = tokio_postgres::connect(&url, tokio_postgres::NoTls)
+        .await
+        .expect("DEEPRECALL_TEST_DATABASE_URL should be reachable");
+    tokio::spawn(connection);
+    client
+        .batch_execute(&format!(
+            "DROP SCHEMA IF EXISTS {0} CASCADE; CREATE SCHEMA {0}; SET search_path TO {0}",
+            schema
+        ))
+        .await
+        .unwrap();
+    Some(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use postgres_types::{FromSql, IsNull, Type};
+    use serde_json::json;
+
+    /// Bind a JSON value the way inserts do and return the wire bytes (None for NULL)
+    fn bind(ty: &Type, column: &str, column_type: &str, value: Value) -> Option<bytes::BytesMut> {
+        let param = json_to_param("t", column, Some(column_type), &value);
+        let mut out = bytes::BytesMut::new();
+        match param.to_sql_checked(ty, &mut out).expect("parameter should accept the column type") {
+            IsNull::Yes => None,
+            IsNull::No => Some(out),
+        }
+    }
+
+    #[test]
+    fn parse_iso8601_reads_every_component() {
+        assert_eq!(
+            PgInterval::parse_iso8601("P1Y2M3W4DT5H6M7.5S"),
+            Some(PgInterval {
+                months: 14,
+                days: 25,
+                microseconds: 5 * 3_600_000_000 + 6 * 60_000_000 + 7_500_000,
+            })
+        );
+        assert_eq!(
+            PgInterval::parse_iso8601("PT-90S"),
+            Some(PgInterval { months: 0, days: 0, microseconds: -90_000_000 })
+        );
+    }
+
+    #[test]
+    fn parse_iso8601_rejects_malformed_input() {
+        for text in ["", "P", "PT", "1D", "P1X", "PD", "P1DT", "P1.5D", "P1D2"] {
+            assert_eq!(PgInterval::parse_iso8601(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn parse_iso8601_rejects_overflow() {
+        for text in [
+            "P200000000Y",
+            "P400000000W",
+            "P2147483647M1Y",
+            "P2147483647D1D",
+            "PT3000000000000H",
+            "PT9223372036854S9223372036854S",
+        ] {
+            assert_eq!(PgInterval::parse_iso8601(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn interval_round_trips_through_the_wire_format() {
+        for text in ["P1Y2M3DT4H5M6S", "P14D", "PT0.5S", "P-1Y-2M"] {
+            let raw = bind(&Type::INTERVAL, "duration", "interval", json!(text)).unwrap();
+            let interval = PgInterval::from_sql(&Type::INTERVAL, &raw).unwrap();
+            assert_eq!(Some(interval), PgInterval::parse_iso8601(text));
+            assert_eq!(PgInterval::parse_iso8601(&interval.to_iso8601()), Some(interval));
+        }
+    }
+
+    #[test]
+    fn date_round_trips_through_the_wire_format() {
+        let raw = bind(&Type::DATE, "due", "date", json!("2024-02-29")).unwrap();
+        let date = chrono::NaiveDate::from_sql(&Type::DATE, &raw).unwrap();
+        assert_eq!(date.format("%Y-%m-%d").to_string(), "2024-02-29");
+    }
+
+    #[test]
+    fn null_date_and_interval_bind_as_typed_nulls() {
+        assert!(bind(&Type::DATE, "due", "date", Value::Null).is_none());
+        assert!(bind(&Type::INTERVAL, "duration", "interval", Value::Null).is_none());
+    }
+
+    #[test]
+    fn null_timestamps_bind_as_typed_nulls() {
+        assert!(bind(&Type::TIMESTAMPTZ, "reviewed_at", "timestamptz", Value::Null).is_none());
+        assert!(bind(&Type::TIMESTAMP, "reviewed_at", "timestamp", Value::Null).is_none());
+    }
+
+    #[test]
+    fn annotation_geometry_round_trips_as_jsonb_array() {
+        let rects = json!([
+            {"x": 0.1, "y": 0.2, "width": 0.3, "height": 0.05},
+            {"x": 0.1, "y": 0.26, "width": 0.5, "height": 0.05},
+        ]);
+        let raw = bind(&Type::JSONB_ARRAY, "rects", "_jsonb", rects.clone()).unwrap();
+        let decoded: Vec<Option<Value>> = FromSql::from_sql(&Type::JSONB_ARRAY, &raw).unwrap();
+        let decoded: Vec<Value> = decoded.into_iter().map(|v| v.unwrap_or(Value::Null)).collect();
+        assert_eq!(Value::Array(decoded), rects);
+
+        assert!(bind(&Type::JSONB_ARRAY, "rects", "_jsonb", Value::Null).is_none());
+        assert!(bind(&Type::JSON_ARRAY, "rects", "_json", Value::Null).is_none());
+    }
+
+    fn row(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn batched_insert_is_one_statement() {
+        let columns = vec!["id".to_string(), "title".to_string()];
+        let query = insert_statement("works", &columns, 3, OnConflict::Skip);
+        assert_eq!(
+            query,
+            "INSERT INTO works (id, title) VALUES ($1, $2), ($3, $4), ($5, $6) \
+             ON CONFLICT (id) DO NOTHING RETURNING *"
+        );
+
+        let query = insert_statement("works", &columns, 2, OnConflict::Overwrite);
+        assert!(query.ends_with("DO UPDATE SET id = EXCLUDED.id, title = EXCLUDED.title RETURNING *"));
+    }
+
+    #[test]
+    fn large_batches_split_only_at_the_parameter_limit() {
+        // 40k rows of 3 columns: two statements instead of 40k round-trips
+        let per_statement = rows_per_statement(3);
+        assert_eq!(per_statement, 21_845);
+        assert_eq!(40_000usize.div_ceil(per_statement), 2);
+        assert!(per_statement * 3 <= MAX_STATEMENT_PARAMS);
+        assert_eq!(rows_per_statement(100_000), 1);
+    }
+
+    #[test]
+    fn returned_rows_follow_input_order() {
+        let a = "6F9619FF-8B86-D011-B42D-00C04FC964FF";
+        let b = "7a2c4d10-0000-4000-8000-000000000001";
+        let c = "7a2c4d10-0000-4000-8000-000000000002";
+        let rows = vec![
+            row(&[("id", json!(a)), ("title", json!("A"))]),
+            row(&[("id", json!(b)), ("title", json!("B"))]),
+            row(&[("id", json!(c)), ("title", json!("C"))]),
+        ];
+        // Postgres lowercases UUIDs and may return rows in any order
+        let returned = vec![
+            json!({"id": c, "title": "C"}),
+            json!({"id": a.to_lowercase(), "title": "A"}),
+            json!({"id": b, "title": "B"}),
+        ];
+
+        let matched = match_returned_rows(conflict_key("works"), &rows, returned);
+        let titles: Vec<&Value> = matched.iter().map(|v| &v["title"]).collect();
+        assert_eq!(titles, [&json!("A"), &json!("B"), &json!("C")]);
+    }
+
+    #[test]
+    fn skipped_rows_map_to_null() {
+        let key = conflict_key("device_blobs");
+        let rows = vec![
+            row(&[("device_id", json!("d1")), ("sha256", json!("aa"))]),
+            row(&[("device_id", json!("d1")), ("sha256", json!("bb"))]),
+            row(&[("device_id", json!("d2")), ("sha256", json!("aa"))]),
+        ];
+        let returned = vec![
+            json!({"device_id": "d2", "sha256": "aa"}),
+            json!({"device_id": "d1", "sha256": "aa"}),
+        ];
+
+        let matched = match_returned_rows(key, &rows, returned);
+        assert_eq!(matched[0]["device_id"], json!("d1"));
+        assert_eq!(matched[1], Value::Null);
+        assert_eq!(matched[2]["device_id"], json!("d2"));
+    }
+
+    #[test]
+    fn adversarial_identifiers_are_rejected() {
+        for name in [
+            "",
+            "id; DROP TABLE works",
+            "title\"",
+            "title'",
+            "a b",
+            "a-b",
+            "a.b",
+            "works.id",
+            "Title",
+            "1column",
+            "col\0",
+            "col\n",
+            "col--",
+            "tïtle",
+            "\u{212a}ey",
+            "(select 1)",
+        ] {
+            assert!(!is_safe_identifier(name), "{:?}", name);
+        }
+        for name in ["id", "_private", "avatar_display_path", "page2", "x"] {
+            assert!(is_safe_identifier(name), "{:?}", name);
+        }
+    }
+
+    fn column(name: &str, is_nullable: bool, has_default: bool) -> ColumnInfo {
+        ColumnInfo {
+            name: name.to_string(),
+            data_type: "text".to_string(),
+            is_nullable,
+            has_default,
+            is_primary_key: name == "id",
+        }
+    }
+
+    #[test]
+    fn payload_keys_are_checked_after_snake_casing() {
+        let columns = vec![
+            column("id", false, false),
+            column("title", false, false),
+            column("subtitle", true, false),
+            column("created_at", false, true),
+        ];
+        let check = |payload: Value, is_insert: bool| {
+            let data = keys_to_snake_case(&payload).unwrap();
+            check_payload_columns("works", &data, &columns, is_insert)
+        };
+
+        assert!(check(json!({"id": "1", "title": "T"}), true).is_ok());
+        assert!(check(json!({"subtitle": null}), false).is_ok());
+
+        for key in [
+            "title = 'x', id",
+            "title\" = 1; --",
+            "Title\u{0}",
+            "title)",
+            "createdAt; DELETE FROM works",
+        ] {
+            let err = check(json!({"id": "1", "title": "T", key: 1}), true).unwrap_err();
+            assert!(err.starts_with("invalid column name"), "{:?}: {}", key, err);
+        }
+
+        // Plain identifiers that aren't columns are still refused
+        let err = check(json!({"id": "1", "title": "T", "isAdmin": true}), true).unwrap_err();
+        assert_eq!(err, "unknown column is_admin for table works");
+
+        let err = check(json!({"id": "1", "title": null}), true).unwrap_err();
+        assert_eq!(err, "missing required column title for table works");
+    }
+
+    #[tokio::test]
+    async fn lww_update_reads_timestamptz_updated_at() {
+        let Some(client) = test_pg_client("deeprecall_test_lww_timestamptz").await else {
+            eprintln!("DEEPRECALL_TEST_DATABASE_URL not set; skipping");
+            return;
+        };
+        client
+            .batch_execute(
+                "CREATE TABLE folder_sources (
+                    id UUID PRIMARY KEY,
+                    display_name TEXT NOT NULL,
+                    updated_at TIMESTAMPTZ NOT NULL
+                );
+                INSERT INTO folder_sources
+                VALUES ('00000000-0000-0000-0000-000000000001', 'Papers',
+                        to_timestamp(1700000000));",
+            )
+            .await
+            .unwrap();
+
+        let update = |name: &str, updated_at: i64| WriteChange {
+            id: "w1".to_string(),
+            table: "folder_sources".to_string(),
+            op: WriteOperation::Update,
+            payload: json!({
+                "id": "00000000-0000-0000-0000-000000000001",
+                "displayName": name,
+                "updatedAt": updated_at,
+            }),
+            created_at: 0,
+            status: "pending".to_string(),
+            retry_count: 0,
+            jsonb_merge: false,
+            hard_delete: false,
+        };
+        let display_name = || async {
+            client
+                .query_one("SELECT display_name FROM folder_sources", &[])
+                .await
+                .unwrap()
+                .get::<_, String>(0)
+        };
+
+        // Older than the stored timestamp: the server row wins
+        let outcome = apply_update(&client, &update("Stale", 1_699_999_999_000)).await.unwrap();
+        assert!(matches!(outcome, UpdateOutcome::Applied(_)));
+        assert_eq!(display_name().await, "Papers");
+
+        // Newer: applied, and the epoch-ms payload is stored as a timestamptz
+        let outcome = apply_update(&client, &update("Library", 1_700_000_001_000)).await.unwrap();
+        assert!(matches!(outcome, UpdateOutcome::Applied(_)));
+        assert_eq!(display_name().await, "Library");
+        let row = client.query_one("SELECT updated_at FROM folder_sources", &[]).await.unwrap();
+        assert_eq!(row_timestamp_ms(&row, "updated_at").unwrap(), 1_700_000_001_000);
+
+        // The folder_sources repo sends ISO strings; the returned row carries one too
+        let mut change = update("Archive", 0);
+        change.payload["updatedAt"] = json!("2023-11-14T22:13:22.000Z");
+        let UpdateOutcome::Applied(row) = apply_update(&client, &change).await.unwrap() else {
+            panic!("update should apply");
+        };
+        assert_eq!(row["display_name"], "Archive");
+        assert_eq!(row["updated_at"], "2023-11-14T22:13:22.000Z");
+
+        client
+            .batch_execute("DROP SCHEMA deeprecall_test_lww_timestamptz CASCADE")
+            .await
+            .unwrap();
+    }
+}
@@ -4,29 +4,100 @@ use std::io::{Read, Write};
 use std::sync::Mutex;
 use std::thread;
 use tauri::{AppHandle, Emitter};
-use crate::app_log;
+use crate::{app_log, log_debug, log_error, log_warn};
+
+/// How long a connection may take to send its request before it's dropped
+const REQUEST_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// Largest request (line + headers) the loopback server will read
+const MAX_REQUEST_BYTES: usize = 16 * 1024;
+/// How long the loopback server waits for the callback before giving up
+const DEFAULT_CALLBACK_TIMEOUT_SECS: u64 = 300;
 
 /// OAuth server state - manages the running HTTP server
 static OAUTH_SERVER: Mutex<Option<OAuthServerHandle>> = Mutex::new(None);
 
+/// PKCE verifier for the flow in progress, consumed by `exchange_oauth_code`
+static PKCE_VERIFIER: Mutex<Option<String>> = Mutex::new(None);
+
+/// Google's token endpoint, the only one `VITE_GOOGLE_DESKTOP_CLIENT_SECRET` is sent to
+const GOOGLE_TOKEN_ENDPOINT: &str = "https://oauth2.googleapis.com/token";
+
+/// Custom landing pages; `None` uses the built-in page
+///
+/// Templates may use `{title}` and `{message}` placeholders (substituted HTML-escaped).
+#[derive(Debug, Clone, Default)]
+struct LandingPages {
+    success: Option<String>,
+    error: Option<String>,
+}
+
 struct OAuthServerHandle {
     port: u16,
     shutdown_sender: std::sync::mpsc::Sender<()>,
 }
 
+/// Address the loopback server ended up listening on
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LoopbackAddress {
+    /// Host to put in the redirect URI ("127.0.0.1", "localhost" or "[::1]")
+    pub host: String,
+    pub port: u16,
+}
+
+/// Bind `ip` on `preferred_port` if given and free, otherwise on an ephemeral port
+fn bind_loopback(ip: &str, preferred_port: Option<u16>) -> std::io::Result<TcpListener> {
+    if let Some(port) = preferred_port {
+        match TcpListener::bind((ip, port)) {
+            Ok(listener) => return Ok(listener),
+            Err(e) => app_log!(
+                "[OAuth] Preferred port {} unavailable on {} ({}), using an ephemeral port",
+                port, ip, e
+            ),
+        }
+    }
+    TcpListener::bind((ip, 0))
+}
+
 /// Start an ephemeral HTTP server for OAuth loopback
-/// Returns the port number it's listening on
+/// Returns the host and port it's listening on
+///
+/// `preferred_port` is tried first for providers that need a fixed registered redirect URI.
+/// `host` may be "127.0.0.1" (default), "localhost" (binds IPv4, falling back to IPv6)
+/// or "::1" for IPv6-only stacks.
+/// If no callback arrives within `timeout_secs` (default 300), the server shuts itself
+/// down and emits `oauth-timeout`.
+/// `code_verifier` is kept on the Rust side for `exchange_oauth_code`.
+/// `success_html`/`error_html` replace the built-in landing pages; see `LandingPages`.
 #[tauri::command]
-pub async fn start_oauth_loopback(app: AppHandle) -> Result<u16, String> {
-    // Find an available port
-    let listener = TcpListener::bind("127.0.0.1:0")
-        .map_err(|e| format!("Failed to bind to localhost: {}", e))?;
+pub async fn start_oauth_loopback(
+    app: AppHandle,
+    preferred_port: Option<u16>,
+    host: Option<String>,
+    timeout_secs: Option<u64>,
+    code_verifier: Option<String>,
+    success_html: Option<String>,
+    error_html: Option<String>,
+) -> Result<LoopbackAddress, String> {
+    let host = host.unwrap_or_else(|| "127.0.0.1".to_string());
+    let (listener, url_host) = match host.as_str() {
+        "127.0.0.1" => (bind_loopback("127.0.0.1", preferred_port), host.clone()),
+        "::1" | "[::1]" => (bind_loopback("::1", preferred_port), "[::1]".to_string()),
+        "localhost" => (
+            bind_loopback("127.0.0.1", preferred_port)
+                .or_else(|_| bind_loopback("::1", preferred_port)),
+            host.clone(),
+        ),
+        other => return Err(format!("Unsupported loopback host: {}", other)),
+    };
+    let listener = listener.map_err(|e| format!("Failed to bind to {}: {}", host, e))?;
     
     let port = listener.local_addr()
         .map_err(|e| format!("Failed to get local address: {}", e))?
         .port();
     
-    app_log!("[OAuth] Starting loopback server on port {}", port);
+    app_log!("[OAuth] Starting loopback server on {}:{}", url_host, port);
+    
+    *PKCE_VERIFIER.lock().unwrap() = code_verifier;
     
     // Create shutdown channel
     let (shutdown_tx, shutdown_rx) = std::sync::mpsc::channel();
@@ -41,11 +112,19 @@ pub async fn start_oauth_loopback(app: AppHandle) -> Result<u16, String> {
     }
     
     // Spawn server thread
+    let timeout = std::time::Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_CALLBACK_TIMEOUT_SECS));
+    let pages = LandingPages {
+        success: success_html,
+        error: error_html,
+    };
     thread::spawn(move || {
-        handle_oauth_server(listener, app, shutdown_rx);
+        handle_oauth_server(listener, app, shutdown_rx, timeout, pages);
     });
     
-    Ok(port)
+    Ok(LoopbackAddress {
+        host: url_host,
+        port,
+    })
 }
 
 /// Stop the OAuth loopback server
@@ -61,16 +140,102 @@ pub async fn stop_oauth_loopback() -> Result<(), String> {
     Ok(())
 }
 
+/// Token endpoint response (RFC 6749 section 5.1)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OAuthTokens {
+    pub access_token: String,
+    pub token_type: Option<String>,
+    pub expires_in: Option<u64>,
+    pub refresh_token: Option<String>,
+    pub id_token: Option<String>,
+    pub scope: Option<String>,
+}
+
+/// Token endpoint error response (RFC 6749 section 5.2)
+#[derive(Debug, serde::Deserialize)]
+struct OAuthErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// Client secret to send to `token_endpoint`, if one is configured for it
+fn client_secret_for(token_endpoint: &str) -> Option<String> {
+    if token_endpoint != GOOGLE_TOKEN_ENDPOINT {
+        return None;
+    }
+    std::env::var("VITE_GOOGLE_DESKTOP_CLIENT_SECRET")
+        .ok()
+        .or_else(|| option_env!("VITE_GOOGLE_DESKTOP_CLIENT_SECRET").map(String::from))
+        .filter(|s| !s.is_empty())
+}
+
+/// Exchange an authorization code for tokens at the provider's token endpoint
+///
+/// Sends the PKCE verifier given to `start_oauth_loopback` (consumed here) and, for
+/// Google, the desktop client secret, so neither has to live in the webview.
+/// Provider errors come back as `error: error_description`.
+#[tauri::command]
+pub async fn exchange_oauth_code(
+    token_endpoint: String,
+    client_id: String,
+    code: String,
+    redirect_uri: String,
+) -> Result<OAuthTokens, String> {
+    let mut form = vec![
+        ("grant_type", "authorization_code".to_string()),
+        ("client_id", client_id),
+        ("code", code),
+        ("redirect_uri", redirect_uri),
+    ];
+    if let Some(verifier) = PKCE_VERIFIER.lock().unwrap().take() {
+        form.push(("code_verifier", verifier));
+    }
+    if let Some(secret) = client_secret_for(&token_endpoint) {
+        form.push(("client_secret", secret));
+    }
+    
+    app_log!("[OAuth] Exchanging authorization code at {}", token_endpoint);
+    let response = reqwest::Client::new()
+        .post(&token_endpoint)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| format!("Token request failed: {}", e))?;
+    
+    let status = response.status();
+    let body = response.text()
+        .await
+        .map_err(|e| format!("Failed to read token response: {}", e))?;
+    
+    if !status.is_success() {
+        let message = match serde_json::from_str::<OAuthErrorResponse>(&body) {
+            Ok(err) => match err.error_description {
+                Some(description) => format!("{}: {}", err.error, description),
+                None => err.error,
+            },
+            Err(_) => format!("HTTP {}: {}", status, body),
+        };
+        log_error!("[OAuth] Token exchange failed: {}", message);
+        return Err(message);
+    }
+    
+    serde_json::from_str(&body).map_err(|e| format!("Invalid token response: {}", e))
+}
+
 /// Handle incoming OAuth requests
 fn handle_oauth_server(
     listener: TcpListener,
     app: AppHandle,
     shutdown_rx: std::sync::mpsc::Receiver<()>,
+    timeout: std::time::Duration,
+    pages: LandingPages,
 ) {
     // Set non-blocking mode so we can check for shutdown
     listener.set_nonblocking(true)
         .expect("Cannot set non-blocking");
     
+    let started = std::time::Instant::now();
     loop {
         // Check for shutdown signal
         if shutdown_rx.try_recv().is_ok() {
@@ -78,14 +243,29 @@ fn handle_oauth_server(
             break;
         }
         
+        // Give up on abandoned sign-ins so the thread and port are released
+        if started.elapsed() >= timeout {
+            app_log!("[OAuth] No callback within {}s, shutting down server", timeout.as_secs());
+            let _ = app.emit("oauth-timeout", serde_json::json!({
+                "timeout_secs": timeout.as_secs(),
+            }));
+            break;
+        }
+        
         // Accept incoming connections
         match listener.accept() {
             Ok((stream, addr)) => {
-                app_log!("[OAuth] Connection from {}", addr);
-                handle_oauth_request(stream, &app);
+                log_debug!("[OAuth] Connection from {}", addr);
+                let emit = |event: &str, payload: serde_json::Value| {
+                    let _ = app.emit(event, payload);
+                };
+                if !handle_oauth_request(stream, &emit, &pages) {
+                    // Favicon, stray path or rejected request; keep waiting for the real callback
+                    continue;
+                }
                 
-                // After handling one request, shut down automatically
-                app_log!("[OAuth] Request handled, shutting down server");
+                // The callback carried a code or an error, so the flow is over
+                app_log!("[OAuth] Callback handled, shutting down server");
                 break;
             }
             Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
@@ -94,7 +274,7 @@ fn handle_oauth_server(
                 continue;
             }
             Err(e) => {
-                app_log!("[OAuth] Error accepting connection: {}", e);
+                log_error!("[OAuth] Error accepting connection: {}", e);
                 break;
             }
         }
@@ -103,14 +283,55 @@ fn handle_oauth_server(
     app_log!("[OAuth] Server thread exiting");
 }
 
-/// Handle a single OAuth callback request
-fn handle_oauth_request(mut stream: TcpStream, app: &AppHandle) {
-    let mut buffer = [0; 4096];
+/// Read the request line and headers, bounded by `REQUEST_READ_TIMEOUT` and `MAX_REQUEST_BYTES`
+///
+/// The timeout covers the whole request, so a client trickling bytes can't hold the
+/// server past it.
+fn read_request(stream: &mut TcpStream) -> Result<Vec<u8>, String> {
+    // Accepted sockets may inherit the listener's non-blocking mode on some platforms
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    let deadline = std::time::Instant::now() + REQUEST_READ_TIMEOUT;
+    let timed_out = || format!("request not received within {}s", REQUEST_READ_TIMEOUT.as_secs());
     
-    match stream.read(&mut buffer) {
-        Ok(size) => {
-            let request = String::from_utf8_lossy(&buffer[..size]);
-            app_log!("[OAuth] Received request:\n{}", request);
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
+            return Err(timed_out());
+        }
+        stream.set_read_timeout(Some(remaining)).map_err(|e| e.to_string())?;
+        
+        let size = match stream.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(size) => size,
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {
+                return Err(timed_out());
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        request.extend_from_slice(&buffer[..size]);
+        
+        if request.len() > MAX_REQUEST_BYTES {
+            return Err(format!("request exceeds {} bytes", MAX_REQUEST_BYTES));
+        }
+        if request.windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+    }
+    Ok(request)
+}
+
+/// Handle a single request to the loopback server, reporting the outcome through `emit`
+///
+/// Returns true only for a `/oauth2/callback` carrying a `code` or `error`. Anything else
+/// (favicon fetches, preflights, other paths, malformed or rejected requests) is answered
+/// and the server keeps listening.
+fn handle_oauth_request(mut stream: TcpStream, emit: &dyn Fn(&str, serde_json::Value), pages: &LandingPages) -> bool {
+    match read_request(&mut stream) {
+        Ok(buffer) => {
+            let request = String::from_utf8_lossy(&buffer);
+            log_debug!("[OAuth] Received request:\n{}", request);
             
             // Parse the request line
             let first_line = request.lines().next().unwrap_or("");
@@ -126,7 +347,7 @@ fn handle_oauth_request(mut stream: TcpStream, app: &AppHandle) {
                         let query = &path[query_start + 1..];
                         let params = parse_query_string(query);
                         
-                        app_log!("[OAuth] Parsed params: {:?}", params);
+                        log_debug!("[OAuth] Parsed params: {:?}", params);
                         
                         // Check for OAuth error
                         if let Some(error) = params.get("error") {
@@ -134,38 +355,46 @@ fn handle_oauth_request(mut stream: TcpStream, app: &AppHandle) {
                                 .map(|s| s.as_str())
                                 .unwrap_or("Unknown error");
                             
-                            app_log!("[OAuth] Error: {} - {}", error, error_desc);
+                            log_warn!("[OAuth] Error: {} - {}", error, error_desc);
                             
                             // Emit error event
-                            let _ = app.emit("oauth-error", serde_json::json!({
+                            emit("oauth-error", serde_json::json!({
                                 "error": error,
                                 "error_description": error_desc,
                             }));
                             
                             // Send error response
-                            send_response(&mut stream, "OAuth Error", 
-                                &format!("<h1>OAuth Error</h1><p>{}</p><p>You can close this window.</p>", error_desc));
+                            send_page(&mut stream, pages.error.as_deref(), "OAuth Error", error_desc,
+                                &format!("<h1>OAuth Error</h1><p>{}</p><p>You can close this window.</p>", html_escape(error_desc)));
+                            return true;
                         } else if let Some(code) = params.get("code") {
                             // Success! Emit the authorization code
                             app_log!("[OAuth] Got authorization code: {}...", &code[..code.len().min(20)]);
                             
-                            let _ = app.emit("oauth-callback", serde_json::json!({
+                            emit("oauth-callback", serde_json::json!({
                                 "code": code,
                                 "state": params.get("state"),
                             }));
                             
                             // Send success response
-                            send_response(&mut stream, "Sign In Successful", 
+                            send_page(&mut stream, pages.success.as_deref(), "Sign In Successful",
+                                "You can close this window and return to the app.",
                                 "<h1>✓ Sign In Successful</h1><p>You can close this window and return to the app.</p>");
+                            return true;
                         } else {
                             app_log!("[OAuth] No code or error in callback");
-                            send_response(&mut stream, "Invalid Request", 
+                            send_page(&mut stream, pages.error.as_deref(), "Invalid Request",
+                                "Missing authorization code.",
                                 "<h1>Invalid OAuth Callback</h1><p>Missing authorization code.</p>");
                         }
                     } else {
-                        send_response(&mut stream, "Invalid Request", 
+                        send_page(&mut stream, pages.error.as_deref(), "Invalid Request",
+                            "Missing query parameters.",
                             "<h1>Invalid OAuth Callback</h1><p>Missing query parameters.</p>");
                     }
+                } else if path.starts_with("/favicon.ico") {
+                    // Browsers ask for this before or alongside the callback
+                    send_no_content(&mut stream);
                 } else {
                     // Not the OAuth callback path
                     send_response(&mut stream, "Not Found", 
@@ -174,9 +403,34 @@ fn handle_oauth_request(mut stream: TcpStream, app: &AppHandle) {
             }
         }
         Err(e) => {
-            app_log!("[OAuth] Error reading request: {}", e);
+            log_warn!("[OAuth] Rejected request: {}", e);
+            emit("oauth-error", serde_json::json!({
+                "error": "invalid_request",
+                "error_description": format!("Loopback request rejected: {}", e),
+            }));
+            send_bad_request(&mut stream, &e);
         }
     }
+    
+    false
+}
+
+/// Reply 204 to requests that need no body (favicon)
+fn send_no_content(stream: &mut TcpStream) {
+    let _ = stream.write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n");
+    let _ = stream.flush();
+}
+
+/// Reply 400 to a request that was rejected before it could be parsed
+fn send_bad_request(stream: &mut TcpStream, reason: &str) {
+    let response = format!(
+        "HTTP/1.1 400 Bad Request\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        reason.len(),
+        reason
+    );
+    
+    let _ = stream.write_all(response.as_bytes());
+    let _ = stream.flush();
 }
 
 /// Parse URL query string into a HashMap
@@ -197,6 +451,41 @@ fn parse_query_string(query: &str) -> HashMap<String, String> {
         .collect()
 }
 
+/// Closes the landing page shortly after it's shown
+const AUTO_CLOSE_SCRIPT: &str = r#"<script>
+        // Auto-close after 2 seconds
+        setTimeout(() => {
+            window.close();
+        }, 2000);
+    </script>"#;
+
+/// Escape text for interpolation into HTML
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Send a landing page: `template` with `{title}`/`{message}` filled in if given,
+/// otherwise the built-in page with `body`. The auto-close script is always included.
+fn send_page(stream: &mut TcpStream, template: Option<&str>, title: &str, message: &str, body: &str) {
+    let Some(template) = template else {
+        send_response(stream, title, body);
+        return;
+    };
+    
+    let mut html = template
+        .replace("{title}", &html_escape(title))
+        .replace("{message}", &html_escape(message));
+    match html.rfind("</body>") {
+        Some(end) => html.insert_str(end, AUTO_CLOSE_SCRIPT),
+        None => html.push_str(AUTO_CLOSE_SCRIPT),
+    }
+    write_html(stream, &html);
+}
+
 /// Send an HTML response to the browser
 fn send_response(stream: &mut TcpStream, title: &str, body: &str) {
     let html = format!(
@@ -240,17 +529,17 @@ fn send_response(stream: &mut TcpStream, title: &str, body: &str) {
     <div class="container">
         {}
     </div>
-    <script>
-        // Auto-close after 2 seconds
-        setTimeout(() => {{
-            window.close();
-        }}, 2000);
-    </script>
+    {}
 </body>
 </html>"#,
-        title, body
+        title, body, AUTO_CLOSE_SCRIPT
     );
     
+    write_html(stream, &html);
+}
+
+/// Write a 200 response carrying `html`
+fn write_html(stream: &mut TcpStream, html: &str) {
     let response = format!(
         "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\r\n{}",
         html.len(),
@@ -260,3 +549,93 @@ fn send_response(stream: &mut TcpStream, title: &str, body: &str) {
     let _ = stream.write_all(response.as_bytes());
     let _ = stream.flush();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    type Events = RefCell<Vec<(String, serde_json::Value)>>;
+
+    /// Send `request` over loopback, let `handle_oauth_request` answer it, and return
+    /// whether it ended the flow plus the raw response
+    fn exchange(listener: &TcpListener, request: &str, events: &Events) -> (bool, String) {
+        let addr = listener.local_addr().unwrap();
+        let request = request.to_string();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        let emit = |event: &str, payload: serde_json::Value| {
+            events.borrow_mut().push((event.to_string(), payload));
+        };
+        let done = handle_oauth_request(stream, &emit, &LandingPages::default());
+        (done, client.join().unwrap())
+    }
+
+    #[test]
+    fn favicon_then_callback() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let events = Events::default();
+
+        let (done, response) = exchange(
+            &listener,
+            "GET /favicon.ico HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n",
+            &events,
+        );
+        assert!(!done, "a favicon fetch must not end the flow");
+        assert!(response.starts_with("HTTP/1.1 204"));
+        assert!(events.borrow().is_empty());
+
+        let (done, response) = exchange(
+            &listener,
+            "GET /oauth2/callback?code=4%2F0abc&state=xyz HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n",
+            &events,
+        );
+        assert!(done);
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("Sign In Successful"));
+        assert_eq!(
+            *events.borrow(),
+            vec![(
+                "oauth-callback".to_string(),
+                serde_json::json!({ "code": "4/0abc", "state": "xyz" })
+            )]
+        );
+    }
+
+    #[test]
+    fn stray_paths_keep_the_server_listening() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let events = Events::default();
+
+        let (done, response) = exchange(&listener, "GET / HTTP/1.1\r\n\r\n", &events);
+        assert!(!done);
+        assert!(response.contains("404 Not Found"));
+
+        let (done, _) = exchange(&listener, "GET /oauth2/callback?state=xyz HTTP/1.1\r\n\r\n", &events);
+        assert!(!done, "a callback without code or error must not end the flow");
+        assert!(events.borrow().is_empty());
+    }
+
+    #[test]
+    fn error_description_is_escaped_on_the_built_in_page() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let events = Events::default();
+
+        let (done, response) = exchange(
+            &listener,
+            "GET /oauth2/callback?error=access_denied&error_description=%3Cscript%3Ealert(1)%3C%2Fscript%3E HTTP/1.1\r\n\r\n",
+            &events,
+        );
+        assert!(done);
+        assert!(!response.contains("<script>alert(1)"));
+        assert!(response.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert_eq!(events.borrow()[0].1["error_description"], "<script>alert(1)</script>");
+    }
+}
@@ -3,24 +3,213 @@
  */
 
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
 
-pub fn get_db_path() -> Result<PathBuf> {
+/// Held by operations that rewrite large parts of the catalog (scan, vacuum) so they
+/// never run at the same time
+pub static CATALOG_LOCK: Mutex<()> = Mutex::new(());
+
+/// Environment variable overriding the data root
+pub const DATA_DIR_ENV: &str = "DEEPRECALL_DATA_DIR";
+
+/// File under the OS config directory holding a persisted data root override
+///
+/// Lives outside the data root (and so outside the catalog) because it decides where
+/// the catalog is.
+pub fn data_dir_config_path() -> Result<PathBuf> {
+    let config_dir = dirs::config_dir().context("Failed to get config directory")?;
+    Ok(config_dir.join("DeepRecall").join("data_dir"))
+}
+
+/// Data root from `DEEPRECALL_DATA_DIR` or the persisted setting, if either is set
+fn data_dir_override() -> Result<Option<PathBuf>> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|v| !v.is_empty()) {
+        return Ok(Some(PathBuf::from(dir)));
+    }
+    let config_path = data_dir_config_path()?;
+    if !config_path.exists() {
+        return Ok(None);
+    }
+    let saved = std::fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read {}", config_path.display()))?;
+    let saved = saved.trim();
+    Ok((!saved.is_empty()).then(|| PathBuf::from(saved)))
+}
+
+/// Create `dir` if needed and confirm files can be written there
+pub fn ensure_writable_dir(dir: &std::path::Path) -> Result<()> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Data directory {} cannot be created", dir.display()))?;
+    let probe = dir.join(".deeprecall-write-test");
+    std::fs::write(&probe, b"")
+        .with_context(|| format!("Data directory {} is not writable", dir.display()))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Data root resolved by the first `get_data_dir` call this session
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// `~/DeepRecall`, the data root when nothing overrides it
+fn default_data_dir() -> Result<PathBuf> {
     let home_dir = dirs::home_dir().context("Failed to get home directory")?;
-    let app_dir = home_dir.join("DeepRecall");
-    std::fs::create_dir_all(&app_dir)?;
-    Ok(app_dir.join("catalog.db"))
+    Ok(home_dir.join("DeepRecall"))
 }
 
+/// Data root the next session will use: `DEEPRECALL_DATA_DIR`, else the persisted
+/// setting, else `~/DeepRecall`
+pub fn configured_data_dir() -> Result<PathBuf> {
+    match data_dir_override()? {
+        Some(dir) => Ok(dir),
+        None => default_data_dir(),
+    }
+}
+
+/// Root holding the catalog, blobs and caches (see `configured_data_dir`)
+///
+/// Resolved and checked once, then cached for the rest of the session: the catalog and
+/// blob store never move underneath open connections, so a changed setting applies on
+/// the next launch. An override that can't be written to is an error; it never falls
+/// back to the default, which would silently split the library across two locations.
+pub fn get_data_dir() -> Result<PathBuf> {
+    if let Some(dir) = DATA_DIR.get() {
+        return Ok(dir.clone());
+    }
+    let dir = match data_dir_override()? {
+        Some(dir) => {
+            ensure_writable_dir(&dir)?;
+            dir
+        }
+        None => {
+            let app_dir = default_data_dir()?;
+            std::fs::create_dir_all(&app_dir)?;
+            app_dir
+        }
+    };
+    Ok(DATA_DIR.get_or_init(|| dir).clone())
+}
+
+pub fn get_db_path() -> Result<PathBuf> {
+    Ok(get_data_dir()?.join("catalog.db"))
+}
+
+/// How long a connection waits on a locked catalog before failing with SQLITE_BUSY
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(5000);
+
 pub fn get_connection() -> Result<Connection> {
     let db_path = get_db_path()?;
     let conn = Connection::open(db_path)?;
+    configure_connection(&conn)?;
     init_schema(&conn)?;
     Ok(conn)
 }
 
+/// WAL lets readers run alongside a writer (e.g. listing blobs during a scan), and the
+/// busy timeout makes concurrent writers wait their turn instead of erroring out
+fn configure_connection(conn: &Connection) -> Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    // journal_mode is persistent and returns the resulting mode as a row
+    conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+    conn.execute_batch("PRAGMA synchronous = NORMAL")?;
+    Ok(())
+}
+
+/// A schema change, applied once per catalog
+///
+/// Migrations must tolerate catalogs created before versioning existed, which may
+/// already contain some of their tables and columns.
+struct Migration {
+    description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+/// Every schema change in order; a catalog at version N has applied the first N.
+/// Only ever append to this list.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "blobs, paths and settings tables",
+        apply: migrate_base_tables,
+    },
+    Migration {
+        description: "blobs.declared_mime",
+        apply: |conn| ensure_column(conn, "blobs", "declared_mime", "TEXT"),
+    },
+    Migration {
+        description: "pending_writes outbox",
+        apply: migrate_pending_writes,
+    },
+    Migration {
+        description: "avatar_migrations table",
+        apply: migrate_avatar_migrations,
+    },
+    Migration {
+        description: "blobs.page_count",
+        apply: |conn| ensure_column(conn, "blobs", "page_count", "INTEGER"),
+    },
+    Migration {
+        description: "blobs_fts filename index",
+        apply: migrate_filename_index,
+    },
+    Migration {
+        description: "blob_aliases table",
+        apply: migrate_blob_aliases,
+    },
+    Migration {
+        description: "blob_tags table",
+        apply: migrate_blob_tags,
+    },
+];
+
+/// Schema version the running code expects
+fn current_schema_version() -> i64 {
+    MIGRATIONS.len() as i64
+}
+
+/// Version recorded in the catalog's `schema_version` table (0 when unversioned)
+fn get_schema_version(conn: &Connection) -> Result<i64> {
+    let version = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(version)
+}
+
+/// Apply any pending migrations in a single transaction
 fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            applied_ms INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    if get_schema_version(conn)? >= current_schema_version() {
+        return Ok(());
+    }
+
+    // IMMEDIATE takes the write lock up front, so two connections can't both migrate
+    let tx = rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate)?;
+    let from = get_schema_version(&tx)?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        let version = index as i64 + 1;
+        (migration.apply)(&tx)
+            .with_context(|| format!("Migration {} ({}) failed", version, migration.description))?;
+        tx.execute(
+            "INSERT INTO schema_version (version, applied_ms) VALUES (?1, ?2)",
+            params![version, chrono::Utc::now().timestamp_millis()],
+        )?;
+        println!("[Catalog] Applied migration {}: {}", version, migration.description);
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn migrate_base_tables(conn: &Connection) -> Result<()> {
     // Create blobs table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS blobs (
@@ -54,6 +243,140 @@ fn init_schema(conn: &Connection) -> Result<()> {
         [],
     )?;
 
+    // Create settings table (simple key/value store for storage preferences)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn migrate_pending_writes(conn: &Connection) -> Result<()> {
+    // Create outbox table for writes that couldn't reach Postgres
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pending_writes (
+            id TEXT PRIMARY KEY,
+            change_json TEXT NOT NULL,
+            queued_ms INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_avatar_migrations(conn: &Connection) -> Result<()> {
+    // Create avatar migration table (legacy avatar file -> blob hash)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS avatar_migrations (
+            old_path TEXT PRIMARY KEY,
+            sha256 TEXT NOT NULL,
+            migrated_ms INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_filename_index(conn: &Connection) -> Result<()> {
+    // Create full-text index over filenames, filling it from existing blobs
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS blobs_fts USING fts5(hash UNINDEXED, filename)",
+        [],
+    )?;
+    conn.execute("DELETE FROM blobs_fts", [])?;
+    conn.execute(
+        "INSERT INTO blobs_fts (hash, filename) SELECT hash, COALESCE(filename, '') FROM blobs",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_blob_aliases(conn: &Connection) -> Result<()> {
+    // Every filename a blob has been stored under, seeded with the current ones
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS blob_aliases (
+            hash TEXT NOT NULL,
+            filename TEXT NOT NULL,
+            first_seen_ms INTEGER NOT NULL,
+            PRIMARY KEY (hash, filename)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO blob_aliases (hash, filename, first_seen_ms)
+         SELECT hash, filename, created_ms FROM blobs WHERE filename IS NOT NULL",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_blob_tags(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS blob_tags (
+            hash TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            created_ms INTEGER NOT NULL,
+            PRIMARY KEY (hash, tag)
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_blob_tags_tag ON blob_tags(tag)", [])?;
+
+    // Foreign keys are off and `insert_blob` replaces rows, so cascade with a trigger;
+    // REPLACE doesn't fire delete triggers, so re-storing a blob keeps its tags
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS blob_tags_cascade AFTER DELETE ON blobs
+         BEGIN
+             DELETE FROM blob_tags WHERE hash = OLD.hash;
+         END",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Add a column to an existing table if it isn't there yet
+fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
+    let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = ?1")?;
+    let mut rows = stmt.query(params![key])?;
+
+    if let Some(row) = rows.next()? {
+        Ok(Some(row.get(0)?))
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_setting(conn: &Connection, key: &str) -> Result<()> {
+    conn.execute("DELETE FROM settings WHERE key = ?1", params![key])?;
     Ok(())
 }
 
@@ -72,10 +395,72 @@ pub fn insert_blob(
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'healthy')",
         params![hash, size, mime, mtime_ms, created_ms, filename],
     )?;
+    index_filename(conn, hash, filename.unwrap_or(""))?;
+    if let Some(filename) = filename {
+        conn.execute(
+            "INSERT OR IGNORE INTO blob_aliases (hash, filename, first_seen_ms) VALUES (?1, ?2, ?3)",
+            params![hash, filename, created_ms],
+        )?;
+    }
 
     Ok(())
 }
 
+/// Replace a blob's entry in the filename search index
+fn index_filename(conn: &Connection, hash: &str, filename: &str) -> Result<()> {
+    conn.execute("DELETE FROM blobs_fts WHERE hash = ?1", params![hash])?;
+    conn.execute(
+        "INSERT INTO blobs_fts (hash, filename) VALUES (?1, ?2)",
+        params![hash, filename],
+    )?;
+    Ok(())
+}
+
+/// Turn free text into an FTS5 query matching every word as a prefix
+///
+/// Each word is quoted so FTS syntax characters in filenames are taken literally.
+fn fts_prefix_query(query: &str) -> String {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Blobs whose filename matches `query` (words match as prefixes), best matches first
+pub fn search_blobs(
+    conn: &Connection,
+    query: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<super::types::BlobWithMetadata>> {
+    let fts_query = fts_prefix_query(query);
+    if fts_query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT b.hash, b.size, b.mime, b.mtime_ms, b.created_ms, b.filename, b.health,
+                b.image_width, b.image_height, b.line_count,
+                (SELECT MIN(p.path) FROM paths p WHERE p.hash = b.hash), b.page_count
+         FROM blobs_fts f
+         JOIN blobs b ON b.hash = f.hash
+         WHERE blobs_fts MATCH ?1
+         ORDER BY f.rank
+         LIMIT ?2 OFFSET ?3",
+    )?;
+
+    let rows = stmt.query_map(params![fts_query, limit, offset], blob_with_metadata_from_row)?;
+
+    let mut blobs = Vec::new();
+    for blob in rows {
+        blobs.push(blob?);
+    }
+
+    Ok(blobs)
+}
+
 pub fn insert_path(conn: &Connection, hash: &str, path: &str) -> Result<()> {
     conn.execute(
         "INSERT OR REPLACE INTO paths (hash, path) VALUES (?1, ?2)",
@@ -85,6 +470,93 @@ pub fn insert_path(conn: &Connection, hash: &str, path: &str) -> Result<()> {
     Ok(())
 }
 
+/// Settings key holding this installation's stable device id
+const DEVICE_ID_KEY: &str = "device_id";
+
+/// Return this device's id, generating and persisting a UUID on first use
+pub fn get_or_create_device_id(conn: &Connection) -> Result<String> {
+    if let Some(existing) = get_setting(conn, DEVICE_ID_KEY)? {
+        return Ok(existing);
+    }
+
+    let device_id = uuid::Uuid::new_v4().to_string();
+    // INSERT OR IGNORE so a concurrent first call can't replace an id already handed out
+    conn.execute(
+        "INSERT OR IGNORE INTO settings (key, value) VALUES (?1, ?2)",
+        params![DEVICE_ID_KEY, device_id],
+    )?;
+    get_setting(conn, DEVICE_ID_KEY)?.context("Failed to persist device id")
+}
+
+pub fn record_avatar_migration(conn: &Connection, old_path: &str, sha256: &str) -> Result<()> {
+    let migrated_ms = chrono::Utc::now().timestamp_millis();
+
+    conn.execute(
+        "INSERT OR REPLACE INTO avatar_migrations (old_path, sha256, migrated_ms) VALUES (?1, ?2, ?3)",
+        params![old_path, sha256, migrated_ms],
+    )?;
+
+    Ok(())
+}
+
+pub fn list_avatar_migrations(conn: &Connection) -> Result<Vec<super::types::AvatarMigration>> {
+    let mut stmt = conn.prepare(
+        "SELECT old_path, sha256 FROM avatar_migrations ORDER BY migrated_ms, old_path",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(super::types::AvatarMigration {
+            old_path: row.get(0)?,
+            sha256: row.get(1)?,
+        })
+    })?;
+
+    let mut migrations = Vec::new();
+    for migration in rows {
+        migrations.push(migration?);
+    }
+
+    Ok(migrations)
+}
+
+pub fn queue_pending_write(conn: &Connection, id: &str, change_json: &str) -> Result<()> {
+    let queued_ms = chrono::Utc::now().timestamp_millis();
+
+    conn.execute(
+        "INSERT OR REPLACE INTO pending_writes (id, change_json, queued_ms) VALUES (?1, ?2, ?3)",
+        params![id, change_json, queued_ms],
+    )?;
+
+    Ok(())
+}
+
+pub fn list_pending_writes(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, change_json FROM pending_writes ORDER BY queued_ms, rowid",
+    )?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    let mut pending = Vec::new();
+    for entry in rows {
+        pending.push(entry?);
+    }
+
+    Ok(pending)
+}
+
+pub fn delete_pending_write(conn: &Connection, id: &str) -> Result<()> {
+    conn.execute("DELETE FROM pending_writes WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+pub fn update_path(conn: &Connection, old_path: &str, new_path: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE paths SET path = ?1 WHERE path = ?2",
+        params![new_path, old_path],
+    )?;
+
+    Ok(())
+}
+
 pub fn get_blob_by_hash(conn: &Connection, hash: &str) -> Result<Option<super::types::BlobInfo>> {
     let mut stmt = conn.prepare(
         "SELECT b.hash, b.size, b.mime, b.mtime_ms, b.created_ms, b.filename, b.health, p.path
@@ -112,16 +584,150 @@ pub fn get_blob_by_hash(conn: &Connection, hash: &str) -> Result<Option<super::t
     }
 }
 
+/// Health values the catalog assigns to blobs
+pub const HEALTH_STATUSES: &[&str] = &["healthy", "missing", "modified", "relocated"];
+
+fn blob_with_metadata_from_row(
+    row: &rusqlite::Row,
+) -> rusqlite::Result<super::types::BlobWithMetadata> {
+    Ok(super::types::BlobWithMetadata {
+        sha256: row.get(0)?,
+        size: row.get(1)?,
+        mime: row.get(2)?,
+        mtime_ms: row.get(3)?,
+        created_ms: row.get(4)?,
+        filename: row.get(5)?,
+        health: row.get(6)?,
+        image_width: row.get(7)?,
+        image_height: row.get(8)?,
+        line_count: row.get(9)?,
+        path: row.get(10)?,
+        page_count: row.get(11)?,
+    })
+}
+
 pub fn list_all_blobs(conn: &Connection) -> Result<Vec<super::types::BlobWithMetadata>> {
     let mut stmt = conn.prepare(
         "SELECT b.hash, b.size, b.mime, b.mtime_ms, b.created_ms, b.filename, b.health, 
-                b.image_width, b.image_height, b.line_count, p.path
+                b.image_width, b.image_height, b.line_count, p.path, b.page_count
          FROM blobs b
          LEFT JOIN paths p ON b.hash = p.hash",
     )?;
 
-    let rows = stmt.query_map([], |row| {
-        Ok(super::types::BlobWithMetadata {
+    let rows = stmt.query_map([], blob_with_metadata_from_row)?;
+
+    let mut blobs = Vec::new();
+    for blob in rows {
+        blobs.push(blob?);
+    }
+
+    Ok(blobs)
+}
+
+/// `LIKE` pattern for a MIME filter: `"image/"` matches every image type, anything
+/// else matches exactly
+fn mime_like_pattern(mime_prefix: &str) -> String {
+    let escaped = mime_prefix
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    if mime_prefix.ends_with('/') {
+        format!("{}%", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// One page of blobs (each once, with its primary path), optionally limited to MIME
+/// types matching `mime_prefix`, plus the total number matching
+///
+/// Ties on the sort column are broken by hash so pages never overlap. `limit` of `None`
+/// returns everything from `offset` on.
+pub fn list_all_blobs_paged(
+    conn: &Connection,
+    mime_prefix: Option<&str>,
+    sort: super::types::BlobSortKey,
+    descending: bool,
+    limit: Option<i64>,
+    offset: i64,
+) -> Result<(Vec<super::types::BlobWithMetadata>, i64)> {
+    const FILTER: &str = "WHERE (?1 IS NULL OR b.mime LIKE ?1 ESCAPE '\\')";
+    let pattern = mime_prefix.map(mime_like_pattern);
+
+    let total = conn.query_row(
+        &format!("SELECT COUNT(*) FROM blobs b {}", FILTER),
+        params![pattern],
+        |row| row.get(0),
+    )?;
+
+    let direction = if descending { "DESC" } else { "ASC" };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT b.hash, b.size, b.mime, b.mtime_ms, b.created_ms, b.filename, b.health,
+                b.image_width, b.image_height, b.line_count,
+                (SELECT MIN(p.path) FROM paths p WHERE p.hash = b.hash), b.page_count
+         FROM blobs b
+         {}
+         ORDER BY {} {}, b.hash {}
+         LIMIT ?2 OFFSET ?3",
+        FILTER,
+        sort.column(),
+        direction,
+        direction
+    ))?;
+
+    // SQLite treats a negative LIMIT as "no limit"
+    let rows = stmt.query_map(
+        params![pattern, limit.unwrap_or(-1), offset],
+        blob_with_metadata_from_row,
+    )?;
+
+    let mut blobs = Vec::new();
+    for blob in rows {
+        blobs.push(blob?);
+    }
+
+    Ok((blobs, total))
+}
+
+/// Every blob once, ordered by hash, with its primary (first) path
+pub fn list_blobs_with_primary_path(
+    conn: &Connection,
+) -> Result<Vec<super::types::BlobWithMetadata>> {
+    let mut stmt = conn.prepare(
+        "SELECT b.hash, b.size, b.mime, b.mtime_ms, b.created_ms, b.filename, b.health,
+                b.image_width, b.image_height, b.line_count,
+                (SELECT MIN(p.path) FROM paths p WHERE p.hash = b.hash), b.page_count
+         FROM blobs b
+         ORDER BY b.hash",
+    )?;
+
+    let rows = stmt.query_map([], blob_with_metadata_from_row)?;
+
+    let mut blobs = Vec::new();
+    for blob in rows {
+        blobs.push(blob?);
+    }
+
+    Ok(blobs)
+}
+
+/// Next `limit` blobs ordered by hash, strictly after `after_hash`
+pub fn list_blobs_after(
+    conn: &Connection,
+    after_hash: &str,
+    limit: i64,
+) -> Result<Vec<super::types::BlobInfo>> {
+    let mut stmt = conn.prepare(
+        "SELECT b.hash, b.size, b.mime, b.mtime_ms, b.created_ms, b.filename, b.health,
+                (SELECT p.path FROM paths p WHERE p.hash = b.hash LIMIT 1)
+         FROM blobs b
+         WHERE b.hash > ?1
+         ORDER BY b.hash
+         LIMIT ?2",
+    )?;
+
+    let rows = stmt.query_map(params![after_hash, limit], |row| {
+        Ok(super::types::BlobInfo {
             sha256: row.get(0)?,
             size: row.get(1)?,
             mime: row.get(2)?,
@@ -129,14 +735,253 @@ pub fn list_all_blobs(conn: &Connection) -> Result<Vec<super::types::BlobWithMet
             created_ms: row.get(4)?,
             filename: row.get(5)?,
             health: row.get(6)?,
-            image_width: row.get(7)?,
-            image_height: row.get(8)?,
-            line_count: row.get(9)?,
-            path: row.get(10)?,
-            page_count: None,
+            path: row.get(7)?,
+        })
+    })?;
+
+    let mut blobs = Vec::new();
+    for blob in rows {
+        blobs.push(blob?);
+    }
+
+    Ok(blobs)
+}
+
+/// Compact per-blob snapshot of the catalog, ordered by hash
+pub fn blob_manifest(conn: &Connection) -> Result<Vec<super::types::BlobManifestEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT hash, size, mime, COALESCE(health, 'healthy') FROM blobs ORDER BY hash",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(super::types::BlobManifestEntry {
+            sha256: row.get(0)?,
+            size: row.get(1)?,
+            mime: row.get(2)?,
+            health: row.get(3)?,
         })
     })?;
 
+    let mut entries = Vec::new();
+    for entry in rows {
+        entries.push(entry?);
+    }
+
+    Ok(entries)
+}
+
+/// List blobs with the given health status (one of `HEALTH_STATUSES`)
+pub fn list_blobs_by_health(
+    conn: &Connection,
+    status: &str,
+) -> Result<Vec<super::types::BlobWithMetadata>> {
+    let mut stmt = conn.prepare(
+        "SELECT b.hash, b.size, b.mime, b.mtime_ms, b.created_ms, b.filename, b.health, 
+                b.image_width, b.image_height, b.line_count, p.path, b.page_count
+         FROM blobs b
+         LEFT JOIN paths p ON b.hash = p.hash
+         WHERE b.health = ?1",
+    )?;
+
+    let rows = stmt.query_map(params![status], blob_with_metadata_from_row)?;
+
+    let mut blobs = Vec::new();
+    for blob in rows {
+        blobs.push(blob?);
+    }
+
+    Ok(blobs)
+}
+
+/// Push `entry` onto the last group if it shares `key`, otherwise start a new group
+fn push_grouped(
+    groups: &mut Vec<super::types::DuplicateGroup>,
+    key: String,
+    entry: super::types::DuplicateEntry,
+) {
+    match groups.last_mut() {
+        Some(group) if group.key == key => group.entries.push(entry),
+        _ => groups.push(super::types::DuplicateGroup {
+            key,
+            entries: vec![entry],
+        }),
+    }
+}
+
+/// Filenames shared by more than one distinct blob
+pub fn find_duplicate_filenames(conn: &Connection) -> Result<Vec<super::types::DuplicateGroup>> {
+    let mut stmt = conn.prepare(
+        "SELECT b.filename, b.hash, b.size,
+                (SELECT p.path FROM paths p WHERE p.hash = b.hash LIMIT 1)
+         FROM blobs b
+         WHERE b.filename IN (
+             SELECT filename FROM blobs
+             WHERE filename IS NOT NULL
+             GROUP BY filename
+             HAVING COUNT(DISTINCT hash) > 1
+         )
+         ORDER BY b.filename, b.size",
+    )?;
+
+    let mut groups = Vec::new();
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let filename: String = row.get(0)?;
+        let entry = super::types::DuplicateEntry {
+            sha256: row.get(1)?,
+            name: filename.clone(),
+            size: row.get(2)?,
+            path: row.get(3)?,
+        };
+        push_grouped(&mut groups, filename, entry);
+    }
+
+    Ok(groups)
+}
+
+/// Blobs whose content is stored under more than one distinct file name
+pub fn find_same_content_different_names(
+    conn: &Connection,
+) -> Result<Vec<super::types::DuplicateGroup>> {
+    let mut stmt = conn.prepare(
+        "SELECT p.hash, p.path, b.size
+         FROM paths p
+         JOIN blobs b ON b.hash = p.hash
+         WHERE p.hash IN (SELECT hash FROM paths GROUP BY hash HAVING COUNT(*) > 1)
+         ORDER BY p.hash, p.path",
+    )?;
+
+    let mut groups: Vec<super::types::DuplicateGroup> = Vec::new();
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let hash: String = row.get(0)?;
+        let path: String = row.get(1)?;
+        let name = std::path::Path::new(&path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        let entry = super::types::DuplicateEntry {
+            sha256: hash.clone(),
+            name,
+            size: row.get(2)?,
+            path: Some(path),
+        };
+        push_grouped(&mut groups, hash, entry);
+    }
+
+    // Several paths with the same name (e.g. in different shards) aren't a naming problem
+    groups.retain(|group| {
+        let first = &group.entries[0].name;
+        group.entries.iter().any(|e| &e.name != first)
+    });
+
+    Ok(groups)
+}
+
+/// Blobs known under more than one filename or stored at more than one path, with the
+/// space deduplication saves
+///
+/// Logical size counts each blob once per filename it was stored under; physical size
+/// counts it once per file on disk.
+pub fn dedup_report(conn: &Connection) -> Result<super::types::DedupReport> {
+    use std::collections::BTreeMap;
+
+    let mut names: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut stmt = conn.prepare("SELECT hash, filename FROM blob_aliases ORDER BY hash, filename")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        names.entry(row.get(0)?).or_default().push(row.get(1)?);
+    }
+
+    let mut paths: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut stmt = conn.prepare("SELECT hash, path FROM paths ORDER BY hash, path")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        paths.entry(row.get(0)?).or_default().push(row.get(1)?);
+    }
+
+    let mut report = super::types::DedupReport {
+        groups: Vec::new(),
+        logical_bytes: 0,
+        physical_bytes: 0,
+        saved_bytes: 0,
+    };
+    let mut stmt = conn.prepare("SELECT hash, size FROM blobs ORDER BY hash")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let hash: String = row.get(0)?;
+        let size: i64 = row.get(1)?;
+        let filenames = names.remove(&hash).unwrap_or_default();
+        let blob_paths = paths.remove(&hash).unwrap_or_default();
+
+        report.logical_bytes += size * filenames.len().max(1) as i64;
+        report.physical_bytes += size * blob_paths.len() as i64;
+
+        if filenames.len() > 1 || blob_paths.len() > 1 {
+            let bytes_saved = size * filenames.len().saturating_sub(1) as i64;
+            report.saved_bytes += bytes_saved;
+            report.groups.push(super::types::DedupGroup {
+                sha256: hash,
+                size,
+                filenames,
+                paths: blob_paths,
+                bytes_saved,
+            });
+        }
+    }
+
+    // Biggest wins first
+    report.groups.sort_by(|a, b| b.bytes_saved.cmp(&a.bytes_saved).then(b.size.cmp(&a.size)));
+    Ok(report)
+}
+
+/// Tag a blob; tagging it again with the same tag is a no-op
+pub fn add_blob_tag(conn: &Connection, hash: &str, tag: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO blob_tags (hash, tag, created_ms) VALUES (?1, ?2, ?3)",
+        params![hash, tag, chrono::Utc::now().timestamp_millis()],
+    )?;
+    Ok(())
+}
+
+/// Remove a tag from a blob, returning whether it had it
+pub fn remove_blob_tag(conn: &Connection, hash: &str, tag: &str) -> Result<bool> {
+    let removed = conn.execute(
+        "DELETE FROM blob_tags WHERE hash = ?1 AND tag = ?2",
+        params![hash, tag],
+    )?;
+    Ok(removed > 0)
+}
+
+/// A blob's tags, alphabetically
+pub fn list_blob_tags(conn: &Connection, hash: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT tag FROM blob_tags WHERE hash = ?1 ORDER BY tag")?;
+    let rows = stmt.query_map(params![hash], |row| row.get(0))?;
+
+    let mut tags = Vec::new();
+    for tag in rows {
+        tags.push(tag?);
+    }
+
+    Ok(tags)
+}
+
+/// Blobs carrying `tag`, most recently tagged first
+pub fn list_blobs_by_tag(
+    conn: &Connection,
+    tag: &str,
+) -> Result<Vec<super::types::BlobWithMetadata>> {
+    let mut stmt = conn.prepare(
+        "SELECT b.hash, b.size, b.mime, b.mtime_ms, b.created_ms, b.filename, b.health,
+                b.image_width, b.image_height, b.line_count,
+                (SELECT MIN(p.path) FROM paths p WHERE p.hash = b.hash), b.page_count
+         FROM blob_tags t
+         JOIN blobs b ON b.hash = t.hash
+         WHERE t.tag = ?1
+         ORDER BY t.created_ms DESC, b.hash",
+    )?;
+
+    let rows = stmt.query_map(params![tag], blob_with_metadata_from_row)?;
+
     let mut blobs = Vec::new();
     for blob in rows {
         blobs.push(blob?);
@@ -148,6 +993,90 @@ pub fn list_all_blobs(conn: &Connection) -> Result<Vec<super::types::BlobWithMet
 pub fn delete_blob(conn: &Connection, hash: &str) -> Result<()> {
     conn.execute("DELETE FROM paths WHERE hash = ?1", params![hash])?;
     conn.execute("DELETE FROM blobs WHERE hash = ?1", params![hash])?;
+    conn.execute("DELETE FROM blobs_fts WHERE hash = ?1", params![hash])?;
+    conn.execute("DELETE FROM blob_aliases WHERE hash = ?1", params![hash])?;
+    Ok(())
+}
+
+/// Record an image's pixel dimensions (`None` when they couldn't be read)
+pub fn set_image_dimensions(
+    conn: &Connection,
+    hash: &str,
+    width: Option<i32>,
+    height: Option<i32>,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE blobs SET image_width = ?1, image_height = ?2 WHERE hash = ?3",
+        params![width, height, hash],
+    )?;
+    Ok(())
+}
+
+/// Record a text blob's line count (`None` when it couldn't be counted)
+pub fn set_line_count(conn: &Connection, hash: &str, line_count: Option<i32>) -> Result<()> {
+    conn.execute(
+        "UPDATE blobs SET line_count = ?1 WHERE hash = ?2",
+        params![line_count, hash],
+    )?;
+    Ok(())
+}
+
+pub fn get_line_count(conn: &Connection, hash: &str) -> Result<Option<i32>> {
+    let line_count = conn
+        .query_row(
+            "SELECT line_count FROM blobs WHERE hash = ?1",
+            params![hash],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(line_count.flatten())
+}
+
+/// Record a PDF's page count (`None` when it couldn't be read)
+pub fn set_page_count(conn: &Connection, hash: &str, page_count: Option<i32>) -> Result<()> {
+    conn.execute(
+        "UPDATE blobs SET page_count = ?1 WHERE hash = ?2",
+        params![page_count, hash],
+    )?;
+    Ok(())
+}
+
+/// Record the MIME the caller supplied when it was overridden by content sniffing
+pub fn set_declared_mime(conn: &Connection, hash: &str, declared_mime: Option<&str>) -> Result<()> {
+    conn.execute(
+        "UPDATE blobs SET declared_mime = ?1 WHERE hash = ?2",
+        params![declared_mime, hash],
+    )?;
+    Ok(())
+}
+
+pub fn update_health(conn: &Connection, hash: &str, health: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE blobs SET health = ?1 WHERE hash = ?2",
+        params![health, hash],
+    )?;
+    Ok(())
+}
+
+pub fn get_paths_for_hash(conn: &Connection, hash: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT path FROM paths WHERE hash = ?1")?;
+    let rows = stmt.query_map(params![hash], |row| row.get(0))?;
+
+    let mut paths = Vec::new();
+    for path in rows {
+        paths.push(path?);
+    }
+
+    Ok(paths)
+}
+
+pub fn delete_path(conn: &Connection, path: &str) -> Result<()> {
+    conn.execute("DELETE FROM paths WHERE path = ?1", params![path])?;
+    Ok(())
+}
+
+pub fn delete_paths_for_hash(conn: &Connection, hash: &str) -> Result<()> {
+    conn.execute("DELETE FROM paths WHERE hash = ?1", params![hash])?;
     Ok(())
 }
 
@@ -156,6 +1085,7 @@ pub fn update_filename(conn: &Connection, hash: &str, filename: &str) -> Result<
         "UPDATE blobs SET filename = ?1 WHERE hash = ?2",
         params![filename, hash],
     )?;
+    index_filename(conn, hash, filename)?;
     Ok(())
 }
 
@@ -201,3 +1131,173 @@ pub fn get_stats(conn: &Connection) -> Result<super::types::HealthReport> {
         total_size,
     })
 }
+
+/// Blob count and total size per MIME type; rows without a MIME are grouped as "unknown"
+pub fn get_stats_by_mime(
+    conn: &Connection,
+) -> Result<std::collections::BTreeMap<String, super::types::MimeStats>> {
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(NULLIF(mime, ''), 'unknown'), COUNT(*), COALESCE(SUM(size), 0)
+         FROM blobs
+         GROUP BY 1",
+    )?;
+
+    let stats = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                super::types::MimeStats {
+                    count: row.get(1)?,
+                    size: row.get(2)?,
+                },
+            ))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(stats)
+}
+
+/// Total on-disk size of the catalog, including any WAL sidecar file
+pub fn catalog_file_size() -> Result<u64> {
+    let db_path = get_db_path()?;
+    let mut total = std::fs::metadata(&db_path).map(|m| m.len()).unwrap_or(0);
+    let wal_path = db_path.with_extension("db-wal");
+    total += std::fs::metadata(&wal_path).map(|m| m.len()).unwrap_or(0);
+    Ok(total)
+}
+
+/// Rebuild the catalog file to reclaim space freed by deletes
+pub fn vacuum(conn: &Connection) -> Result<()> {
+    let journal_mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?;
+    if journal_mode.eq_ignore_ascii_case("wal") {
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    }
+    conn.execute_batch("VACUUM")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Open a catalog file the way `get_connection` does
+    fn open_catalog(path: &std::path::Path) -> Connection {
+        let conn = Connection::open(path).unwrap();
+        configure_connection(&conn).unwrap();
+        init_schema(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn concurrent_writers_wait_instead_of_failing() {
+        let dir = std::env::temp_dir().join(format!("deeprecall-catalog-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("concurrent.db");
+        let _ = std::fs::remove_file(&path);
+
+        // Every thread opens (and migrates) the fresh catalog at once, then writes
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let path = path.clone();
+                std::thread::spawn(move || -> Result<()> {
+                    let mut conn = Connection::open(&path)?;
+                    configure_connection(&conn)?;
+                    init_schema(&conn)?;
+                    for i in 0..50 {
+                        let key = format!("writer-{}-{}", writer, i);
+                        if i % 2 == 0 {
+                            set_setting(&conn, &key, "value")?;
+                        } else {
+                            let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+                            set_setting(&tx, &key, "value")?;
+                            tx.commit()?;
+                        }
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        for handle in writers {
+            handle.join().unwrap().unwrap();
+        }
+
+        let conn = open_catalog(&path);
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM settings WHERE key LIKE 'writer-%'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 8 * 50);
+        assert_eq!(get_schema_version(&conn).unwrap(), current_schema_version());
+
+        drop(conn);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).unwrap();
+        let names = stmt.query_map([], |row| row.get(1)).unwrap();
+        names.map(|name| name.unwrap()).collect()
+    }
+
+    #[test]
+    fn unversioned_catalog_migrates_to_current() {
+        // A catalog from before versioning: no schema_version, no newer columns or tables
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE blobs (
+                hash TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                mime TEXT NOT NULL,
+                mtime_ms INTEGER NOT NULL,
+                created_ms INTEGER NOT NULL,
+                filename TEXT,
+                health TEXT DEFAULT 'healthy',
+                image_width INTEGER,
+                image_height INTEGER,
+                line_count INTEGER
+            );
+            CREATE TABLE paths (hash TEXT NOT NULL, path TEXT NOT NULL PRIMARY KEY);
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO blobs (hash, size, mime, mtime_ms, created_ms, filename)
+                VALUES ('abc', 3, 'application/pdf', 1, 2, 'paper.pdf');
+            INSERT INTO paths (hash, path) VALUES ('abc', '/blobs/ab/abc.pdf');",
+        )
+        .unwrap();
+
+        init_schema(&conn).unwrap();
+
+        assert_eq!(get_schema_version(&conn).unwrap(), current_schema_version());
+        let blob_columns = columns(&conn, "blobs");
+        assert!(blob_columns.contains(&"declared_mime".to_string()));
+        assert!(blob_columns.contains(&"page_count".to_string()));
+        for table in ["pending_writes", "avatar_migrations", "blob_aliases", "blob_tags"] {
+            assert!(!columns(&conn, table).is_empty(), "{} missing", table);
+        }
+
+        // Existing rows survive and seed the new indexes
+        let blob = get_blob_by_hash(&conn, "abc").unwrap().unwrap();
+        assert_eq!(blob.filename.as_deref(), Some("paper.pdf"));
+        assert_eq!(blob.path.as_deref(), Some("/blobs/ab/abc.pdf"));
+        let alias: String = conn
+            .query_row("SELECT filename FROM blob_aliases WHERE hash = 'abc'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(alias, "paper.pdf");
+        let indexed: String = conn
+            .query_row("SELECT filename FROM blobs_fts WHERE hash = 'abc'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(indexed, "paper.pdf");
+
+        // Re-running is a no-op
+        init_schema(&conn).unwrap();
+        let applied: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(applied, current_schema_version());
+    }
+
+    #[test]
+    fn empty_catalog_migrates_to_current() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        assert_eq!(get_schema_version(&conn).unwrap(), current_schema_version());
+    }
+}
@@ -6,7 +6,21 @@ pub mod catalog;
 pub mod types;
 
 pub use catalog::{
-    delete_blob, get_blob_by_hash, get_connection, get_stats, insert_blob, insert_path,
-    list_all_blobs, update_filename,
+    add_blob_tag, blob_manifest, catalog_file_size, configured_data_dir, data_dir_config_path, dedup_report,
+    delete_blob, delete_path, delete_paths_for_hash, delete_pending_write, delete_setting,
+    ensure_writable_dir, find_duplicate_filenames, find_same_content_different_names,
+    get_blob_by_hash, get_connection, get_data_dir, get_line_count, get_or_create_device_id,
+    get_paths_for_hash, get_setting, get_stats, get_stats_by_mime, insert_blob, insert_path,
+    list_all_blobs, list_all_blobs_paged, list_avatar_migrations, list_blob_tags, list_blobs_after,
+    list_blobs_by_health, list_blobs_by_tag, list_blobs_with_primary_path, list_pending_writes,
+    queue_pending_write, record_avatar_migration, remove_blob_tag, search_blobs, set_declared_mime,
+    set_image_dimensions, set_line_count, set_page_count, set_setting, update_filename,
+    update_health, update_path, vacuum, CATALOG_LOCK, DATA_DIR_ENV, HEALTH_STATUSES,
+};
+pub use types::{
+    AvatarMigration, BlobAction, BlobCategory, BlobClass, BlobInfo, BlobManifestEntry, BlobPage,
+    BlobReference, BlobSortKey, BlobWithMetadata, DedupReport, DuplicateGroup, GcReport,
+    HealthReport, ImportResult, IntegrityProgress, IntegrityReport, ReindexReport,
+    RelocationReport, ReshardResult, ScanResult, StoreBlobResult, VacuumResult, VerifyResult,
+    ZipExportResult,
 };
-pub use types::{BlobInfo, BlobWithMetadata, HealthReport, ScanResult};
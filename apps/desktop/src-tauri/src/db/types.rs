@@ -39,14 +39,212 @@ pub struct BlobWithMetadata {
     pub line_count: Option<i32>,
 }
 
+/// Column `list_blobs` orders by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlobSortKey {
+    #[default]
+    CreatedMs,
+    Size,
+    Filename,
+}
+
+impl BlobSortKey {
+    pub fn column(self) -> &'static str {
+        match self {
+            BlobSortKey::CreatedMs => "b.created_ms",
+            BlobSortKey::Size => "b.size",
+            BlobSortKey::Filename => "b.filename",
+        }
+    }
+}
+
+/// One page of `list_blobs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobPage {
+    pub blobs: Vec<BlobWithMetadata>,
+    /// Matching blobs across all pages
+    pub total: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanResult {
     pub added: i32,
     pub updated: i32,
+    /// Files whose size and mtime matched the catalog, so re-detection was skipped
+    pub skipped: i32,
+    /// Known blobs found at a new location after their recorded primary path vanished
+    pub relocated: i32,
+    /// Recorded paths removed because their file no longer exists
     pub deleted: i32,
     pub errors: Vec<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub added: i32,
+    /// Files already in the store, or over the size limit
+    pub skipped: i32,
+    pub errors: Vec<String>,
+}
+
+/// Outcome of one file in a `store_blobs` batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreBlobResult {
+    pub filename: String,
+    /// The stored (or already present) blob; `None` if the file failed
+    pub blob: Option<BlobWithMetadata>,
+    /// The content was already in the store, so nothing was written
+    pub deduplicated: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobReference {
+    pub table: String,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReshardResult {
+    pub depth: usize,
+    pub moved: i32,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelocationReport {
+    pub checked: i32,
+    pub relocated: i32,
+    pub duplicates_removed: i32,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReindexReport {
+    pub checked: i32,
+    pub unchanged: i32,
+    pub relocated: i32,
+    pub missing: i32,
+    /// Files in the store with no catalog entry (left for `scan_blobs`)
+    pub untracked: i32,
+    pub errors: Vec<String>,
+}
+
+/// Outcome of `verify_blobs`: how many blobs moved into each health state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyResult {
+    pub checked: i32,
+    pub healthy: i32,
+    pub modified: i32,
+    pub missing: i32,
+    /// Blobs whose health was already correct
+    pub unchanged: i32,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityProgress {
+    pub verified: i32,
+    pub total: i32,
+    pub modified: i32,
+    pub missing: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// Blobs re-hashed during this run
+    pub verified: i32,
+    pub modified: i32,
+    pub missing: i32,
+    /// Pass to `verify_all_integrity` to continue; `None` once the whole catalog is done
+    pub resume_token: Option<String>,
+    pub health: HealthReport,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateEntry {
+    pub sha256: String,
+    pub name: String,
+    pub size: i64,
+    pub path: Option<String>,
+}
+
+/// Catalog entries sharing a key: a filename, or a content hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub key: String,
+    pub entries: Vec<DuplicateEntry>,
+}
+
+/// Outcome of `gc_blobs`: unreferenced files removed (or, in a dry run, removable)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GcReport {
+    pub dry_run: bool,
+    pub paths: Vec<String>,
+    pub bytes: u64,
+    pub errors: Vec<String>,
+}
+
+/// One blob in a `dedup_report`: every name and file it is known by
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupGroup {
+    pub sha256: String,
+    pub size: i64,
+    /// Distinct filenames the content was stored under
+    pub filenames: Vec<String>,
+    pub paths: Vec<String>,
+    /// Bytes not written because later filenames reused the stored content
+    pub bytes_saved: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupReport {
+    pub groups: Vec<DedupGroup>,
+    /// Size if every filename had its own copy
+    pub logical_bytes: i64,
+    /// Size of the files actually on disk
+    pub physical_bytes: i64,
+    pub saved_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZipExportResult {
+    pub exported: i32,
+    pub bytes: u64,
+    /// Hashes skipped because their file is missing from disk
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VacuumResult {
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+/// One blob in a device's manifest, for diffing against other devices or `device_blobs`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobManifestEntry {
+    pub sha256: String,
+    pub size: i64,
+    pub mime: String,
+    pub health: String,
+}
+
+/// A legacy avatar file and the blob it was imported as
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AvatarMigration {
+    pub old_path: String,
+    pub sha256: String,
+}
+
+/// Blob count and total size for one MIME type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MimeStats {
+    pub count: i64,
+    pub size: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthReport {
     pub total_blobs: i32,
@@ -57,6 +255,35 @@ pub struct HealthReport {
     pub total_size: i64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobCategory {
+    Document,
+    Image,
+    Audio,
+    Video,
+    Code,
+    Archive,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlobAction {
+    OpenReader,
+    ViewImage,
+    PlayMedia,
+    ViewText,
+    OpenExternal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobClass {
+    pub category: BlobCategory,
+    pub previewable: bool,
+    pub default_action: BlobAction,
+}
+
 impl From<BlobInfo> for BlobWithMetadata {
     fn from(info: BlobInfo) -> Self {
         BlobWithMetadata {
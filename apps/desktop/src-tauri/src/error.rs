@@ -0,0 +1,149 @@
+//! Structured error type returned by Tauri commands
+//!
+//! Serializes to `{ code, message }` so the frontend can branch on `code`
+//! instead of matching on message text.
+
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum CommandError {
+    /// The requested blob, row or file doesn't exist
+    NotFound(String),
+    /// The blob is catalogued but its file is gone from disk
+    Missing(String),
+    /// The OS refused access to a file or directory
+    PermissionDenied(String),
+    /// Any other filesystem failure
+    Io(String),
+    /// SQLite or Postgres rejected a query
+    Database(String),
+    /// Postgres is unreachable
+    Offline(String),
+    /// Arguments from the frontend failed validation
+    InvalidInput(String),
+    /// The OS keychain failed
+    Keychain(String),
+    /// The target is still referenced or was changed by someone else
+    Conflict(String),
+    /// The operation can't run right now (e.g. another maintenance task holds the catalog)
+    Busy(String),
+    /// The user cancelled a long-running operation
+    Cancelled(String),
+    /// Anything that doesn't fit above
+    Internal(String),
+}
+
+pub type CommandResult<T> = Result<T, CommandError>;
+
+impl CommandError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::NotFound(_) => "not_found",
+            CommandError::Missing(_) => "missing",
+            CommandError::PermissionDenied(_) => "permission_denied",
+            CommandError::Io(_) => "io",
+            CommandError::Database(_) => "database",
+            CommandError::Offline(_) => "offline",
+            CommandError::InvalidInput(_) => "invalid_input",
+            CommandError::Keychain(_) => "keychain",
+            CommandError::Conflict(_) => "conflict",
+            CommandError::Busy(_) => "busy",
+            CommandError::Cancelled(_) => "cancelled",
+            CommandError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            CommandError::NotFound(m)
+            | CommandError::Missing(m)
+            | CommandError::PermissionDenied(m)
+            | CommandError::Io(m)
+            | CommandError::Database(m)
+            | CommandError::Offline(m)
+            | CommandError::InvalidInput(m)
+            | CommandError::Keychain(m)
+            | CommandError::Conflict(m)
+            | CommandError::Busy(m)
+            | CommandError::Cancelled(m)
+            | CommandError::Internal(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            code: &'static str,
+            message: &'a str,
+        }
+
+        Payload {
+            code: self.code(),
+            message: self.message(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => CommandError::NotFound(e.to_string()),
+            std::io::ErrorKind::PermissionDenied => CommandError::PermissionDenied(e.to_string()),
+            _ => CommandError::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for CommandError {
+    fn from(e: rusqlite::Error) -> Self {
+        CommandError::Database(e.to_string())
+    }
+}
+
+impl From<tokio_postgres::Error> for CommandError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        if e.is_closed() {
+            CommandError::Offline(e.to_string())
+        } else {
+            CommandError::Database(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(e: serde_json::Error) -> Self {
+        CommandError::InvalidInput(e.to_string())
+    }
+}
+
+impl From<std::time::SystemTimeError> for CommandError {
+    fn from(e: std::time::SystemTimeError) -> Self {
+        CommandError::Io(e.to_string())
+    }
+}
+
+impl From<anyhow::Error> for CommandError {
+    fn from(e: anyhow::Error) -> Self {
+        // Catalog helpers return anyhow; recover the underlying kind where we can
+        if let Some(io) = e.downcast_ref::<std::io::Error>() {
+            let kind = io.kind();
+            return CommandError::from(std::io::Error::new(kind, format!("{:#}", e)));
+        }
+        if e.downcast_ref::<rusqlite::Error>().is_some() {
+            return CommandError::Database(format!("{:#}", e));
+        }
+        CommandError::Internal(format!("{:#}", e))
+    }
+}
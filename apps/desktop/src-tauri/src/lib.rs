@@ -1,6 +1,7 @@
 // Module declarations
 mod commands;
 mod db;
+mod error;
 mod logger;
 
 use commands::{auth, avatars, blobs, database, devtools, oauth_server};
@@ -84,46 +85,117 @@ pub fn run() {
                     app_log!("DevTools opened via TAURI_OPEN_DEVTOOLS");
                 }
             }
+            
+            // Warn the UI before the blob store fills the disk
+            blobs::spawn_storage_monitor(app.handle().clone());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             greet,
             logger::get_log_path,
+            logger::set_log_level,
+            logger::tail_log,
+            logger::clear_logs,
             // Auth commands
             auth::open_auth_window,
             auth::save_auth_session,
             auth::get_auth_session,
             auth::clear_auth_session,
+            auth::get_session_status,
+            auth::list_auth_keys,
+            auth::clear_all_auth_sessions,
             // OAuth loopback server commands
             oauth_server::start_oauth_loopback,
             oauth_server::stop_oauth_loopback,
+            oauth_server::exchange_oauth_code,
             // DevTools commands
             devtools::open_devtools,
             devtools::close_devtools,
             devtools::is_devtools_open,
             // Blob commands
             blobs::list_blobs,
+            blobs::search_blobs,
+            blobs::list_blobs_by_health,
+            blobs::export_blob_manifest,
             blobs::stat_blob,
             blobs::store_blob,
+            blobs::store_blobs,
+            blobs::store_blob_from_path,
+            blobs::import_blobs_from_dir,
+            blobs::begin_blob_upload,
+            blobs::get_blob_upload_offset,
+            blobs::append_blob_chunk,
+            blobs::finish_blob_upload,
+            blobs::abort_blob_upload,
             blobs::delete_blob,
             blobs::rename_blob,
+            blobs::add_blob_tag,
+            blobs::remove_blob_tag,
+            blobs::list_blob_tags,
+            blobs::search_blobs_by_tag,
             blobs::scan_blobs,
+            blobs::fix_mislocated_blobs,
+            blobs::reindex_paths,
+            blobs::get_scan_ignore_list,
+            blobs::set_scan_ignore_list,
             blobs::health_check,
+            blobs::verify_blobs,
+            blobs::verify_all_integrity,
+            blobs::get_integrity_resume_token,
+            blobs::cancel_integrity_check,
+            blobs::set_integrity_throttle,
             blobs::get_blob_stats,
+            blobs::vacuum_catalog,
+            blobs::check_storage,
+            blobs::set_storage_thresholds,
+            blobs::export_catalog_csv,
+            blobs::export_blobs_zip,
+            blobs::find_duplicate_filenames,
+            blobs::find_same_content_different_names,
+            blobs::dedup_report,
             blobs::read_blob,
+            blobs::read_blob_bytes,
+            blobs::read_blob_base64,
+            blobs::read_blob_data_url,
+            blobs::get_thumbnail,
+            blobs::prefetch_blob,
             blobs::sync_blob_to_electric,
+            blobs::get_or_create_device_id,
             blobs::clear_all_blobs,
+            blobs::classify_blob,
+            blobs::get_blob_shard_depth,
+            blobs::get_blob_references,
+            blobs::delete_blob_file,
+            blobs::gc_blobs,
+            blobs::resolve_blob_path,
+            blobs::reveal_blob_in_explorer,
+            blobs::open_blob_store_dir,
+            blobs::get_data_directory,
+            blobs::set_data_directory,
+            blobs::set_blob_shard_depth,
             // Database commands
             database::flush_writes,
+            database::queue_writes,
+            database::flush_pending_writes,
             database::clear_all_database,
             database::export_all_data,
+            database::export_all_data_to_file,
+            database::cancel_export,
             database::estimate_export_size,
             database::import_data,
+            database::import_data_from_file,
+            database::merge_authors,
             database::query_postgres_table,
+            database::describe_table,
+            database::verify_pg_schema,
+            database::get_row,
             database::query_all_postgres_tables,
+            database::get_table_counts,
             // Avatar commands
             avatars::upload_avatar,
+            avatars::list_avatar_versions,
             avatars::delete_avatar,
+            avatars::migrate_avatars_to_blobs,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
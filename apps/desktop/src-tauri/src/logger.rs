@@ -1,8 +1,68 @@
 use std::fs::{OpenOptions, File};
-use std::io::Write;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::sync::Mutex;
-use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::path::{Path, PathBuf};
 use lazy_static::lazy_static;
+use crate::error::{CommandError, CommandResult};
+
+/// Severity of a log message; messages above the current level are dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum LogLevel {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+}
+
+impl LogLevel {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => LogLevel::Error,
+            1 => LogLevel::Warn,
+            2 => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+        }
+    }
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            other => Err(format!("Unknown log level: {}", other)),
+        }
+    }
+}
+
+/// Most verbose level currently written (override at startup with `LOG_LEVEL`)
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+
+/// Write one JSON object per line instead of plain text (`LOG_FORMAT=json`)
+static JSON_FORMAT: AtomicBool = AtomicBool::new(false);
+
+/// Size at which the active log is rotated (override with `LOG_MAX_BYTES`)
+const DEFAULT_MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+/// Rotated logs kept as `deeprecall.log.1` .. `.N` (override with `LOG_MAX_ARCHIVES`)
+const DEFAULT_MAX_LOG_ARCHIVES: usize = 5;
+
+static MAX_LOG_BYTES: AtomicU64 = AtomicU64::new(DEFAULT_MAX_LOG_BYTES);
+static MAX_LOG_ARCHIVES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_LOG_ARCHIVES);
 
 lazy_static! {
     static ref LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
@@ -16,6 +76,22 @@ pub fn init_logger() {
         .join("DeepRecall")
         .join("deeprecall.log");
 
+    if let Ok(level) = std::env::var("LOG_LEVEL") {
+        match level.parse::<LogLevel>() {
+            Ok(level) => LOG_LEVEL.store(level as u8, Ordering::Relaxed),
+            Err(e) => eprintln!("Ignoring LOG_LEVEL: {}", e),
+        }
+    }
+    if std::env::var("LOG_FORMAT").is_ok_and(|f| f.eq_ignore_ascii_case("json")) {
+        JSON_FORMAT.store(true, Ordering::Relaxed);
+    }
+    if let Some(max_bytes) = std::env::var("LOG_MAX_BYTES").ok().and_then(|v| v.parse().ok()) {
+        MAX_LOG_BYTES.store(max_bytes, Ordering::Relaxed);
+    }
+    if let Some(max_archives) = std::env::var("LOG_MAX_ARCHIVES").ok().and_then(|v| v.parse().ok()) {
+        MAX_LOG_ARCHIVES.store(max_archives, Ordering::Relaxed);
+    }
+
     // Create directory if it doesn't exist
     if let Some(parent) = log_path.parent() {
         let _ = std::fs::create_dir_all(parent);
@@ -47,23 +123,260 @@ pub fn get_log_path() -> String {
         .unwrap_or_else(|| "Log file not initialized".to_string())
 }
 
+/// Chunk size used when scanning the log backwards
+const TAIL_CHUNK_BYTES: u64 = 8 * 1024;
+
+/// Read the last `max_lines` lines of a file, scanning backwards from the end so large
+/// logs aren't loaded whole
+fn read_tail(path: &Path, max_lines: usize) -> std::io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let mut pos = file.metadata()?.len();
+    let mut tail: Vec<u8> = Vec::new();
+    let mut newlines = 0;
+
+    // One newline more than lines wanted guarantees the oldest kept line is complete
+    while pos > 0 && newlines <= max_lines {
+        let chunk_len = TAIL_CHUNK_BYTES.min(pos);
+        pos -= chunk_len;
+        file.seek(SeekFrom::Start(pos))?;
+        let mut chunk = vec![0u8; chunk_len as usize];
+        file.read_exact(&mut chunk)?;
+        newlines += chunk.iter().filter(|&&b| b == b'\n').count();
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+    }
+
+    let text = String::from_utf8_lossy(&tail);
+    let lines: Vec<&str> = text.lines().collect();
+    let skip = lines.len().saturating_sub(max_lines);
+    Ok(lines[skip..].iter().map(|line| line.to_string()).collect())
+}
+
+/// Return the last `max_lines` lines of the active log, for the in-app log viewer
+#[tauri::command]
+pub fn tail_log(max_lines: usize) -> Result<Vec<String>, String> {
+    let log_path = LOG_PATH
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "Log file not initialized".to_string())?;
+    if max_lines == 0 {
+        return Ok(Vec::new());
+    }
+    read_tail(&log_path, max_lines)
+        .map_err(|e| format!("Failed to read {}: {}", log_path.display(), e))
+}
+
+/// Truncate the active log and, with `remove_archives`, delete rotated `.N` files
+///
+/// Holds the `LOG_FILE` lock throughout so concurrent writes land either before the
+/// truncation or after the header line.
+#[tauri::command]
+pub fn clear_logs(remove_archives: Option<bool>) -> Result<(), String> {
+    let mut log_file = LOG_FILE.lock().unwrap();
+    let file = log_file.as_mut().ok_or_else(|| "Log file not initialized".to_string())?;
+    file.set_len(0).map_err(|e| format!("Failed to clear log file: {}", e))?;
+
+    if remove_archives.unwrap_or(false) {
+        if let Some(log_path) = LOG_PATH.lock().unwrap().clone() {
+            for n in 1..=MAX_LOG_ARCHIVES.load(Ordering::Relaxed) {
+                let archive = archive_path(&log_path, n);
+                if archive.exists() {
+                    std::fs::remove_file(&archive)
+                        .map_err(|e| format!("Failed to remove {}: {}", archive.display(), e))?;
+                }
+            }
+        }
+    }
+
+    let header = format_line(
+        LogLevel::Info,
+        &format!("=== Log cleared at {} ===", chrono::Local::now()),
+    );
+    file.write_all(header.as_bytes())
+        .and_then(|_| file.flush())
+        .map_err(|e| format!("Failed to write to log file: {}", e))
+}
+
+/// Change which messages are written, without restarting
+///
+/// Accepts `error`, `warn`, `info` or `debug`.
+#[tauri::command]
+pub fn set_log_level(level: String) -> CommandResult<()> {
+    let level: LogLevel = level.parse().map_err(CommandError::InvalidInput)?;
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+    log(&format!("Log level set to {}", level.label()));
+    Ok(())
+}
+
+/// `deeprecall.log.<n>`
+fn archive_path(log_path: &Path, n: usize) -> PathBuf {
+    let mut name = log_path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Shift `deeprecall.log.1..N` up by one (dropping the oldest), move the active log to
+/// `.1` and open a fresh file in its place
+///
+/// Called with the `LOG_FILE` lock held, so no other thread can write mid-rotation.
+fn rotate(file: &mut Option<File>, log_path: &Path, max_archives: usize) {
+    // Close the active file first; Windows refuses to rename open files
+    *file = None;
+
+    if max_archives == 0 {
+        let _ = std::fs::remove_file(log_path);
+    } else {
+        let _ = std::fs::remove_file(archive_path(log_path, max_archives));
+        for n in (1..max_archives).rev() {
+            let _ = std::fs::rename(archive_path(log_path, n), archive_path(log_path, n + 1));
+        }
+        if let Err(e) = std::fs::rename(log_path, archive_path(log_path, 1)) {
+            eprintln!("Failed to rotate log file: {}", e);
+        }
+    }
+
+    match OpenOptions::new().create(true).append(true).open(log_path) {
+        Ok(fresh) => *file = Some(fresh),
+        Err(e) => eprintln!("Failed to reopen log file after rotation: {}", e),
+    }
+}
+
+/// Split a leading `[Target]` tag (e.g. `[OAuth]`) off a message
+fn split_target(message: &str) -> (&str, &str) {
+    message
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .filter(|(target, _)| !target.is_empty() && !target.contains(char::is_whitespace))
+        .map(|(target, rest)| (target, rest.trim_start()))
+        .unwrap_or(("app", message))
+}
+
+/// Format a log line as plain text or, in JSON mode, `{ts, level, target, message}`
+fn format_line(level: LogLevel, message: &str) -> String {
+    let now = chrono::Local::now();
+    if JSON_FORMAT.load(Ordering::Relaxed) {
+        let (target, message) = split_target(message);
+        let entry = serde_json::json!({
+            "ts": now.to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+            "level": level.label(),
+            "target": target,
+            "message": message,
+        });
+        format!("{}\n", entry)
+    } else {
+        let timestamp = now.format("%Y-%m-%d %H:%M:%S%.3f");
+        format!("[{}] [{}] {}\n", timestamp, level.label(), message)
+    }
+}
+
+/// Log at `Info`
 pub fn log(message: &str) {
-    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
-    let log_line = format!("[{}] {}\n", timestamp, message);
+    log_at(LogLevel::Info, message);
+}
+
+pub fn log_at(level: LogLevel, message: &str) {
+    if level > LogLevel::from_u8(LOG_LEVEL.load(Ordering::Relaxed)) {
+        return;
+    }
+
+    let log_line = format_line(level, message);
     
     // Print to stdout (visible in dev console)
     print!("{}", log_line);
     
     // Write to log file
-    if let Some(ref mut file) = *LOG_FILE.lock().unwrap() {
-        let _ = file.write_all(log_line.as_bytes());
+    let mut log_file = LOG_FILE.lock().unwrap();
+    let log_path = LOG_PATH.lock().unwrap().clone();
+    write_line(
+        &mut log_file,
+        log_path.as_deref(),
+        &log_line,
+        MAX_LOG_BYTES.load(Ordering::Relaxed),
+        MAX_LOG_ARCHIVES.load(Ordering::Relaxed),
+    );
+}
+
+/// Append a line to the log, first rotating it (see `rotate`) if the line would take it
+/// past `max_bytes`
+fn write_line(
+    file: &mut Option<File>,
+    log_path: Option<&Path>,
+    line: &str,
+    max_bytes: u64,
+    max_archives: usize,
+) {
+    let needs_rotation = file.as_ref().is_some_and(|file| {
+        file.metadata()
+            .is_ok_and(|m| m.len() > 0 && m.len() + line.len() as u64 > max_bytes)
+    });
+    if let (true, Some(log_path)) = (needs_rotation, log_path) {
+        rotate(file, log_path, max_archives);
+    }
+    if let Some(file) = file {
+        let _ = file.write_all(line.as_bytes());
         let _ = file.flush();
     }
 }
 
+/// Log at `Info`; kept for existing call sites
 #[macro_export]
 macro_rules! app_log {
     ($($arg:tt)*) => {
         $crate::logger::log(&format!($($arg)*))
     };
 }
+
+#[macro_export]
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        $crate::logger::log_at($crate::logger::LogLevel::Error, &format!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        $crate::logger::log_at($crate::logger::LogLevel::Warn, &format!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        $crate::logger::log_at($crate::logger::LogLevel::Info, &format!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        $crate::logger::log_at($crate::logger::LogLevel::Debug, &format!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_rotates_past_the_size_limit() {
+        let dir = std::env::temp_dir().join(format!("deeprecall-log-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let log_path = dir.join("deeprecall.log");
+
+        let mut file = Some(OpenOptions::new().create(true).append(true).open(&log_path).unwrap());
+        for i in 0..40 {
+            let line = format_line(LogLevel::Info, &format!("line {} {}", i, "x".repeat(64)));
+            write_line(&mut file, Some(&log_path), &line, 1024, 3);
+        }
+
+        assert!(archive_path(&log_path, 1).exists(), "deeprecall.log.1 missing");
+        assert!(archive_path(&log_path, 3).exists());
+        assert!(!archive_path(&log_path, 4).exists(), "only 3 archives are kept");
+        assert!(std::fs::metadata(&log_path).unwrap().len() <= 1024);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
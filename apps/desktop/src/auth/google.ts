@@ -55,7 +55,7 @@ export async function signInWithGoogle(
   console.log("[Google OAuth] Generated PKCE challenge");
 
   // Step 2: Start loopback server
-  const server = await startLoopbackListener();
+  const server = await startLoopbackListener({ codeVerifier: pkce.verifier });
   console.log("[Google OAuth] Loopback server running on:", server.url);
 
   try {
@@ -90,30 +90,21 @@ export async function signInWithGoogle(
     console.log("[Google OAuth] Received authorization code");
 
     // Step 5: Exchange code with Google for tokens
-    const GOOGLE_CLIENT_SECRET =
-      import.meta.env.VITE_GOOGLE_DESKTOP_CLIENT_SECRET || "";
-    const tokenResponse = await fetch(GOOGLE_TOKEN_URL, {
-      method: "POST",
-      headers: {
-        "Content-Type": "application/x-www-form-urlencoded",
-      },
-      body: new URLSearchParams({
-        client_id: GOOGLE_CLIENT_ID,
-        client_secret: GOOGLE_CLIENT_SECRET, // Required by Google even for Desktop apps
-        code: code,
-        code_verifier: pkce.verifier,
-        redirect_uri: server.url,
-        grant_type: "authorization_code",
-      }),
-    });
-
-    if (!tokenResponse.ok) {
-      const error = await tokenResponse.text();
+    // (in Rust, which holds the PKCE verifier and client secret)
+    const { invoke } = await import("@tauri-apps/api/core");
+    let tokens: { id_token?: string; refresh_token?: string };
+    try {
+      tokens = await invoke("exchange_oauth_code", {
+        tokenEndpoint: GOOGLE_TOKEN_URL,
+        clientId: GOOGLE_CLIENT_ID,
+        code,
+        redirectUri: server.url,
+      });
+    } catch (error) {
       console.error("[Google OAuth] Token exchange failed:", error);
       throw new Error(`Failed to exchange code with Google: ${error}`);
     }
 
-    const tokens = await tokenResponse.json();
     console.log("[Google OAuth] Received tokens from Google");
 
     // Step 6: Exchange ID token with Auth Broker for app JWT
@@ -60,8 +60,21 @@ function base64URLEncode(buffer: ArrayBuffer): string {
 /**
  * Start a loopback HTTP server for OAuth callback
  * Returns the redirect URI that should be used in OAuth requests
+ *
+ * `preferredPort` is tried first (for providers with a fixed registered redirect URI);
+ * `host` selects the loopback interface ("127.0.0.1" by default, "localhost" or "::1").
+ * `codeVerifier` is handed to the Rust side for `exchange_oauth_code`.
+ * `successHtml`/`errorHtml` replace the built-in landing pages ({title}/{message} placeholders).
  */
-export async function startLoopbackListener(): Promise<{
+export async function startLoopbackListener(
+  options: {
+    preferredPort?: number;
+    host?: "127.0.0.1" | "localhost" | "::1";
+    codeVerifier?: string;
+    successHtml?: string;
+    errorHtml?: string;
+  } = {}
+): Promise<{
   url: string;
   port: number;
   waitForCode: () => Promise<{ code: string; state?: string }>;
@@ -71,8 +84,17 @@ export async function startLoopbackListener(): Promise<{
   const { listen } = await import("@tauri-apps/api/event");
 
   // Start the loopback server
-  const port = await invoke<number>("start_oauth_loopback");
-  const url = `http://127.0.0.1:${port}/oauth2/callback`;
+  const { host, port } = await invoke<{ host: string; port: number }>(
+    "start_oauth_loopback",
+    {
+      preferredPort: options.preferredPort,
+      host: options.host,
+      codeVerifier: options.codeVerifier,
+      successHtml: options.successHtml,
+      errorHtml: options.errorHtml,
+    }
+  );
+  const url = `http://${host}:${port}/oauth2/callback`;
 
   console.log("[OAuth] Started loopback server:", { port, url });
 
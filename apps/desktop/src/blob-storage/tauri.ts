@@ -13,6 +13,24 @@ import type {
 } from "@deeprecall/blob-storage";
 import { logger } from "@deeprecall/telemetry";
 
+/**
+ * Structured error returned by Rust commands ({ code, message })
+ */
+export interface CommandError {
+  code: string;
+  message: string;
+}
+
+/**
+ * Extract a readable message from a rejected invoke()
+ */
+export function commandErrorMessage(error: unknown): string {
+  if (error && typeof error === "object" && "message" in error) {
+    return String((error as CommandError).message);
+  }
+  return String(error);
+}
+
 /**
  * Tauri-specific BlobCAS implementation
  * Uses Rust backend for file operations
@@ -45,11 +63,19 @@ export class TauriBlobStorage implements BlobCAS {
   /**
    * List all blobs with metadata
    */
-  async list(opts?: { orphanedOnly?: boolean }): Promise<BlobWithMetadata[]> {
+  async list(opts?: {
+    orphanedOnly?: boolean;
+    mimePrefix?: string;
+  }): Promise<BlobWithMetadata[]> {
     try {
-      return await invoke<BlobWithMetadata[]>("list_blobs", {
-        orphanedOnly: opts?.orphanedOnly || false,
-      });
+      const page = await invoke<{ blobs: BlobWithMetadata[]; total: number }>(
+        "list_blobs",
+        {
+          orphanedOnly: opts?.orphanedOnly || false,
+          mimePrefix: opts?.mimePrefix,
+        }
+      );
+      return page.blobs;
     } catch (error) {
       logger.error("cas", "Error listing blobs", {
         orphanedOnly: opts?.orphanedOnly,
@@ -95,7 +121,7 @@ export class TauriBlobStorage implements BlobCAS {
       return result;
     } catch (error) {
       logger.error("cas", "Error storing blob", { error });
-      throw new Error(`Failed to store blob: ${error}`);
+      throw new Error(`Failed to store blob: ${commandErrorMessage(error)}`);
     }
   }
 
@@ -138,7 +164,7 @@ export class TauriBlobStorage implements BlobCAS {
       await invoke<void>("delete_blob", { sha256 });
     } catch (error) {
       logger.error("cas", "Error deleting blob", { sha256, error });
-      throw new Error(`Failed to delete blob: ${error}`);
+      throw new Error(`Failed to delete blob: ${commandErrorMessage(error)}`);
     }
   }
 
@@ -150,7 +176,7 @@ export class TauriBlobStorage implements BlobCAS {
       await invoke<void>("rename_blob", { sha256, filename });
     } catch (error) {
       logger.error("cas", "Error renaming blob", { sha256, filename, error });
-      throw new Error(`Failed to rename blob: ${error}`);
+      throw new Error(`Failed to rename blob: ${commandErrorMessage(error)}`);
     }
   }
 
@@ -162,7 +188,7 @@ export class TauriBlobStorage implements BlobCAS {
       return await invoke<ScanResult>("scan_blobs");
     } catch (error) {
       logger.error("cas", "Error scanning blobs", { error });
-      throw new Error(`Failed to scan blobs: ${error}`);
+      throw new Error(`Failed to scan blobs: ${commandErrorMessage(error)}`);
     }
   }
 
@@ -174,7 +200,9 @@ export class TauriBlobStorage implements BlobCAS {
       return await invoke<HealthReport>("health_check");
     } catch (error) {
       logger.error("cas", "Error checking blob health", { error });
-      throw new Error(`Failed to check blob health: ${error}`);
+      throw new Error(
+        `Failed to check blob health: ${commandErrorMessage(error)}`
+      );
     }
   }
 
@@ -190,7 +218,9 @@ export class TauriBlobStorage implements BlobCAS {
       return await invoke("get_blob_stats");
     } catch (error) {
       logger.error("cas", "Error getting blob stats", { error });
-      throw new Error(`Failed to get blob stats: ${error}`);
+      throw new Error(
+        `Failed to get blob stats: ${commandErrorMessage(error)}`
+      );
     }
   }
 }
@@ -23,8 +23,9 @@ export function PostgresIndicator() {
 
       try {
         // Try a simple query to check connection (works is required table)
-        const result = await invoke<any[]>("query_postgres_table", {
+        await invoke<{ rows: any[]; total: number }>("query_postgres_table", {
           table: "works", // Changed from tableName to table
+          limit: 1,
         });
 
         // If we got here without error, connection is good
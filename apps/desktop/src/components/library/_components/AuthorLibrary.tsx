@@ -18,6 +18,19 @@ import { useReaderUI } from "@deeprecall/data/stores/reader-ui";
 import { convertFileSrc } from "@tauri-apps/api/core";
 import { invoke } from "@tauri-apps/api/core";
 
+const SHA256_PATTERN = /^[0-9a-f]{64}$/;
+
+/**
+ * Resolve an avatar blob hash to an asset URL (legacy values are used as-is)
+ */
+async function resolveAvatarUrl(reference: string): Promise<string> {
+  if (!SHA256_PATTERN.test(reference)) {
+    return reference;
+  }
+  const path = await invoke<string>("resolve_blob_path", { sha256: reference });
+  return convertFileSrc(path);
+}
+
 interface AuthorLibraryProps {
   isOpen: boolean;
   onClose: () => void;
@@ -40,8 +53,9 @@ export function AuthorLibrary({ isOpen, onClose }: AuthorLibraryProps) {
       const originalBase64 = await blobToBase64(originalBlob);
       const displayBase64 = await blobToBase64(displayBlob);
 
+      // Avatars are stored as blobs; the author keeps their hashes
       const result = await invoke<{
-        paths: { original: string; display: string };
+        sha256: { original: string; display: string };
         cropRegion: { x: number; y: number; size: number };
       }>("upload_avatar", {
         authorId,
@@ -50,13 +64,16 @@ export function AuthorLibrary({ isOpen, onClose }: AuthorLibraryProps) {
         cropRegion: JSON.stringify(cropRegion),
       });
 
-      return result;
+      return { paths: result.sha256, cropRegion: result.cropRegion };
     },
 
-    deleteAvatar: async (path) => {
-      await invoke("delete_avatar", { path });
+    // Drops the avatar blob unless another record still references it
+    deleteAvatar: async (reference, authorId) => {
+      await invoke("delete_avatar", { authorId, reference });
     },
 
+    resolveAvatarUrl,
+
     // Blob URLs (Tauri asset protocol)
     getBlobUrl: (sha256: string) =>
       convertFileSrc(
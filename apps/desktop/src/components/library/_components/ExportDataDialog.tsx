@@ -9,7 +9,6 @@ import {
 } from "@deeprecall/ui";
 import { invoke } from "@tauri-apps/api/core";
 import { save } from "@tauri-apps/plugin-dialog";
-import { writeTextFile } from "@tauri-apps/plugin-fs";
 
 interface ExportDataDialogProps {
   isOpen: boolean;
@@ -19,9 +18,6 @@ interface ExportDataDialogProps {
 // Tauri implementation of export operations
 const exportOps: ExportOperations = {
   exportData: async (_options: any) => {
-    // Get data from Tauri command
-    const data = await invoke<string>("export_all_data");
-
     // Show save dialog
     const filePath = await save({
       filters: [
@@ -35,17 +31,21 @@ const exportOps: ExportOperations = {
 
     if (!filePath) return; // User cancelled
 
-    // Write file
-    await writeTextFile(filePath, data);
+    // Stream the export straight to the chosen file
+    await invoke("export_all_data", { destPath: filePath });
   },
 
   estimateExportSize: async (_options: any) => {
-    const size = await invoke<number>("estimate_export_size");
+    const estimate = await invoke<{
+      database_bytes: number;
+      blob_bytes: number;
+      total_bytes: number;
+    }>("estimate_export_size");
     return {
-      dexie: size,
+      dexie: estimate.database_bytes,
       sqlite: 0,
-      files: 0,
-      total: size,
+      files: estimate.blob_bytes,
+      total: estimate.total_bytes,
     };
   },
 
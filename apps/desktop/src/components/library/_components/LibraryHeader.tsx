@@ -9,6 +9,7 @@ import {
 } from "@deeprecall/ui";
 import { useBlobStats } from "@deeprecall/data/hooks";
 import { useTauriBlobStorage } from "@/hooks/useBlobStorage";
+import { commandErrorMessage } from "@/blob-storage/tauri";
 import { useQueryClient } from "@tanstack/react-query";
 import { invoke } from "@tauri-apps/api/core";
 import { logger } from "@deeprecall/telemetry";
@@ -119,7 +120,7 @@ export function LibraryHeader({
         alert("✅ Database and blobs cleared successfully!");
       } catch (error) {
         logger.error("cas", "Failed to clear database", { error });
-        alert(`❌ Failed to clear database: ${error}`);
+        alert(`❌ Failed to clear database: ${commandErrorMessage(error)}`);
       }
     }
   };
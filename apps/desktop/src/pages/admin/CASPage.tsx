@@ -143,7 +143,10 @@ export default function CASPage() {
       setIsSyncing(true);
       try {
         // Get all local blobs from Tauri CAS
-        const localBlobs = await invoke<any[]>("list_blobs", {
+        const { blobs: localBlobs } = await invoke<{
+          blobs: any[];
+          total: number;
+        }>("list_blobs", {
           orphanedOnly: false,
         });
 
@@ -4,8 +4,51 @@
  * Displays an author's avatar with fallback to initials
  */
 
+import { createContext, useContext, useEffect, useState } from "react";
 import type { Author } from "@deeprecall/core";
 
+/**
+ * Turns a stored avatar reference (e.g. a blob hash) into a displayable URL.
+ * Platforms that store avatars by reference provide one; without it the
+ * reference is used as the URL directly.
+ */
+export type AvatarUrlResolver = (reference: string) => Promise<string>;
+
+export const AvatarUrlResolverContext = createContext<
+  AvatarUrlResolver | undefined
+>(undefined);
+
+/**
+ * Resolve an avatar reference to a URL using the surrounding resolver
+ */
+export function useAvatarUrl(reference?: string): string | undefined {
+  const resolve = useContext(AvatarUrlResolverContext);
+  const [url, setUrl] = useState<string | undefined>(
+    resolve ? undefined : reference
+  );
+
+  useEffect(() => {
+    if (!reference || !resolve) {
+      setUrl(reference);
+      return;
+    }
+
+    let cancelled = false;
+    resolve(reference)
+      .then((resolved) => {
+        if (!cancelled) setUrl(resolved);
+      })
+      .catch(() => {
+        if (!cancelled) setUrl(undefined);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [reference, resolve]);
+
+  return url;
+}
+
 interface AuthorAvatarProps {
   author: Author;
   size?: "small" | "medium" | "large";
@@ -26,14 +69,15 @@ export function AuthorAvatar({
   };
 
   const initials = `${author.firstName[0]}${author.lastName[0]}`.toUpperCase();
+  const avatarUrl = useAvatarUrl(author.avatarDisplayPath);
 
-  if (author.avatarDisplayPath) {
+  if (avatarUrl) {
     return (
       <div
         className={`${sizeClasses[size]} rounded-full bg-neutral-800 border border-neutral-700 overflow-hidden shrink-0 ${className}`}
       >
         <img
-          src={author.avatarDisplayPath}
+          src={avatarUrl}
           alt={getAuthorFullName(author)}
           className="w-full h-full object-cover"
         />
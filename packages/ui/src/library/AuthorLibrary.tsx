@@ -32,6 +32,8 @@ import {
 } from "@deeprecall/data";
 import { formatWorkStats, parseAuthorList } from "../utils";
 import { AuthorListView } from "./AuthorListView";
+import { AvatarUrlResolverContext } from "./AuthorAvatar";
+import type { AvatarUrlResolver } from "./AuthorAvatar";
 import { AuthorEditView } from "./AuthorEditView";
 import { AuthorCreateView } from "./AuthorCreateView";
 import { AuthorImportView } from "./AuthorImportView";
@@ -64,7 +66,9 @@ export interface AuthorLibraryPlatformOps {
     paths: { original: string; display: string };
     cropRegion: CropRegion;
   }>;
-  deleteAvatar: (path: string) => Promise<void>;
+  deleteAvatar: (reference: string, authorId?: string) => Promise<void>;
+  // Optional: resolve stored avatar references (e.g. blob hashes) to URLs
+  resolveAvatarUrl?: AvatarUrlResolver;
 
   // Blob URLs (platform storage)
   getBlobUrl: (sha256: string) => string;
@@ -209,165 +213,167 @@ export function AuthorLibrary({
   if (!isOpen) return null;
 
   return (
-    <div className="fixed inset-0 z-50 flex items-center justify-center p-4 bg-black/60 backdrop-blur-sm">
-      <div className="bg-neutral-900 rounded-xl shadow-2xl w-[80vw] h-[80vh] flex flex-col border border-neutral-800">
-        {/* Header */}
-        <div className="shrink-0 px-6 py-4 border-b border-neutral-800">
-          <div className="flex items-center justify-between">
-            <div className="flex items-center gap-3">
-              <div className="p-2 bg-blue-600/20 rounded-lg">
-                <User className="w-5 h-5 text-blue-400" />
-              </div>
-              <div>
-                <h2 className="text-xl font-bold text-neutral-100">
-                  Author Library
-                </h2>
-                <p className="text-sm text-neutral-400 mt-0.5">
-                  {view === "list" && `${displayAuthors.length} authors`}
-                  {view === "edit" && "Edit author"}
-                  {view === "create" && "Create new author"}
-                  {view === "import" && "Import from BibTeX"}
-                  {view === "avatar" && "Edit avatar"}
-                </p>
+    <AvatarUrlResolverContext.Provider value={platformOps.resolveAvatarUrl}>
+      <div className="fixed inset-0 z-50 flex items-center justify-center p-4 bg-black/60 backdrop-blur-sm">
+        <div className="bg-neutral-900 rounded-xl shadow-2xl w-[80vw] h-[80vh] flex flex-col border border-neutral-800">
+          {/* Header */}
+          <div className="shrink-0 px-6 py-4 border-b border-neutral-800">
+            <div className="flex items-center justify-between">
+              <div className="flex items-center gap-3">
+                <div className="p-2 bg-blue-600/20 rounded-lg">
+                  <User className="w-5 h-5 text-blue-400" />
+                </div>
+                <div>
+                  <h2 className="text-xl font-bold text-neutral-100">
+                    Author Library
+                  </h2>
+                  <p className="text-sm text-neutral-400 mt-0.5">
+                    {view === "list" && `${displayAuthors.length} authors`}
+                    {view === "edit" && "Edit author"}
+                    {view === "create" && "Create new author"}
+                    {view === "import" && "Import from BibTeX"}
+                    {view === "avatar" && "Edit avatar"}
+                  </p>
+                </div>
               </div>
+              <button
+                onClick={handleClose}
+                className="p-2 text-neutral-400 hover:text-neutral-200 hover:bg-neutral-800 rounded-lg transition-colors"
+              >
+                <X className="w-5 h-5" />
+              </button>
             </div>
-            <button
-              onClick={handleClose}
-              className="p-2 text-neutral-400 hover:text-neutral-200 hover:bg-neutral-800 rounded-lg transition-colors"
-            >
-              <X className="w-5 h-5" />
-            </button>
           </div>
-        </div>
 
-        {/* Content */}
-        <div className="flex-1 overflow-hidden">
-          {view === "list" && (
-            <AuthorListView
-              authors={displayAuthors}
-              searchQuery={searchQuery}
-              onSearchChange={setSearchQuery}
-              sortBy={sortBy}
-              onSortChange={setSortBy}
-              displayMode={displayMode}
-              onDisplayModeChange={setDisplayMode}
-              onSelectAuthor={handleSelectAuthor}
-              onCreateNew={handleCreateNew}
-              onImportBibtex={handleImportBibtex}
-              works={works}
-              getAuthorFullName={getAuthorFullName}
-              formatWorkStats={formatWorkStats}
-            />
-          )}
-
-          {view === "edit" && selectedAuthor && (
-            <AuthorEditView
-              author={selectedAuthor}
-              onBack={handleBackToList}
-              onUpdate={async ({ id, updates }) => {
-                await updateAuthorMutation.mutateAsync({ id, updates });
-              }}
-              onDelete={async (id) => {
-                await deleteAuthorMutation.mutateAsync(id);
-              }}
-              onEditAvatar={handleEditAvatar}
-              onDroppedFile={(file) => {
-                setDroppedAvatarFile(file);
-                setView("avatar");
-              }}
-              works={works}
-              presets={presets}
-              onViewWork={setViewingWork}
-              onContextMenu={(e, sha256, title) => {
-                setContextMenu({
-                  x: e.clientX,
-                  y: e.clientY,
-                  sha256,
-                  title,
-                });
-              }}
-              getAuthorFullName={getAuthorFullName}
-            />
-          )}
-
-          {view === "create" && (
-            <AuthorCreateView
-              onBack={handleBackToList}
-              onCreate={async (data) => {
-                return await createAuthorMutation.mutateAsync(
-                  data as Omit<Author, "id" | "createdAt" | "updatedAt">
-                );
-              }}
-            />
-          )}
-
-          {view === "import" && (
-            <AuthorImportView
-              bibtexInput={bibtexInput}
-              onBibtexChange={setBibtexInput}
-              onBack={handleBackToList}
-              onImport={findOrCreateAuthor}
-              parseAuthorList={parseAuthorList}
-              getAuthorFullName={getAuthorFullName}
-            />
-          )}
-
-          {view === "avatar" && selectedAuthor && (
-            <AvatarEditView
-              author={selectedAuthor}
-              onBack={() => {
-                setDroppedAvatarFile(null);
-                setView("edit");
-              }}
-              onUpdate={async ({ id, updates }) => {
-                await updateAuthorMutation.mutateAsync({ id, updates });
-              }}
-              droppedFile={droppedAvatarFile}
-              uploadAvatar={platformOps.uploadAvatar}
-              deleteAvatar={platformOps.deleteAvatar}
-              ImageCropper={ImageCropper}
-            />
-          )}
+          {/* Content */}
+          <div className="flex-1 overflow-hidden">
+            {view === "list" && (
+              <AuthorListView
+                authors={displayAuthors}
+                searchQuery={searchQuery}
+                onSearchChange={setSearchQuery}
+                sortBy={sortBy}
+                onSortChange={setSortBy}
+                displayMode={displayMode}
+                onDisplayModeChange={setDisplayMode}
+                onSelectAuthor={handleSelectAuthor}
+                onCreateNew={handleCreateNew}
+                onImportBibtex={handleImportBibtex}
+                works={works}
+                getAuthorFullName={getAuthorFullName}
+                formatWorkStats={formatWorkStats}
+              />
+            )}
+
+            {view === "edit" && selectedAuthor && (
+              <AuthorEditView
+                author={selectedAuthor}
+                onBack={handleBackToList}
+                onUpdate={async ({ id, updates }) => {
+                  await updateAuthorMutation.mutateAsync({ id, updates });
+                }}
+                onDelete={async (id) => {
+                  await deleteAuthorMutation.mutateAsync(id);
+                }}
+                onEditAvatar={handleEditAvatar}
+                onDroppedFile={(file) => {
+                  setDroppedAvatarFile(file);
+                  setView("avatar");
+                }}
+                works={works}
+                presets={presets}
+                onViewWork={setViewingWork}
+                onContextMenu={(e, sha256, title) => {
+                  setContextMenu({
+                    x: e.clientX,
+                    y: e.clientY,
+                    sha256,
+                    title,
+                  });
+                }}
+                getAuthorFullName={getAuthorFullName}
+              />
+            )}
+
+            {view === "create" && (
+              <AuthorCreateView
+                onBack={handleBackToList}
+                onCreate={async (data) => {
+                  return await createAuthorMutation.mutateAsync(
+                    data as Omit<Author, "id" | "createdAt" | "updatedAt">
+                  );
+                }}
+              />
+            )}
+
+            {view === "import" && (
+              <AuthorImportView
+                bibtexInput={bibtexInput}
+                onBibtexChange={setBibtexInput}
+                onBack={handleBackToList}
+                onImport={findOrCreateAuthor}
+                parseAuthorList={parseAuthorList}
+                getAuthorFullName={getAuthorFullName}
+              />
+            )}
+
+            {view === "avatar" && selectedAuthor && (
+              <AvatarEditView
+                author={selectedAuthor}
+                onBack={() => {
+                  setDroppedAvatarFile(null);
+                  setView("edit");
+                }}
+                onUpdate={async ({ id, updates }) => {
+                  await updateAuthorMutation.mutateAsync({ id, updates });
+                }}
+                droppedFile={droppedAvatarFile}
+                uploadAvatar={platformOps.uploadAvatar}
+                deleteAvatar={platformOps.deleteAvatar}
+                ImageCropper={ImageCropper}
+              />
+            )}
+          </div>
         </div>
-      </div>
 
-      {/* Simple PDF Viewer for quick preview */}
-      {viewingWork && (
-        <SimplePDFViewer
-          sha256={viewingWork.sha256}
-          title={viewingWork.title}
-          onClose={() => setViewingWork(null)}
-          getBlobUrl={platformOps.getBlobUrl}
-        />
-      )}
-
-      {/* Context Menu */}
-      {contextMenu && (
-        <>
-          <div
-            className="fixed inset-0 z-9998"
-            onClick={() => setContextMenu(null)}
+        {/* Simple PDF Viewer for quick preview */}
+        {viewingWork && (
+          <SimplePDFViewer
+            sha256={viewingWork.sha256}
+            title={viewingWork.title}
+            onClose={() => setViewingWork(null)}
+            getBlobUrl={platformOps.getBlobUrl}
           />
-          <div
-            className="fixed z-9999 bg-neutral-800 border border-neutral-700 rounded-lg shadow-xl py-1 min-w-[180px]"
-            style={{ left: contextMenu.x, top: contextMenu.y }}
-          >
-            <button
-              onClick={() => {
-                platformOps.openWorkInReader(
-                  contextMenu.sha256,
-                  contextMenu.title
-                );
-                setContextMenu(null);
-              }}
-              className="w-full px-4 py-2 text-left text-sm text-neutral-200 hover:bg-neutral-700 transition-colors flex items-center gap-2"
+        )}
+
+        {/* Context Menu */}
+        {contextMenu && (
+          <>
+            <div
+              className="fixed inset-0 z-9998"
+              onClick={() => setContextMenu(null)}
+            />
+            <div
+              className="fixed z-9999 bg-neutral-800 border border-neutral-700 rounded-lg shadow-xl py-1 min-w-[180px]"
+              style={{ left: contextMenu.x, top: contextMenu.y }}
             >
-              <ExternalLink className="w-4 h-4" />
-              Open in Reader
-            </button>
-          </div>
-        </>
-      )}
-    </div>
+              <button
+                onClick={() => {
+                  platformOps.openWorkInReader(
+                    contextMenu.sha256,
+                    contextMenu.title
+                  );
+                  setContextMenu(null);
+                }}
+                className="w-full px-4 py-2 text-left text-sm text-neutral-200 hover:bg-neutral-700 transition-colors flex items-center gap-2"
+              >
+                <ExternalLink className="w-4 h-4" />
+                Open in Reader
+              </button>
+            </div>
+          </>
+        )}
+      </div>
+    </AvatarUrlResolverContext.Provider>
   );
 }
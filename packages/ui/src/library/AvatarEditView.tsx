@@ -7,6 +7,7 @@
 import type { Author, CropRegion } from "@deeprecall/core";
 import { Trash2 } from "lucide-react";
 import { logger } from "@deeprecall/telemetry";
+import { useAvatarUrl } from "./AuthorAvatar";
 
 interface AvatarEditViewProps {
   author: Author;
@@ -22,7 +23,7 @@ interface AvatarEditViewProps {
     paths: { original: string; display: string };
     cropRegion: CropRegion;
   }>;
-  deleteAvatar: (path: string) => Promise<void>;
+  deleteAvatar: (reference: string, authorId?: string) => Promise<void>;
   ImageCropper: React.ComponentType<{
     initialImageUrl?: string;
     initialCropRegion?: CropRegion;
@@ -45,6 +46,27 @@ export function AvatarEditView({
   deleteAvatar,
   ImageCropper,
 }: AvatarEditViewProps) {
+  const originalUrl = useAvatarUrl(author.avatarOriginalPath);
+
+  /**
+   * Release avatars the author no longer points at. Runs after the author
+   * update so a failed cleanup never leaves the author without an avatar.
+   */
+  const releaseAvatars = async (references: (string | undefined)[]) => {
+    for (const reference of references) {
+      if (!reference) continue;
+      try {
+        await deleteAvatar(reference, author.id);
+      } catch (error) {
+        logger.warn("ui", "Failed to delete superseded avatar", {
+          error,
+          authorId: author.id,
+          reference,
+        });
+      }
+    }
+  };
+
   const handleSave = async (data: {
     originalBlob: Blob;
     displayBlob: Blob;
@@ -57,14 +79,6 @@ export function AvatarEditView({
         ...data,
       });
 
-      // Delete old avatar if exists
-      if (author.avatarOriginalPath) {
-        await deleteAvatar(author.avatarOriginalPath);
-      }
-      if (author.avatarDisplayPath) {
-        await deleteAvatar(author.avatarDisplayPath);
-      }
-
       // Update author
       await onUpdate({
         id: author.id,
@@ -75,6 +89,14 @@ export function AvatarEditView({
         },
       });
 
+      // Delete old avatar if replaced (identical uploads reuse the same blobs)
+      const current = [result.paths.original, result.paths.display];
+      await releaseAvatars(
+        [author.avatarOriginalPath, author.avatarDisplayPath].filter(
+          (reference) => !current.includes(reference ?? "")
+        )
+      );
+
       alert("Avatar updated successfully!");
       onBack();
     } catch (error) {
@@ -92,14 +114,6 @@ export function AvatarEditView({
     }
 
     try {
-      // Delete avatar files
-      if (author.avatarOriginalPath) {
-        await deleteAvatar(author.avatarOriginalPath);
-      }
-      if (author.avatarDisplayPath) {
-        await deleteAvatar(author.avatarDisplayPath);
-      }
-
       // Update author to remove avatar paths
       await onUpdate({
         id: author.id,
@@ -110,6 +124,12 @@ export function AvatarEditView({
         },
       });
 
+      // Delete avatar files
+      await releaseAvatars([
+        author.avatarOriginalPath,
+        author.avatarDisplayPath,
+      ]);
+
       alert("Avatar removed successfully!");
       onBack();
     } catch (error) {
@@ -155,7 +175,9 @@ export function AvatarEditView({
       )}
       <div className="flex-1">
         <ImageCropper
-          initialImageUrl={author.avatarOriginalPath}
+          // Remount once the stored avatar has been resolved
+          key={originalUrl ?? "new"}
+          initialImageUrl={originalUrl}
           initialCropRegion={author.avatarCropRegion}
           initialFile={droppedFile || undefined}
           onSave={handleSave}